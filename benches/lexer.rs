@@ -0,0 +1,56 @@
+//! Measures how quickly the parser gets through a large
+//! script.
+//!
+//! Run with ``cargo bench``. The script mixes the kinds
+//! of tokens found in real programs: numbers, strings,
+//! lists, units, names, and definitions.
+
+use cattywampus::parser;
+use std::{hint::black_box, time::Instant};
+
+const LINES: usize = 20_000;
+const ROUNDS: usize = 10;
+
+fn script() -> String {
+    let lines = [
+        ":: area ( w:Float64 h:Float64 -- a:Float64 ) w h * ;",
+        "3.5 4.25 area 2 * 1.5e3 + total!",
+        "{1,2.5,\"label\",'sym} #{'x:1,'y:2} [1,2;3,4] det",
+        "9.81 m/s^2 12.0 kg * >N \\ force",
+        "-42 17 + 0.125f sqrt true \"text\" 'done",
+        "[ dup * ] call 2.0 sin cos tan ln exp",
+    ];
+
+    lines
+        .iter()
+        .cycle()
+        .take(LINES)
+        .fold(String::new(), |mut acc, line| {
+            acc += line;
+            acc += "\n";
+            acc
+        })
+}
+
+fn main() {
+    let source = script();
+    let mut tokens = 0;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for line in source.lines() {
+            tokens += black_box(parser::parse_line(line)).count();
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let megabytes = (source.len() * ROUNDS) as f64 / 1e6;
+    println!(
+        "parsed {} tokens ({:.1} MB) in {:.3} s: {:.1} MB/s, {:.2} M tokens/s",
+        tokens,
+        megabytes,
+        elapsed,
+        megabytes / elapsed,
+        tokens as f64 / elapsed / 1e6
+    );
+}
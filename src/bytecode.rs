@@ -0,0 +1,133 @@
+//! Compiled code
+//!
+//! Before a word or quotation runs, its ``Node``s are
+//! compiled to a flat list of instructions, so that
+//! names are resolved and literals are taken apart only
+//! once, rather than every time the code runs. The
+//! ``Interpreter`` runs the instructions.
+
+use super::{
+    functions::{AngleMode, BuiltinFun},
+    numerical::Method,
+    units::Unit,
+    value::Value,
+    words::{Definition, Node},
+};
+use std::{cell::RefCell, fmt, rc::Rc};
+
+/// A compiled block of code
+pub type Code = Rc<[Instr]>;
+
+/// A single step of compiled code
+#[derive(Debug, PartialEq)]
+pub enum Instr {
+    /// Push a copy of a value.
+    Push(Value),
+
+    /// Apply a builtin, checking its arguments.
+    Apply(&'static BuiltinFun),
+
+    /// Apply a builtin whose arguments are known to have
+    /// the right types, without checking them.
+    ApplyUnchecked(&'static BuiltinFun),
+
+    /// Attach a unit to the value on top of the stack.
+    AttachUnit(Unit),
+
+    /// Convert the quantity on top of the stack.
+    Convert(Unit),
+
+    /// Pop a value into a variable.
+    Store(String),
+
+    /// Push the value of a variable.
+    Recall(String),
+
+    /// Push the value of a local, by its place in the
+    /// frame.
+    Local(usize),
+
+    /// Push a quotation, with its source and its
+    /// compiled body.
+    Quote(Rc<Vec<Node>>, Code),
+
+    /// Run the quotation on top of the stack.
+    Call,
+
+    /// Run a user-defined word.
+    Invoke(WordRef),
+
+    /// Change the unit that angles are measured in.
+    SetAngleMode(AngleMode),
+
+    /// Run a numerical method on the quotation and
+    /// numbers on top of the stack.
+    Numerical(Method),
+
+    /// Pop the tolerance of the numerical methods.
+    SetTolerance,
+
+    /// Pop the iteration limit of the numerical
+    /// methods.
+    SetIterations,
+
+    /// Run whatever a name refers to when the
+    /// instruction runs, for names that were not
+    /// defined when the code was compiled.
+    Lookup(String),
+}
+
+/// A user-defined word, as it is run
+pub struct Word {
+    pub def: Rc<Definition>,
+
+    /// The vocabulary that names in the word are looked
+    /// up in
+    pub vocabulary: Rc<str>,
+
+    pub code: Code,
+}
+
+/// A shared reference to a user-defined word
+///
+/// The dictionary and all compiled code that calls a
+/// word share one ``Word``, so when the word is
+/// redefined, the code that calls it runs the new
+/// definition.
+#[derive(Clone)]
+pub struct WordRef(Rc<RefCell<Word>>);
+
+impl WordRef {
+    pub fn new(word: Word) -> WordRef {
+        WordRef(Rc::new(RefCell::new(word)))
+    }
+
+    /// Return the word as it is currently defined.
+    pub fn get(&self) -> (Rc<Definition>, Rc<str>, Code) {
+        let word = self.0.borrow();
+        (
+            Rc::clone(&word.def),
+            Rc::clone(&word.vocabulary),
+            Rc::clone(&word.code),
+        )
+    }
+
+    /// Replace the word with a new definition.
+    pub fn set(&self, word: Word) {
+        *self.0.borrow_mut() = word;
+    }
+}
+
+// Words can call themselves, so only the names are
+// shown.
+impl fmt::Debug for WordRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WordRef({})", self.0.borrow().def.name)
+    }
+}
+
+impl PartialEq for WordRef {
+    fn eq(&self, other: &WordRef) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
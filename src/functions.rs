@@ -4,10 +4,19 @@
 //! implementations, but could eventually support user-
 //! defined lambda closures.
 
-use super::value::{Type, Value};
+use super::{
+    random::Generator,
+    stack::Stack,
+    typecheck::{self, TypeError},
+    value::{Type, Value},
+};
 use lazy_static::lazy_static;
 use std::{collections::HashMap, fmt};
 
+/// The name of the vocabulary that holds the builtin
+/// functions, which is searched after every other.
+pub const BUILTINS: &str = "builtins";
+
 lazy_static! {
     /// The intrinsic built-in functions supported in the calculator.
     pub static ref INTRINSIC_FNS: HashMap<&'static str, BuiltinFun> = {
@@ -16,33 +25,181 @@ lazy_static! {
 
         let mut fns = HashMap::new();
 
+        // Arithmetic
+        fns.insert("+", BuiltinFun::new("+", (&[Number, Number], &[Number]), add_impl));
+        fns.insert("-", BuiltinFun::new("-", (&[Number, Number], &[Number]), sub_impl));
+        fns.insert("*", BuiltinFun::new("*", (&[Number, Number], &[Number]), mul_impl));
+        fns.insert("/", BuiltinFun::new("/", (&[Number, Number], &[Number]), div_impl));
+        fns.insert("negate", BuiltinFun::new("negate", (&[Number], &[Number]), negate_impl));
+
         // Integer operations
-        fns.insert("inc", BuiltinFun::new("inc", (&[Int32], &[Int32]), inc_impl));
-        fns.insert("dec", BuiltinFun::new("dec", (&[Int32], &[Int32]), dec_impl));
+        fns.insert("inc", BuiltinFun::new("inc", (&[Integer], &[Integer]), inc_impl));
+        fns.insert("dec", BuiltinFun::new("dec", (&[Integer], &[Integer]), dec_impl));
+
+        // Number theory
+        fns.insert("gcd",        BuiltinFun::new("gcd",        (&[Integer, Integer], &[Integer]), gcd_impl));
+        fns.insert("lcm",        BuiltinFun::new("lcm",        (&[Integer, Integer], &[Integer]), lcm_impl));
+        fns.insert("prime?",     BuiltinFun::new("prime?",     (&[Integer], &[Bool]), is_prime_impl));
+        fns.insert("next-prime", BuiltinFun::new("next-prime", (&[Integer], &[Integer]), next_prime_impl));
+        fns.insert("factor",     BuiltinFun::new("factor",     (&[Integer], &[List]), factor_impl));
+        fns.insert("totient",    BuiltinFun::new("totient",    (&[Integer], &[Integer]), totient_impl));
+        fns.insert("modpow",     BuiltinFun::new("modpow",     (&[Integer, Integer, Integer], &[Integer]), modpow_impl));
+        fns.insert("modinv",     BuiltinFun::new("modinv",     (&[Integer, Integer], &[Integer]), modinv_impl));
+        fns.insert("isqrt",      BuiltinFun::new("isqrt",      (&[Integer], &[Integer]), isqrt_impl));
+        fns.insert("divmod",     BuiltinFun::new("divmod",     (&[Integer, Integer], &[Integer, Integer]), divmod_impl));
+        fns.insert("binomial",   BuiltinFun::new("binomial",   (&[Integer, Integer], &[Integer]), binomial_impl));
+        fns.insert("factorial",  BuiltinFun::new("factorial",  (&[Number], &[Number]), factorial_impl));
+
+        // Bitwise
+        fns.insert("band",     BuiltinFun::new("band",     (&[Integer, Integer], &[Integer]), band_impl));
+        fns.insert("bor",      BuiltinFun::new("bor",      (&[Integer, Integer], &[Integer]), bor_impl));
+        fns.insert("bxor",     BuiltinFun::new("bxor",     (&[Integer, Integer], &[Integer]), bxor_impl));
+        fns.insert("bnot",     BuiltinFun::new("bnot",     (&[Integer], &[Integer]), bnot_impl));
+        fns.insert("shl",      BuiltinFun::new("shl",      (&[Integer, Integer], &[Integer]), shl_impl));
+        fns.insert("shr",      BuiltinFun::new("shr",      (&[Integer, Integer], &[Integer]), shr_impl));
+        fns.insert("rotl",     BuiltinFun::new("rotl",     (&[Integer, Integer], &[Integer]), rotl_impl));
+        fns.insert("rotr",     BuiltinFun::new("rotr",     (&[Integer, Integer], &[Integer]), rotr_impl));
+        fns.insert("popcount", BuiltinFun::new("popcount", (&[Integer], &[Integer]), popcount_impl));
+        fns.insert("clz",      BuiltinFun::new("clz",      (&[Integer], &[Integer]), clz_impl));
+        fns.insert("ctz",      BuiltinFun::new("ctz",      (&[Integer], &[Integer]), ctz_impl));
 
         // Algebraic
         fns.insert("recip", BuiltinFun::new("recip", (&[Fractional], &[Fractional]), recip_impl));
         fns.insert("sqrt", BuiltinFun::new("sqrt", (&[Fractional], &[Fractional]), sqrt_impl));
         fns.insert("cbrt", BuiltinFun::new("cbrt", (&[Fractional], &[Fractional]), cbrt_impl));
+        fns.insert("hypot", BuiltinFun::new("hypot", (&[Number, Number], &[Number]), hypot_impl));
+
+        // Signs & rounding
+        fns.insert("abs",    BuiltinFun::new("abs",    (&[Number], &[Number]), abs_impl));
+        fns.insert("signum", BuiltinFun::new("signum", (&[Number], &[Number]), signum_impl));
+        fns.insert("floor",  BuiltinFun::new("floor",  (&[Number], &[Number]), floor_impl));
+        fns.insert("ceil",   BuiltinFun::new("ceil",   (&[Number], &[Number]), ceil_impl));
+        fns.insert("round",  BuiltinFun::new("round",  (&[Number], &[Number]), round_impl));
+        fns.insert("trunc",  BuiltinFun::new("trunc",  (&[Number], &[Number]), trunc_impl));
+        fns.insert("fract",  BuiltinFun::new("fract",  (&[Number], &[Number]), fract_impl));
+
+        // Comparison
+        fns.insert("min",   BuiltinFun::new("min",   (&[Number, Number], &[Number]), min_impl).or(BuiltinFun::new("min", (&[List], &[Number]), minimum_impl)));
+        fns.insert("max",   BuiltinFun::new("max",   (&[Number, Number], &[Number]), max_impl).or(BuiltinFun::new("max", (&[List], &[Number]), maximum_impl)));
+        fns.insert("clamp", BuiltinFun::new("clamp", (&[Number, Number, Number], &[Number]), clamp_impl));
 
         // Exponential & Logarithmic
         fns.insert("exp", BuiltinFun::new("exp", (&[Fractional], &[Fractional]), exp_impl));
         fns.insert("ln",  BuiltinFun::new("ln",  (&[Fractional], &[Fractional]), ln_impl));
+        fns.insert("pow", BuiltinFun::new("pow", (&[Number, Number], &[Number]), pow_impl));
+        fns.insert("expm1", BuiltinFun::new("expm1", (&[Fractional], &[Fractional]), expm1_impl));
+        fns.insert("ln1p",  BuiltinFun::new("ln1p",  (&[Fractional], &[Fractional]), ln1p_impl));
+        fns.insert("log10", BuiltinFun::new("log10", (&[Fractional], &[Fractional]), log10_impl));
+        fns.insert("log2",  BuiltinFun::new("log2",  (&[Fractional], &[Fractional]), log2_impl));
+        fns.insert("logb",  BuiltinFun::new("logb",  (&[Fractional], &[Fractional]), logb_impl));
 
         // Trigonometry
-        fns.insert("sin", BuiltinFun::new("sin", (&[Fractional], &[Fractional]), sin_impl));
-        fns.insert("cos", BuiltinFun::new("cos", (&[Fractional], &[Fractional]), cos_impl));
-        fns.insert("tan", BuiltinFun::new("tan", (&[Fractional], &[Fractional]), tan_impl));
+        fns.insert("sin", BuiltinFun::new("sin", (&[Fractional], &[Fractional]), sin_impl).with_angles(Angles::Argument));
+        fns.insert("cos", BuiltinFun::new("cos", (&[Fractional], &[Fractional]), cos_impl).with_angles(Angles::Argument));
+        fns.insert("tan", BuiltinFun::new("tan", (&[Fractional], &[Fractional]), tan_impl).with_angles(Angles::Argument));
 
         // Trigonometry - reciprocals
-        fns.insert("csc", BuiltinFun::new("csc", (&[Fractional], &[Fractional]), csc_impl));
-        fns.insert("sec", BuiltinFun::new("sec", (&[Fractional], &[Fractional]), sec_impl));
-        fns.insert("cot", BuiltinFun::new("cot", (&[Fractional], &[Fractional]), cot_impl));
+        fns.insert("csc", BuiltinFun::new("csc", (&[Fractional], &[Fractional]), csc_impl).with_angles(Angles::Argument));
+        fns.insert("sec", BuiltinFun::new("sec", (&[Fractional], &[Fractional]), sec_impl).with_angles(Angles::Argument));
+        fns.insert("cot", BuiltinFun::new("cot", (&[Fractional], &[Fractional]), cot_impl).with_angles(Angles::Argument));
 
         // Trigonometry - principal inverses
-        fns.insert("asin", BuiltinFun::new("asin", (&[Fractional], &[Fractional]), asin_impl));
-        fns.insert("acos", BuiltinFun::new("acos", (&[Fractional], &[Fractional]), acos_impl));
-        fns.insert("atan", BuiltinFun::new("atan", (&[Fractional], &[Fractional]), atan_impl));
+        fns.insert("asin", BuiltinFun::new("asin", (&[Fractional], &[Fractional]), asin_impl).with_angles(Angles::Result));
+        fns.insert("acos", BuiltinFun::new("acos", (&[Fractional], &[Fractional]), acos_impl).with_angles(Angles::Result));
+        fns.insert("atan", BuiltinFun::new("atan", (&[Fractional], &[Fractional]), atan_impl).with_angles(Angles::Result));
+        fns.insert("atan2", BuiltinFun::new("atan2", (&[Fractional, Fractional], &[Fractional]), atan2_impl).with_angles(Angles::Result));
+
+        // Hyperbolic
+        fns.insert("sinh", BuiltinFun::new("sinh", (&[Fractional], &[Fractional]), sinh_impl));
+        fns.insert("cosh", BuiltinFun::new("cosh", (&[Fractional], &[Fractional]), cosh_impl));
+        fns.insert("tanh", BuiltinFun::new("tanh", (&[Fractional], &[Fractional]), tanh_impl));
+
+        // Hyperbolic - inverses
+        fns.insert("asinh", BuiltinFun::new("asinh", (&[Fractional], &[Fractional]), asinh_impl));
+        fns.insert("acosh", BuiltinFun::new("acosh", (&[Fractional], &[Fractional]), acosh_impl));
+        fns.insert("atanh", BuiltinFun::new("atanh", (&[Fractional], &[Fractional]), atanh_impl));
+
+        // Trigonometry - angle conversions
+        fns.insert("deg>rad", BuiltinFun::new("deg>rad", (&[Fractional], &[Fractional]), deg_to_rad_impl));
+        fns.insert("rad>deg", BuiltinFun::new("rad>deg", (&[Fractional], &[Fractional]), rad_to_deg_impl));
+
+        // Special functions
+        fns.insert("gamma",     BuiltinFun::new("gamma",     (&[Fractional], &[Fractional]), gamma_impl));
+        fns.insert("lgamma",    BuiltinFun::new("lgamma",    (&[Fractional], &[Fractional]), lgamma_impl));
+        fns.insert("digamma",   BuiltinFun::new("digamma",   (&[Fractional], &[Fractional]), digamma_impl));
+        fns.insert("beta",      BuiltinFun::new("beta",      (&[Fractional, Fractional], &[Fractional]), beta_impl));
+        fns.insert("erf",       BuiltinFun::new("erf",       (&[Fractional], &[Fractional]), erf_impl));
+        fns.insert("erfc",      BuiltinFun::new("erfc",      (&[Fractional], &[Fractional]), erfc_impl));
+        fns.insert("besselj0",  BuiltinFun::new("besselj0",  (&[Fractional], &[Fractional]), besselj0_impl));
+        fns.insert("besselj1",  BuiltinFun::new("besselj1",  (&[Fractional], &[Fractional]), besselj1_impl));
+        fns.insert("bessely0",  BuiltinFun::new("bessely0",  (&[Fractional], &[Fractional]), bessely0_impl));
+        fns.insert("bessely1",  BuiltinFun::new("bessely1",  (&[Fractional], &[Fractional]), bessely1_impl));
+
+        // Statistics
+        fns.insert("sum",       BuiltinFun::new("sum",       (&[List], &[Number]), sum_impl));
+        fns.insert("product",   BuiltinFun::new("product",   (&[List], &[Number]), product_impl));
+        fns.insert("mean",      BuiltinFun::new("mean",      (&[List], &[Fractional]), mean_impl));
+        fns.insert("median",    BuiltinFun::new("median",    (&[List], &[Fractional]), median_impl));
+        fns.insert("mode",      BuiltinFun::new("mode",      (&[List], &[Number]), mode_impl));
+        fns.insert("variance",  BuiltinFun::new("variance",  (&[List], &[Fractional]), variance_impl));
+        fns.insert("pvariance", BuiltinFun::new("pvariance", (&[List], &[Fractional]), pvariance_impl));
+        fns.insert("stddev",    BuiltinFun::new("stddev",    (&[List], &[Fractional]), stddev_impl));
+        fns.insert("pstddev",   BuiltinFun::new("pstddev",   (&[List], &[Fractional]), pstddev_impl));
+        fns.insert("range",     BuiltinFun::new("range",     (&[List], &[Number]), range_impl));
+        fns.insert("quantile",  BuiltinFun::new("quantile",  (&[List, Fractional], &[Fractional]), quantile_impl));
+        fns.insert("skewness",  BuiltinFun::new("skewness",  (&[List], &[Fractional]), skewness_impl));
+        fns.insert("kurtosis",  BuiltinFun::new("kurtosis",  (&[List], &[Fractional]), kurtosis_impl));
+
+        // Statistics - over the whole stack
+        fns.insert("stack-sum",       BuiltinFun::new("stack-sum",       (&[List], &[Number]), sum_impl).gathering_stack());
+        fns.insert("stack-product",   BuiltinFun::new("stack-product",   (&[List], &[Number]), product_impl).gathering_stack());
+        fns.insert("stack-mean",      BuiltinFun::new("stack-mean",      (&[List], &[Fractional]), mean_impl).gathering_stack());
+        fns.insert("stack-median",    BuiltinFun::new("stack-median",    (&[List], &[Fractional]), median_impl).gathering_stack());
+        fns.insert("stack-mode",      BuiltinFun::new("stack-mode",      (&[List], &[Number]), mode_impl).gathering_stack());
+        fns.insert("stack-variance",  BuiltinFun::new("stack-variance",  (&[List], &[Fractional]), variance_impl).gathering_stack());
+        fns.insert("stack-pvariance", BuiltinFun::new("stack-pvariance", (&[List], &[Fractional]), pvariance_impl).gathering_stack());
+        fns.insert("stack-stddev",    BuiltinFun::new("stack-stddev",    (&[List], &[Fractional]), stddev_impl).gathering_stack());
+        fns.insert("stack-pstddev",   BuiltinFun::new("stack-pstddev",   (&[List], &[Fractional]), pstddev_impl).gathering_stack());
+        fns.insert("stack-min",       BuiltinFun::new("stack-min",       (&[List], &[Number]), minimum_impl).gathering_stack());
+        fns.insert("stack-max",       BuiltinFun::new("stack-max",       (&[List], &[Number]), maximum_impl).gathering_stack());
+        fns.insert("stack-range",     BuiltinFun::new("stack-range",     (&[List], &[Number]), range_impl).gathering_stack());
+        fns.insert("stack-quantile",  BuiltinFun::new("stack-quantile",  (&[List, Fractional], &[Fractional]), quantile_impl).gathering_stack());
+        fns.insert("stack-skewness",  BuiltinFun::new("stack-skewness",  (&[List], &[Fractional]), skewness_impl).gathering_stack());
+        fns.insert("stack-kurtosis",  BuiltinFun::new("stack-kurtosis",  (&[List], &[Fractional]), kurtosis_impl).gathering_stack());
+
+        // Fitting
+        fns.insert("linreg",   BuiltinFun::new("linreg",   (&[List, List], &[Float64, Float64, Float64]), linreg_impl));
+        fns.insert("polyfit",  BuiltinFun::new("polyfit",  (&[List, List, Int32], &[List]), polyfit_impl));
+        fns.insert("expfit",   BuiltinFun::new("expfit",   (&[List, List], &[Float64, Float64]), expfit_impl));
+        fns.insert("powerfit", BuiltinFun::new("powerfit", (&[List, List], &[Float64, Float64]), powerfit_impl));
+
+        // Random numbers
+        fns.insert("random",  BuiltinFun::random("random",  (&[], &[Float64]), random_impl));
+        fns.insert("randint", BuiltinFun::random("randint", (&[Int32, Int32], &[Int32]), randint_impl));
+        fns.insert("normal",  BuiltinFun::random("normal",  (&[Float64, Float64], &[Float64]), normal_impl));
+        fns.insert("shuffle", BuiltinFun::random("shuffle", (&[List], &[List]), shuffle_impl));
+        fns.insert("choice",  BuiltinFun::random("choice",  (&[List], &[Any]), choice_impl));
+        fns.insert("seed",    BuiltinFun::random("seed",    (&[Int32], &[]), seed_impl));
+
+        // Linear algebra
+        fns.insert("transpose", BuiltinFun::new("transpose", (&[Matrix], &[Matrix]), transpose_impl));
+        fns.insert("det",       BuiltinFun::new("det",       (&[Matrix], &[Any]), det_impl));
+        fns.insert("inv",       BuiltinFun::new("inv",       (&[Matrix], &[Matrix]), inv_impl));
+        fns.insert("mmul",      BuiltinFun::new("mmul",      (&[Matrix, Matrix], &[Matrix]), mmul_impl));
+        fns.insert("solve",     BuiltinFun::new("solve",     (&[Matrix, Matrix], &[Matrix]), solve_impl));
+        fns.insert("identity",  BuiltinFun::new("identity",  (&[Int32], &[Matrix]), identity_impl));
+        fns.insert("trace",     BuiltinFun::new("trace",     (&[Matrix], &[Any]), trace_impl));
+        fns.insert("rank",      BuiltinFun::new("rank",      (&[Matrix], &[Int32]), rank_impl));
+        fns.insert("lu",        BuiltinFun::new("lu",        (&[Matrix], &[Matrix, Matrix, Matrix]), lu_impl));
+        fns.insert("eigen",     BuiltinFun::new("eigen",     (&[Matrix], &[Matrix, Matrix]), eigen_impl));
+
+        // Maps
+        fns.insert("get",    BuiltinFun::new("get",    (&[Map, Key], &[Any]), get_impl));
+        fns.insert("put",    BuiltinFun::new("put",    (&[Map, Key, Any], &[Map]), put_impl));
+        fns.insert("has?",   BuiltinFun::new("has?",   (&[Map, Key], &[Bool]), has_impl));
+        fns.insert("keys",   BuiltinFun::new("keys",   (&[Map], &[List]), keys_impl));
+        fns.insert("values", BuiltinFun::new("values", (&[Map], &[List]), values_impl));
+        fns.insert("remove", BuiltinFun::new("remove", (&[Map, Key], &[Map]), remove_impl));
 
         fns
     };
@@ -61,12 +218,38 @@ pub type Signiture<'a> = (&'a [Type], &'a [Type]);
 /// implementations return. It is optimized for
 /// functions that return single values.
 pub enum FunctionResult {
+    /// A single output ``Value``
     Scalar(Value),
-    //List(Vec<Value>),
+
+    /// Several output ``Value``s, in the order
+    /// they are pushed to the stack
+    List(Vec<Value>),
+
+    /// The arguments had the right types, but the
+    /// function could not be applied to them
+    Failure(TypeError),
+}
+
+/// The type of a function's Rust implementation
+#[derive(Clone, Copy)]
+pub enum Implementation {
+    /// Depends only on the arguments
+    Pure(fn(&[Value]) -> FunctionResult),
+
+    /// Also draws from, or reseeds, the interpreter's
+    /// random number generator
+    Random(fn(&[Value], &mut Generator) -> FunctionResult),
 }
 
-/// The type of a function's Rust implementation.
-pub type Implementation = fn(&[Value]) -> FunctionResult;
+impl Implementation {
+    /// Run the implementation on the arguments.
+    pub fn call(self, args: &[Value], generator: &mut Generator) -> FunctionResult {
+        match self {
+            Implementation::Pure(f) => f(args),
+            Implementation::Random(f) => f(args, generator),
+        }
+    }
+}
 
 /// The type of a built-in function
 ///
@@ -77,13 +260,130 @@ pub struct Function<'a> {
     pub name: String,
     pub signiture: Signiture<'a>,
     pub implementation: Implementation,
+    pub angles: Angles,
+
+    /// Whether the first argument, a list, is gathered
+    /// from every value on the stack beneath the others
+    pub gathers_stack: bool,
+
+    /// Other functions of the same name, tried in order
+    /// when the arguments don't match this one
+    pub overloads: Vec<Function<'a>>,
+}
+
+/// Whether a function works with angles, which the
+/// implementation measures in radians
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Angles {
+    /// The function does not take or give angles.
+    None,
+
+    /// The last argument is an angle.
+    Argument,
+
+    /// The result is an angle.
+    Result,
+}
+
+/// The unit that the trigonometric functions measure
+/// angles in
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+    Gradians,
+}
+
+impl AngleMode {
+    /// Return the mode with the given short name, such
+    /// as ``deg``.
+    pub fn from_name(name: &str) -> Option<AngleMode> {
+        match name {
+            "rad" => Some(AngleMode::Radians),
+            "deg" => Some(AngleMode::Degrees),
+            "grad" => Some(AngleMode::Gradians),
+            _ => None,
+        }
+    }
+
+    /// Return the short name of the mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            AngleMode::Radians => "rad",
+            AngleMode::Degrees => "deg",
+            AngleMode::Gradians => "grad",
+        }
+    }
+
+    /// Return the size of a full turn in this mode.
+    pub fn full_turn(self) -> f64 {
+        match self {
+            AngleMode::Radians => std::f64::consts::TAU,
+            AngleMode::Degrees => 360.0,
+            AngleMode::Gradians => 400.0,
+        }
+    }
+
+    /// Convert an angle measured in this mode to
+    /// radians.
+    pub fn to_radians(self, angle: f64) -> f64 {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle.to_radians(),
+            AngleMode::Gradians => angle * (std::f64::consts::PI / 200.0),
+        }
+    }
+
+    /// Convert an angle in radians to this mode.
+    pub fn from_radians(self, angle: f64) -> f64 {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle.to_degrees(),
+            AngleMode::Gradians => angle * (200.0 / std::f64::consts::PI),
+        }
+    }
+}
+
+impl fmt::Display for AngleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
 }
 
 impl<'a> Function<'a> {
     /// Create a new function with the provided
     /// human-readable name, type signiture, and
     /// backing implmentation.
-    pub fn new<S>(name: S, signiture: Signiture<'a>, implementation: Implementation) -> Function<'a>
+    pub fn new<S>(
+        name: S,
+        signiture: Signiture<'a>,
+        implementation: fn(&[Value]) -> FunctionResult,
+    ) -> Function<'a>
+    where
+        S: Into<String>,
+    {
+        Function::with_implementation(name, signiture, Implementation::Pure(implementation))
+    }
+
+    /// Create a new function whose implementation uses
+    /// the interpreter's random number generator.
+    pub fn random<S>(
+        name: S,
+        signiture: Signiture<'a>,
+        implementation: fn(&[Value], &mut Generator) -> FunctionResult,
+    ) -> Function<'a>
+    where
+        S: Into<String>,
+    {
+        Function::with_implementation(name, signiture, Implementation::Random(implementation))
+    }
+
+    fn with_implementation<S>(
+        name: S,
+        signiture: Signiture<'a>,
+        implementation: Implementation,
+    ) -> Function<'a>
     where
         S: Into<String>,
     {
@@ -91,8 +391,43 @@ impl<'a> Function<'a> {
             name: name.into(),
             signiture,
             implementation,
+            angles: Angles::None,
+            gathers_stack: false,
+            overloads: Vec::new(),
         }
     }
+
+    /// Mark the function as taking or giving an angle,
+    /// so that it follows the interpreter's angle mode.
+    pub fn with_angles(mut self, angles: Angles) -> Function<'a> {
+        self.angles = angles;
+        self
+    }
+
+    /// Mark the function as taking its list from the
+    /// whole stack, rather than from a list value.
+    pub fn gathering_stack(mut self) -> Function<'a> {
+        self.gathers_stack = true;
+        self
+    }
+
+    /// Add another function of the same name, which is
+    /// used when the arguments match its signiture but
+    /// not this one's.
+    pub fn or(mut self, overload: Function<'a>) -> Function<'a> {
+        self.overloads.push(overload);
+        self
+    }
+
+    /// Return the first of the function and its
+    /// overloads whose arguments are on the stack, or the
+    /// function itself if there are none.
+    pub fn resolve(&self, stack: &Stack) -> &Function<'a> {
+        std::iter::once(self)
+            .chain(&self.overloads)
+            .find(|fun| typecheck::check_args(fun.signiture.0, stack).is_ok())
+            .unwrap_or(self)
+    }
 }
 
 impl<'a> fmt::Debug for Function<'a> {
@@ -123,23 +458,451 @@ pub type BuiltinFun = Function<'static>;
 
 mod builtins {
     use super::FunctionResult::{self, *};
-    use crate::value::Value;
+    use crate::{
+        matrix::{Dense, Matrix, MAX_IDENTITY_ORDER},
+        number_theory,
+        random::Generator,
+        special,
+        statistics::{self, Moments},
+        typecheck::TypeError,
+        units::{Quantity, Unit},
+        value::{Key, Type, Value},
+    };
+    use num_bigint::BigInt;
+    use num_integer::Integer;
+    use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+    // Arithmetic
+    //
+    // Plain numbers must have the same type, since there
+    // are no implicit conversions. Quantities must have
+    // the same dimension to be added or subtracted, but
+    // may be multiplied or divided by anything numeric.
+    // Integers of types other than ``Int32`` are computed
+    // on exactly, and checked on the way back.
+    pub fn add_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(x), Value::Int32(y)] => checked_int32(x.checked_add(*y)),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x + y)),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x + y)),
+            [Value::Quantity(x), Value::Quantity(y)] => quantity_result(x.add(y)),
+            [Value::Quantity(_), _] | [_, Value::Quantity(_)] => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => exact(args, |xs| Ok(&xs[0] + &xs[1])),
+        }
+    }
+
+    pub fn sub_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(x), Value::Int32(y)] => checked_int32(x.checked_sub(*y)),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x - y)),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x - y)),
+            [Value::Quantity(x), Value::Quantity(y)] => quantity_result(x.sub(y)),
+            [Value::Quantity(_), _] | [_, Value::Quantity(_)] => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => exact(args, |xs| Ok(&xs[0] - &xs[1])),
+        }
+    }
+
+    pub fn mul_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(x), Value::Int32(y)] => checked_int32(x.checked_mul(*y)),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x * y)),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x * y)),
+            [Value::Quantity(x), Value::Quantity(y)] => quantity_result(x.mul(y)),
+            [Value::Quantity(x), y] => Scalar(x.scale(y.to_f64().unwrap()).into_value()),
+            [x, Value::Quantity(y)] => Scalar(y.scale(x.to_f64().unwrap()).into_value()),
+            _ => exact(args, |xs| Ok(&xs[0] * &xs[1])),
+        }
+    }
+
+    pub fn div_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(_), Value::Int32(0)] => Failure(TypeError::DomainError),
+            [Value::Int32(x), Value::Int32(y)] => checked_int32(x.checked_div(*y)),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x / y)),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x / y)),
+            [Value::Quantity(x), Value::Quantity(y)] => quantity_result(x.div(y)),
+            [Value::Quantity(x), y] => Scalar(x.scale(y.to_f64().unwrap().recip()).into_value()),
+            [x, Value::Quantity(y)] => {
+                let one = Quantity::new(x.to_f64().unwrap(), Unit::one());
+                quantity_result(one.div(y))
+            }
+            _ => exact(args, |xs| match xs {
+                [_, y] if y.is_zero() => Err(TypeError::DomainError),
+                [x, y] => Ok(x / y),
+                _ => unreachable!(),
+            }),
+        }
+    }
+
+    pub fn negate_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(x)] => checked_int32(x.checked_neg()),
+            [Value::Float32(x)] => Scalar(Value::Float32(-x)),
+            [Value::Float64(x)] => Scalar(Value::Float64(-x)),
+            [Value::Quantity(x)] => Scalar(Value::Quantity(x.scale(-1.0))),
+            _ => exact(args, |xs| Ok(-&xs[0])),
+        }
+    }
+
+    fn checked_int32(result: Option<i32>) -> FunctionResult {
+        match result {
+            Some(x) => Scalar(Value::Int32(x)),
+            None => Failure(TypeError::Overflow),
+        }
+    }
+
+    // The arguments as ``BigInt``s, with their type, if
+    // they are integers of one type
+    fn integer_args(args: &[Value]) -> Result<(Type, Vec<BigInt>), TypeError> {
+        let typ = args[0].type_of();
+        let xs = args
+            .iter()
+            .map(|arg| match arg.to_bigint() {
+                Some(x) if arg.type_of() == typ => Ok(x),
+                _ => Err(TypeError::TypeMismatch),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok((typ, xs))
+    }
+
+    // Compute exactly on integers of one type, giving a
+    // result of that type if it fits.
+    fn exact<F>(args: &[Value], f: F) -> FunctionResult
+    where
+        F: FnOnce(&[BigInt]) -> Result<BigInt, TypeError>,
+    {
+        match integer_args(args).and_then(|(typ, xs)| Ok((f(&xs)?, typ))) {
+            Ok((x, typ)) => checked_integer(x, &typ),
+            Err(e) => Failure(e),
+        }
+    }
+
+    fn checked_integer(x: BigInt, typ: &Type) -> FunctionResult {
+        match Value::integer(x, typ) {
+            Some(x) => Scalar(x),
+            None => Failure(TypeError::Overflow),
+        }
+    }
+
+    fn quantity_result(result: Result<Quantity, TypeError>) -> FunctionResult {
+        match result {
+            Ok(q) => Scalar(q.into_value()),
+            Err(e) => Failure(e),
+        }
+    }
 
     // Integer operations
     pub fn inc_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Int32(x)] => Scalar(Value::Int32(x + 1)),
-            _ => unreachable!(),
+            [Value::Int32(x)] => checked_int32(x.checked_add(1)),
+            _ => exact(args, |xs| Ok(&xs[0] + 1)),
         }
     }
     
     pub fn dec_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Int32(x)] => Scalar(Value::Int32(x - 1)),
+            [Value::Int32(x)] => checked_int32(x.checked_sub(1)),
+            _ => exact(args, |xs| Ok(&xs[0] - 1)),
+        }
+    }
+
+    // Number theory
+    //
+    // The arguments must be integers of one type. They
+    // are computed on as ``BigInt``s, where nothing can
+    // overflow, and checked on the way back.
+    pub fn gcd_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| Ok(number_theory::gcd(&xs[0], &xs[1])))
+    }
+
+    pub fn lcm_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| Ok(number_theory::lcm(&xs[0], &xs[1])))
+    }
+
+    pub fn is_prime_impl(args: &[Value]) -> FunctionResult {
+        match integer_args(args) {
+            Ok((_, xs)) => Scalar(Value::Bool(number_theory::is_prime(&xs[0]))),
+            Err(e) => Failure(e),
+        }
+    }
+
+    // The smallest prime greater than the argument
+    pub fn next_prime_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| Ok(number_theory::next_prime(&xs[0])))
+    }
+
+    // The prime factors, with multiplicity, in ascending
+    // order
+    pub fn factor_impl(args: &[Value]) -> FunctionResult {
+        match integer_args(args) {
+            Ok((typ, xs)) => match xs[0].to_biguint().filter(|n| !n.is_zero()) {
+                Some(n) => {
+                    let factors = number_theory::factor(&n).into_iter();
+                    let factors = factors.map(|p| Value::integer(p.into(), &typ));
+                    // Every factor is at most the argument,
+                    // so it fits in the same type.
+                    Scalar(Value::List(factors.collect::<Option<_>>().unwrap()))
+                }
+                None => Failure(TypeError::DomainError),
+            },
+            Err(e) => Failure(e),
+        }
+    }
+
+    pub fn totient_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| {
+            match xs[0].to_biguint().filter(|n| !n.is_zero()) {
+                Some(n) => Ok(number_theory::totient(&n).into()),
+                None => Err(TypeError::DomainError),
+            }
+        })
+    }
+
+    // Modular exponentiation, giving a result from zero
+    // up to the modulus
+    pub fn modpow_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| match xs {
+            [b, e, m] if !e.is_negative() && m.is_positive() => Ok(number_theory::mod_pow(b, e, m)),
+            _ => Err(TypeError::DomainError),
+        })
+    }
+
+    pub fn modinv_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| match xs {
+            [x, m] if m.is_positive() => number_theory::mod_inv(x, m).ok_or(TypeError::DomainError),
+            _ => Err(TypeError::DomainError),
+        })
+    }
+
+    pub fn isqrt_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| match &xs[0] {
+            x if x.is_negative() => Err(TypeError::DomainError),
+            x => Ok(number_theory::isqrt(x)),
+        })
+    }
+
+    // The quotient and remainder, truncating like ``/``
+    pub fn divmod_impl(args: &[Value]) -> FunctionResult {
+        match integer_args(args) {
+            Ok((_, xs)) if xs[1].is_zero() => Failure(TypeError::DomainError),
+            Ok((typ, xs)) => {
+                let (q, r) = xs[0].div_rem(&xs[1]);
+                match (Value::integer(q, &typ), Value::integer(r, &typ)) {
+                    (Some(q), Some(r)) => List(vec![q, r]),
+                    _ => Failure(TypeError::Overflow),
+                }
+            }
+            Err(e) => Failure(e),
+        }
+    }
+
+    pub fn binomial_impl(args: &[Value]) -> FunctionResult {
+        exact(args, |xs| match xs {
+            [n, _] if n.is_negative() => Err(TypeError::DomainError),
+            [n, k] => number_theory::binomial(n, k).ok_or(TypeError::Overflow),
+            _ => unreachable!(),
+        })
+    }
+
+    // The factorial is exact for integers, and
+    // x! = Γ(x + 1) for real numbers.
+    pub fn factorial_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(_)] | [Value::Float64(_)] => in_f64(args, |x| special::gamma(x + 1.0)),
+            _ => exact(args, |xs| match &xs[0] {
+                n if n.is_negative() => Err(TypeError::DomainError),
+                n => number_theory::factorial(n).ok_or(TypeError::Overflow),
+            }),
+        }
+    }
+
+    // Bitwise
+    //
+    // Integers of fixed size are words of as many bits
+    // as their type, so ``shr`` fills with zeros, and
+    // shift amounts must be less than the word size. Big
+    // integers act as if they had endless sign bits, so
+    // they cannot be rotated or have their leading zeros
+    // counted, and ``shr`` fills with the sign.
+    pub fn band_impl(args: &[Value]) -> FunctionResult {
+        match integer_args(args) {
+            Ok((Type::BigInt, xs)) => Scalar(Value::BigInt(&xs[0] & &xs[1])),
+            Ok(_) => in_words(args, |x, y| x & y),
+            Err(e) => Failure(e),
+        }
+    }
+
+    pub fn bor_impl(args: &[Value]) -> FunctionResult {
+        match integer_args(args) {
+            Ok((Type::BigInt, xs)) => Scalar(Value::BigInt(&xs[0] | &xs[1])),
+            Ok(_) => in_words(args, |x, y| x | y),
+            Err(e) => Failure(e),
+        }
+    }
+
+    pub fn bxor_impl(args: &[Value]) -> FunctionResult {
+        match integer_args(args) {
+            Ok((Type::BigInt, xs)) => Scalar(Value::BigInt(&xs[0] ^ &xs[1])),
+            Ok(_) => in_words(args, |x, y| x ^ y),
+            Err(e) => Failure(e),
+        }
+    }
+
+    pub fn bnot_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::BigInt(x)] => Scalar(Value::BigInt(!x)),
+            [x] => match word(x) {
+                Ok((x, _)) => word_result(!x, &args[0]),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn shl_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::BigInt(x), n] => match amount(n) {
+                Ok(n) if n.is_negative() => Failure(TypeError::DomainError),
+                Ok(n) => match number_theory::pow(&BigInt::from(2), &n) {
+                    Some(power) => Scalar(Value::BigInt(x * power)),
+                    None => Failure(TypeError::Overflow),
+                },
+                Err(e) => Failure(e),
+            },
+            [x, n] => match (word(x), amount(n)) {
+                (Ok((x, bits)), Ok(n)) => match n.to_u32() {
+                    Some(n) if n < bits => word_result(x << n, &args[0]),
+                    _ => Failure(TypeError::DomainError),
+                },
+                (Err(e), _) | (_, Err(e)) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn shr_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::BigInt(x), n] => match amount(n) {
+                Ok(n) if n.is_negative() => Failure(TypeError::DomainError),
+                // Past the highest bit, only the sign is left.
+                Ok(n) => match n.to_u64() {
+                    Some(n) if n <= x.bits() => Scalar(Value::BigInt(x >> n)),
+                    _ => Scalar(Value::BigInt(x >> x.bits())),
+                },
+                Err(e) => Failure(e),
+            },
+            [x, n] => match (word(x), amount(n)) {
+                (Ok((x, bits)), Ok(n)) => match n.to_u32() {
+                    Some(n) if n < bits => word_result(x >> n, &args[0]),
+                    _ => Failure(TypeError::DomainError),
+                },
+                (Err(e), _) | (_, Err(e)) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    // Rotations may be by any amount, since rotating by
+    // the word size does nothing.
+    pub fn rotl_impl(args: &[Value]) -> FunctionResult {
+        match (word(&args[0]), amount(&args[1])) {
+            (Ok((x, bits)), Ok(n)) => word_result(rotate_left(x, bits, &n), &args[0]),
+            (Err(e), _) | (_, Err(e)) => Failure(e),
+        }
+    }
+
+    pub fn rotr_impl(args: &[Value]) -> FunctionResult {
+        match (word(&args[0]), amount(&args[1])) {
+            (Ok((x, bits)), Ok(n)) => word_result(rotate_left(x, bits, &-n), &args[0]),
+            (Err(e), _) | (_, Err(e)) => Failure(e),
+        }
+    }
+
+    // Rotate the lowest ``bits`` bits of a word left.
+    fn rotate_left(x: u64, bits: u32, n: &BigInt) -> u64 {
+        match n.mod_floor(&BigInt::from(bits)).to_u32().unwrap() {
+            0 => x,
+            n => x << n | x >> (bits - n),
+        }
+    }
+
+    pub fn popcount_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            // A negative number has endless ones.
+            [Value::BigInt(x)] if x.is_negative() => Failure(TypeError::DomainError),
+            [Value::BigInt(x)] => count_result(x.magnitude().count_ones(), &args[0]),
+            [x] => match word(x) {
+                Ok((x, _)) => count_result(x.count_ones().into(), &args[0]),
+                Err(e) => Failure(e),
+            },
             _ => unreachable!(),
         }
     }
 
+    pub fn clz_impl(args: &[Value]) -> FunctionResult {
+        match word(&args[0]) {
+            Ok((x, bits)) => {
+                count_result((x.leading_zeros() - (u64::BITS - bits)).into(), &args[0])
+            }
+            Err(e) => Failure(e),
+        }
+    }
+
+    pub fn ctz_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::BigInt(x)] => match x.trailing_zeros() {
+                Some(n) => count_result(n, &args[0]),
+                // Zero has endless zeros.
+                None => Failure(TypeError::DomainError),
+            },
+            [x] => match word(x) {
+                Ok((x, bits)) => count_result(x.trailing_zeros().min(bits).into(), &args[0]),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    // The bits of a fixed-size integer, and how many
+    // there are
+    fn word(x: &Value) -> Result<(u64, u32), TypeError> {
+        match (x.to_word(), x.type_of().bits()) {
+            (Some(word), Some(bits)) => Ok((word, bits)),
+            _ => Err(TypeError::TypeMismatch),
+        }
+    }
+
+    // An amount to shift or rotate by, which may be an
+    // integer of any type
+    fn amount(n: &Value) -> Result<BigInt, TypeError> {
+        n.to_bigint().ok_or(TypeError::TypeMismatch)
+    }
+
+    // Combine two fixed-size integers of one type bit by
+    // bit.
+    fn in_words(args: &[Value], op: fn(u64, u64) -> u64) -> FunctionResult {
+        match (word(&args[0]), word(&args[1])) {
+            (Ok((x, _)), Ok((y, _))) => word_result(op(x, y), &args[0]),
+            (Err(e), _) | (_, Err(e)) => Failure(e),
+        }
+    }
+
+    // The integer of the same type as ``like`` with the
+    // lowest bits of ``word``
+    fn word_result(word: u64, like: &Value) -> FunctionResult {
+        Scalar(Value::from_word(word, &like.type_of()).unwrap())
+    }
+
+    // A count of bits, which fits in any integer type
+    fn count_result(count: u64, like: &Value) -> FunctionResult {
+        Scalar(Value::integer(count.into(), &like.type_of()).unwrap())
+    }
+
     // Algebraic
     pub fn recip_impl(args: &[Value]) -> FunctionResult {
         match args {
@@ -165,94 +928,1093 @@ mod builtins {
         }
     }
 
-    // Exponential & Logarithmic
-    pub fn exp_impl(args: &[Value]) -> FunctionResult {
+    // Quantities must have the same dimension, and the
+    // result is in the unit of the first.
+    pub fn hypot_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.exp())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.exp())),
-            _ => unreachable!(),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x.hypot(*y))),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x.hypot(*y))),
+            [Value::Quantity(x), Value::Quantity(y)] => match y.convert(&x.unit) {
+                Ok(y) => Scalar(map_magnitude(x, |x| x.hypot(y.magnitude))),
+                Err(e) => Failure(e),
+            },
+            [Value::Quantity(_), _] | [_, Value::Quantity(_)] => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => Failure(TypeError::TypeMismatch),
         }
     }
-    
-    pub fn ln_impl(args: &[Value]) -> FunctionResult {
+
+    // Signs & rounding
+    //
+    // Integers are already whole, so rounding leaves
+    // them as they are. Quantities are rounded in their
+    // own unit, so ``2.5 km round`` is ``3 km``.
+    pub fn abs_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.ln())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.ln())),
-            _ => unreachable!(),
+            [Value::Int32(x)] => checked_int32(x.checked_abs()),
+            [Value::Float32(x)] => Scalar(Value::Float32(x.abs())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.abs())),
+            [Value::Quantity(x)] => Scalar(map_magnitude(x, f64::abs)),
+            _ => exact(args, |xs| Ok(xs[0].abs())),
         }
     }
 
-    // Trigonometry
-    pub fn sin_impl(args: &[Value]) -> FunctionResult {
+    // Unlike ``f64::signum``, the sign of zero is zero.
+    // The sign of a quantity is a plain number.
+    pub fn signum_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.sin())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.sin())),
-            _ => unreachable!(),
+            [Value::Int32(x)] => Scalar(Value::Int32(x.signum())),
+            [Value::Float32(x)] => Scalar(Value::Float32(signum(f64::from(*x)) as f32)),
+            [Value::Float64(x)] => Scalar(Value::Float64(signum(*x))),
+            [Value::Quantity(x)] => Scalar(Value::Float64(signum(x.magnitude))),
+            _ => exact(args, |xs| Ok(xs[0].signum())),
         }
     }
 
-    pub fn cos_impl(args: &[Value]) -> FunctionResult {
+    fn signum(x: f64) -> f64 {
+        if x == 0.0 {
+            x
+        } else {
+            x.signum()
+        }
+    }
+
+    pub fn floor_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.cos())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.cos())),
-            _ => unreachable!(),
+            [Value::Int32(x)] => Scalar(Value::Int32(*x)),
+            [Value::Float32(x)] => Scalar(Value::Float32(x.floor())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.floor())),
+            [Value::Quantity(x)] => Scalar(map_magnitude(x, f64::floor)),
+            _ => exact(args, |xs| Ok(xs[0].clone())),
         }
     }
 
-    pub fn tan_impl(args: &[Value]) -> FunctionResult {
+    pub fn ceil_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.tan())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.tan())),
-            _ => unreachable!(),
+            [Value::Int32(x)] => Scalar(Value::Int32(*x)),
+            [Value::Float32(x)] => Scalar(Value::Float32(x.ceil())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.ceil())),
+            [Value::Quantity(x)] => Scalar(map_magnitude(x, f64::ceil)),
+            _ => exact(args, |xs| Ok(xs[0].clone())),
         }
     }
 
-    // Trigonometry - reciprocals
-    pub fn csc_impl(args: &[Value]) -> FunctionResult {
+    // Halves are rounded away from zero.
+    pub fn round_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.sin().recip())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.sin().recip())),
-            _ => unreachable!(),
+            [Value::Int32(x)] => Scalar(Value::Int32(*x)),
+            [Value::Float32(x)] => Scalar(Value::Float32(x.round())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.round())),
+            [Value::Quantity(x)] => Scalar(map_magnitude(x, f64::round)),
+            _ => exact(args, |xs| Ok(xs[0].clone())),
         }
     }
 
-    pub fn sec_impl(args: &[Value]) -> FunctionResult {
+    pub fn trunc_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.cos().recip())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.cos().recip())),
-            _ => unreachable!(),
+            [Value::Int32(x)] => Scalar(Value::Int32(*x)),
+            [Value::Float32(x)] => Scalar(Value::Float32(x.trunc())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.trunc())),
+            [Value::Quantity(x)] => Scalar(map_magnitude(x, f64::trunc)),
+            _ => exact(args, |xs| Ok(xs[0].clone())),
         }
     }
 
-    pub fn cot_impl(args: &[Value]) -> FunctionResult {
+    // The fractional part has the sign of the number,
+    // so ``x trunc`` and ``x fract`` add up to ``x``.
+    pub fn fract_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.tan().recip())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.tan().recip())),
-            _ => unreachable!(),
+            [Value::Int32(_)] => Scalar(Value::Int32(0)),
+            [Value::Float32(x)] => Scalar(Value::Float32(x.fract())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.fract())),
+            [Value::Quantity(x)] => Scalar(map_magnitude(x, f64::fract)),
+            _ => exact(args, |_| Ok(BigInt::zero())),
         }
     }
-    
-    // Trigonometry - principal inverses
-    pub fn asin_impl(args: &[Value]) -> FunctionResult {
+
+    fn map_magnitude<F: Fn(f64) -> f64>(x: &Quantity, f: F) -> Value {
+        Value::Quantity(Quantity::new(f(x.magnitude), x.unit.clone()))
+    }
+
+    // Comparison
+    //
+    // As with arithmetic, plain numbers must have the
+    // same type, and quantities the same dimension. If
+    // one argument is NaN, the other is the result.
+    pub fn min_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.asin())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.asin())),
-            _ => unreachable!(),
+            [Value::Int32(x), Value::Int32(y)] => Scalar(Value::Int32(*x.min(y))),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x.min(*y))),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x.min(*y))),
+            [Value::Quantity(x), Value::Quantity(y)] => match y.convert(&x.unit) {
+                Ok(converted) if converted.magnitude < x.magnitude => Scalar(args[1].clone()),
+                Ok(_) => Scalar(args[0].clone()),
+                Err(e) => Failure(e),
+            },
+            [Value::Quantity(_), _] | [_, Value::Quantity(_)] => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => exact(args, |xs| Ok(xs[0].clone().min(xs[1].clone()))),
         }
     }
 
-    pub fn acos_impl(args: &[Value]) -> FunctionResult {
+    pub fn max_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.acos())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.acos())),
-            _ => unreachable!(),
+            [Value::Int32(x), Value::Int32(y)] => Scalar(Value::Int32(*x.max(y))),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x.max(*y))),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x.max(*y))),
+            [Value::Quantity(x), Value::Quantity(y)] => match y.convert(&x.unit) {
+                Ok(converted) if converted.magnitude > x.magnitude => Scalar(args[1].clone()),
+                Ok(_) => Scalar(args[0].clone()),
+                Err(e) => Failure(e),
+            },
+            [Value::Quantity(_), _] | [_, Value::Quantity(_)] => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => exact(args, |xs| Ok(xs[0].clone().max(xs[1].clone()))),
         }
     }
 
-    pub fn atan_impl(args: &[Value]) -> FunctionResult {
+    // Clamp ``x`` to the range from ``lo`` to ``hi``,
+    // which must not be empty.
+    pub fn clamp_impl(args: &[Value]) -> FunctionResult {
         match args {
-            [Value::Float32(x)] => Scalar(Value::Float32(x.atan())),
-            [Value::Float64(x)] => Scalar(Value::Float64(x.atan())),
+            [Value::Int32(_), Value::Int32(lo), Value::Int32(hi)] if lo > hi => {
+                Failure(TypeError::DomainError)
+            }
+            [Value::Int32(x), Value::Int32(lo), Value::Int32(hi)] => {
+                Scalar(Value::Int32(*x.clamp(lo, hi)))
+            }
+            [Value::Float32(x), Value::Float32(lo), Value::Float32(hi)] => {
+                clamp_f64(f64::from(*x), f64::from(*lo), f64::from(*hi))
+                    .map_or(Failure(TypeError::DomainError), |x| {
+                        Scalar(Value::Float32(x as f32))
+                    })
+            }
+            [Value::Float64(x), Value::Float64(lo), Value::Float64(hi)] => clamp_f64(*x, *lo, *hi)
+                .map_or(Failure(TypeError::DomainError), |x| {
+                    Scalar(Value::Float64(x))
+                }),
+            [Value::Quantity(x), Value::Quantity(lo), Value::Quantity(hi)] => {
+                let bounds = lo
+                    .convert(&x.unit)
+                    .and_then(|lo| Ok((lo, hi.convert(&x.unit)?)));
+
+                match bounds {
+                    Ok((lo, hi)) => match clamp_f64(x.magnitude, lo.magnitude, hi.magnitude) {
+                        Some(clamped) => {
+                            Scalar(Value::Quantity(Quantity::new(clamped, x.unit.clone())))
+                        }
+                        None => Failure(TypeError::DomainError),
+                    },
+                    Err(e) => Failure(e),
+                }
+            }
+            _ if args.iter().any(|arg| matches!(arg, Value::Quantity(_))) => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => exact(args, |xs| match xs {
+                [_, lo, hi] if lo > hi => Err(TypeError::DomainError),
+                [x, lo, hi] => Ok(x.clone().clamp(lo.clone(), hi.clone())),
+                _ => unreachable!(),
+            }),
+        }
+    }
+
+    // Unlike ``f64::clamp``, this does not panic when
+    // the range is empty or a bound is NaN.
+    fn clamp_f64(x: f64, lo: f64, hi: f64) -> Option<f64> {
+        if lo <= hi {
+            Some(x.clamp(lo, hi))
+        } else {
+            None
+        }
+    }
+
+    // Exponential & Logarithmic
+    pub fn exp_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.exp())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.exp())),
+            _ => unreachable!(),
+        }
+    }
+    
+    pub fn ln_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.ln())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.ln())),
+            _ => unreachable!(),
+        }
+    }
+
+    // Anything may be raised to an integer power, but
+    // integers only to powers that are not negative.
+    // Raising a quantity raises its unit too. Otherwise,
+    // the types must match.
+    pub fn pow_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(_), Value::Int32(y)] if *y < 0 => Failure(TypeError::DomainError),
+            [Value::Int32(x), Value::Int32(y)] => checked_int32(x.checked_pow(*y as u32)),
+            [Value::Float32(x), Value::Float32(y)] => Scalar(Value::Float32(x.powf(*y))),
+            [Value::Float64(x), Value::Float64(y)] => Scalar(Value::Float64(x.powf(*y))),
+            [Value::Float32(x), Value::Int32(y)] => Scalar(Value::Float32(x.powi(*y))),
+            [Value::Float64(x), Value::Int32(y)] => Scalar(Value::Float64(x.powi(*y))),
+            [Value::Quantity(x), Value::Int32(y)] => {
+                let unit = x
+                    .unit
+                    .pow(*y)
+                    .map(|unit| Quantity::new(x.magnitude.powi(*y), unit));
+                quantity_result(unit)
+            }
+            [Value::Quantity(_), _] | [_, Value::Quantity(_)] => {
+                Failure(TypeError::DimensionMismatch)
+            }
+            _ => exact(args, |xs| match xs {
+                [_, y] if y.is_negative() => Err(TypeError::DomainError),
+                [x, y] => number_theory::pow(x, y).ok_or(TypeError::Overflow),
+                _ => unreachable!(),
+            }),
+        }
+    }
+
+    pub fn expm1_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.exp_m1())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.exp_m1())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn ln1p_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.ln_1p())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.ln_1p())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn log10_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.log10())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.log10())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn log2_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.log2())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.log2())),
+            _ => unreachable!(),
+        }
+    }
+
+    // The exponent of the number in base 2, as in C, so
+    // ``12.0 logb`` is ``3.0``. Subnormal numbers have
+    // their true exponent.
+    pub fn logb_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(logb(f64::from(*x)) as f32)),
+            [Value::Float64(x)] => Scalar(Value::Float64(logb(*x))),
+            _ => unreachable!(),
+        }
+    }
+
+    // Every Float32 is exactly a Float64 with the same
+    // exponent, so one version does for both.
+    fn logb(x: f64) -> f64 {
+        if x == 0.0 {
+            return f64::NEG_INFINITY;
+        }
+
+        if !x.is_finite() {
+            return x.abs();
+        }
+
+        let bits = x.abs().to_bits();
+        let exponent = (bits >> 52) as i32;
+
+        if exponent == 0 {
+            // Subnormal, so the exponent is set by the
+            // highest bit of the significand.
+            f64::from(63 - bits.leading_zeros() as i32 - 1074)
+        } else {
+            f64::from(exponent - 1023)
+        }
+    }
+
+    // Trigonometry
+    pub fn sin_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.sin())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.sin())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn cos_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.cos())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.cos())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn tan_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.tan())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.tan())),
+            _ => unreachable!(),
+        }
+    }
+
+    // Trigonometry - reciprocals
+    pub fn csc_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.sin().recip())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.sin().recip())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn sec_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.cos().recip())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.cos().recip())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn cot_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.tan().recip())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.tan().recip())),
+            _ => unreachable!(),
+        }
+    }
+    
+    // Trigonometry - principal inverses
+    pub fn asin_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.asin())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.asin())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn acos_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.acos())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.acos())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn atan_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.atan())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.atan())),
+            _ => unreachable!(),
+        }
+    }
+
+    // The angle of the point ``(x, y)``, from the
+    // arguments ``y x``, as in C.
+    pub fn atan2_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(y), Value::Float32(x)] => Scalar(Value::Float32(y.atan2(*x))),
+            [Value::Float64(y), Value::Float64(x)] => Scalar(Value::Float64(y.atan2(*x))),
+            _ => Failure(TypeError::TypeMismatch),
+        }
+    }
+
+    // Hyperbolic
+    pub fn sinh_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.sinh())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.sinh())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn cosh_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.cosh())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.cosh())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn tanh_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.tanh())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.tanh())),
+            _ => unreachable!(),
+        }
+    }
+
+    // Hyperbolic - inverses
+    pub fn asinh_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.asinh())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.asinh())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn acosh_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.acosh())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.acosh())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn atanh_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.atanh())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.atanh())),
+            _ => unreachable!(),
+        }
+    }
+
+    // Trigonometry - angle conversions
+    pub fn deg_to_rad_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.to_radians())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.to_radians())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn rad_to_deg_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(x.to_degrees())),
+            [Value::Float64(x)] => Scalar(Value::Float64(x.to_degrees())),
+            _ => unreachable!(),
+        }
+    }
+
+    // Special functions
+    //
+    // These are computed in Float64, and rounded for
+    // Float32.
+    pub fn gamma_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::gamma)
+    }
+
+    pub fn lgamma_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::lgamma)
+    }
+
+    pub fn digamma_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::digamma)
+    }
+
+    pub fn beta_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Float32(a), Value::Float32(b)] => Scalar(Value::Float32(special::beta(
+                f64::from(*a),
+                f64::from(*b),
+            ) as f32)),
+            [Value::Float64(a), Value::Float64(b)] => Scalar(Value::Float64(special::beta(*a, *b))),
+            _ => Failure(TypeError::TypeMismatch),
+        }
+    }
+
+    pub fn erf_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::erf)
+    }
+
+    pub fn erfc_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::erfc)
+    }
+
+    pub fn besselj0_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::bessel_j0)
+    }
+
+    pub fn besselj1_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::bessel_j1)
+    }
+
+    pub fn bessely0_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::bessel_y0)
+    }
+
+    pub fn bessely1_impl(args: &[Value]) -> FunctionResult {
+        in_f64(args, special::bessel_y1)
+    }
+
+    fn in_f64(args: &[Value], f: fn(f64) -> f64) -> FunctionResult {
+        match args {
+            [Value::Float32(x)] => Scalar(Value::Float32(f(f64::from(*x)) as f32)),
+            [Value::Float64(x)] => Scalar(Value::Float64(f(*x))),
+            _ => unreachable!(),
+        }
+    }
+
+    // Statistics
+    //
+    // The items of a list must be plain numbers of one
+    // type, and there must be at least one, except in a
+    // sum or product, where none gives 0 or 1. Results that
+    // are items of the list, such as the minimum, keep
+    // their type, and the others are Float32 for Float32
+    // lists and Float64 otherwise.
+    pub fn sum_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::List(items)] if items.is_empty() => Scalar(Value::Int32(0)),
+            [Value::List(items)] => match (samples(items), &items[..]) {
+                (Err(e), _) => Failure(e),
+                (Ok(_), [first, ..]) if first.type_of().is_integer() => {
+                    exact(items, |xs| Ok(xs.iter().sum()))
+                }
+                (Ok(xs), _) => item_like(statistics::sum(&xs), &items[0]),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn product_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::List(items)] if items.is_empty() => Scalar(Value::Int32(1)),
+            [Value::List(items)] => match (samples(items), &items[..]) {
+                (Err(e), _) => Failure(e),
+                (Ok(_), [first, ..]) if first.type_of().is_integer() => {
+                    exact(items, |xs| Ok(xs.iter().product()))
+                }
+                (Ok(xs), _) => item_like(xs.iter().product(), &items[0]),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn mean_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| Ok(statistics::sum(xs) / xs.len() as f64),
+            fractional_like,
+        )
+    }
+
+    pub fn median_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| Ok(statistics::quantile(&sorted(xs), 0.5)),
+            fractional_like,
+        )
+    }
+
+    pub fn mode_impl(args: &[Value]) -> FunctionResult {
+        with_list(args, |xs| Ok(statistics::mode(&sorted(xs))), item_like)
+    }
+
+    pub fn variance_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| sample_moments(xs).map(|m| m.variance(true)),
+            fractional_like,
+        )
+    }
+
+    pub fn pvariance_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| Ok(Moments::of(xs).variance(false)),
+            fractional_like,
+        )
+    }
+
+    pub fn stddev_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| sample_moments(xs).map(|m| m.variance(true).sqrt()),
+            fractional_like,
+        )
+    }
+
+    pub fn pstddev_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| Ok(Moments::of(xs).variance(false).sqrt()),
+            fractional_like,
+        )
+    }
+
+    // The smallest and largest items ignore NaN, as
+    // ``min`` and ``max`` of two numbers do.
+    pub fn minimum_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| Ok(xs.iter().cloned().fold(f64::NAN, f64::min)),
+            item_like,
+        )
+    }
+
+    pub fn maximum_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| Ok(xs.iter().cloned().fold(f64::NAN, f64::max)),
+            item_like,
+        )
+    }
+
+    pub fn range_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| {
+                let min = xs.iter().cloned().fold(f64::NAN, f64::min);
+                let max = xs.iter().cloned().fold(f64::NAN, f64::max);
+                Ok(max - min)
+            },
+            item_like,
+        )
+    }
+
+    pub fn quantile_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::List(items), p] => match p.to_f64().unwrap() {
+                p if (0.0..=1.0).contains(&p) => {
+                    let statistic = |xs: &[f64]| Ok(statistics::quantile(&sorted(xs), p));
+                    with_samples(items, statistic, fractional_like)
+                }
+                _ => Failure(TypeError::DomainError),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn skewness_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| spread_moments(xs).map(|m| m.skewness()),
+            fractional_like,
+        )
+    }
+
+    pub fn kurtosis_impl(args: &[Value]) -> FunctionResult {
+        with_list(
+            args,
+            |xs| spread_moments(xs).map(|m| m.kurtosis()),
+            fractional_like,
+        )
+    }
+
+    // How to give a statistic the type of a list's items
+    type Retype = fn(f64, &Value) -> FunctionResult;
+
+    // Apply a statistic to the samples in a list, which
+    // is the only argument.
+    fn with_list<S>(args: &[Value], statistic: S, result: Retype) -> FunctionResult
+    where
+        S: Fn(&[f64]) -> Result<f64, TypeError>,
+    {
+        match args {
+            [Value::List(items)] => with_samples(items, statistic, result),
+            _ => unreachable!(),
+        }
+    }
+
+    // Apply a statistic to the samples in a list, giving
+    // a result of a type like that of the first item.
+    fn with_samples<S>(items: &[Value], statistic: S, result: Retype) -> FunctionResult
+    where
+        S: Fn(&[f64]) -> Result<f64, TypeError>,
+    {
+        match samples(items).and_then(|xs| statistic(&xs)) {
+            Ok(x) => result(x, &items[0]),
+            Err(e) => Failure(e),
+        }
+    }
+
+    // The items of a list as ``f64``s
+    fn samples(items: &[Value]) -> Result<Vec<f64>, TypeError> {
+        if items.is_empty() {
+            return Err(TypeError::DomainError);
+        }
+
+        items
+            .iter()
+            .map(|x| {
+                same_type(items, x)?;
+                x.to_f64().ok_or(TypeError::TypeMismatch)
+            })
+            .collect()
+    }
+
+    fn same_type(items: &[Value], x: &Value) -> Result<(), TypeError> {
+        if std::mem::discriminant(x) == std::mem::discriminant(&items[0]) {
+            Ok(())
+        } else {
+            Err(TypeError::TypeMismatch)
+        }
+    }
+
+    fn sample_moments(xs: &[f64]) -> Result<Moments, TypeError> {
+        if xs.len() < 2 {
+            Err(TypeError::DomainError)
+        } else {
+            Ok(Moments::of(xs))
+        }
+    }
+
+    // The moments of samples that are not all the same,
+    // by which the higher moments are standardized
+    fn spread_moments(xs: &[f64]) -> Result<Moments, TypeError> {
+        let moments = Moments::of(xs);
+        if moments.m2 == 0.0 {
+            Err(TypeError::DomainError)
+        } else {
+            Ok(moments)
+        }
+    }
+
+    fn sorted(xs: &[f64]) -> Vec<f64> {
+        let mut xs = xs.to_vec();
+        xs.sort_by(f64::total_cmp);
+        xs
+    }
+
+    // A result of the same type as ``like``, which is
+    // exact for integers computed from integers, as long
+    // as they fit.
+    fn item_like(x: f64, like: &Value) -> FunctionResult {
+        match like {
+            Value::Float32(_) => Scalar(Value::Float32(x as f32)),
+            _ if like.type_of().is_integer() => {
+                match BigInt::from_f64(x).filter(|n| n.to_f64() == Some(x)) {
+                    Some(n) => checked_integer(n, &like.type_of()),
+                    None => Failure(TypeError::Overflow),
+                }
+            }
+            _ => Scalar(Value::Float64(x)),
+        }
+    }
+
+    fn fractional_like(x: f64, like: &Value) -> FunctionResult {
+        match like {
+            Value::Float32(_) => Scalar(Value::Float32(x as f32)),
+            _ => Scalar(Value::Float64(x)),
+        }
+    }
+
+    // Fitting
+    //
+    // Each takes a list of x and a list of y, of the same
+    // length, and gives Float64 results.
+    pub fn linreg_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::List(xs), Value::List(ys)] => {
+                with_points(xs, ys, |xs, ys| match statistics::linear_fit(xs, ys) {
+                    Some(fit) => List(vec![
+                        Value::Float64(fit.slope),
+                        Value::Float64(fit.intercept),
+                        Value::Float64(fit.r_squared),
+                    ]),
+                    None => Failure(TypeError::DomainError),
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // The coefficients, from the constant term up
+    pub fn polyfit_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [_, _, Value::Int32(degree)] if *degree < 0 => Failure(TypeError::DomainError),
+            [Value::List(xs), Value::List(ys), Value::Int32(degree)] => {
+                with_points(xs, ys, |xs, ys| {
+                    match statistics::polynomial_fit(xs, ys, *degree as usize) {
+                        Ok(coeffs) => Scalar(Value::List(
+                            coeffs.into_iter().map(Value::Float64).collect(),
+                        )),
+                        Err(e) => Failure(e),
+                    }
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Fits y = a * exp(b * x), giving a and b, by fitting
+    // a line to ln(y), so every y must be positive.
+    pub fn expfit_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::List(xs), Value::List(ys)] => {
+                with_points(xs, ys, |xs, ys| log_fit(xs.to_vec(), positive_logs(ys)))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Fits y = a * x^b, giving a and b, by fitting a line
+    // to ln(y) against ln(x), so every x and y must be
+    // positive.
+    pub fn powerfit_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::List(xs), Value::List(ys)] => {
+                with_points(xs, ys, |xs, ys| match positive_logs(xs) {
+                    Some(ln_xs) => log_fit(ln_xs, positive_logs(ys)),
+                    None => Failure(TypeError::DomainError),
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Apply a fit to the points in a list of x and a list
+    // of y.
+    fn with_points<F>(xs: &[Value], ys: &[Value], fit: F) -> FunctionResult
+    where
+        F: Fn(&[f64], &[f64]) -> FunctionResult,
+    {
+        if xs.len() != ys.len() {
+            return Failure(TypeError::ShapeMismatch);
+        }
+
+        match (samples(xs), samples(ys)) {
+            (Ok(xs), Ok(ys)) => fit(&xs, &ys),
+            (Err(e), _) | (_, Err(e)) => Failure(e),
+        }
+    }
+
+    // Fit a line to ln(y), giving a, the exponential of
+    // the intercept, and b, the slope.
+    fn log_fit(xs: Vec<f64>, ln_ys: Option<Vec<f64>>) -> FunctionResult {
+        match ln_ys.and_then(|ln_ys| statistics::linear_fit(&xs, &ln_ys)) {
+            Some(fit) => List(vec![
+                Value::Float64(fit.intercept.exp()),
+                Value::Float64(fit.slope),
+            ]),
+            None => Failure(TypeError::DomainError),
+        }
+    }
+
+    fn positive_logs(xs: &[f64]) -> Option<Vec<f64>> {
+        xs.iter()
+            .map(|&x| if x > 0.0 { Some(x.ln()) } else { None })
+            .collect()
+    }
+
+    // Random numbers
+    pub fn random_impl(_: &[Value], generator: &mut Generator) -> FunctionResult {
+        Scalar(Value::Float64(generator.next_f64()))
+    }
+
+    // A whole number from the first argument to the
+    // second, inclusive
+    pub fn randint_impl(args: &[Value], generator: &mut Generator) -> FunctionResult {
+        match args {
+            [Value::Int32(lo), Value::Int32(hi)] if lo > hi => Failure(TypeError::DomainError),
+            [Value::Int32(lo), Value::Int32(hi)] => {
+                let span = (i64::from(*hi) - i64::from(*lo)) as u64 + 1;
+                let x = i64::from(*lo) + generator.below(span) as i64;
+                Scalar(Value::Int32(x as i32))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // A normal variate with the given mean and standard
+    // deviation
+    pub fn normal_impl(args: &[Value], generator: &mut Generator) -> FunctionResult {
+        match args {
+            [Value::Float64(_), Value::Float64(sd)] if sd.is_nan() || *sd < 0.0 => {
+                Failure(TypeError::DomainError)
+            }
+            [Value::Float64(mean), Value::Float64(sd)] => {
+                Scalar(Value::Float64(mean + sd * generator.normal()))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn shuffle_impl(args: &[Value], generator: &mut Generator) -> FunctionResult {
+        match args {
+            [Value::List(items)] => {
+                let mut items = items.clone();
+                generator.shuffle(&mut items);
+                Scalar(Value::List(items))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn choice_impl(args: &[Value], generator: &mut Generator) -> FunctionResult {
+        match args {
+            [Value::List(items)] if items.is_empty() => Failure(TypeError::DomainError),
+            [Value::List(items)] => {
+                Scalar(items[generator.below(items.len() as u64) as usize].clone())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Seeds are sign-extended, so that ``-1 seed`` and
+    // ``--seed -1`` agree.
+    pub fn seed_impl(args: &[Value], generator: &mut Generator) -> FunctionResult {
+        match args {
+            [Value::Int32(seed)] => {
+                *generator = Generator::new(i64::from(*seed) as u64);
+                List(Vec::new())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Linear algebra
+    pub fn transpose_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => Scalar(Value::Matrix(m.transpose())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn det_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => match m.det() {
+                Ok(det) => Scalar(det.into()),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn inv_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => match m.inv() {
+                Ok(inv) => Scalar(Value::Matrix(inv)),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn mmul_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(a), Value::Matrix(b)] => match a.mul(b) {
+                Ok(product) => Scalar(Value::Matrix(product)),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn solve_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(a), Value::Matrix(b)] => match a.solve(b) {
+                Ok(x) => Scalar(Value::Matrix(x)),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    // The order is checked before anything is
+    // allocated, as ``n * n`` entries soon run out of
+    // memory.
+    pub fn identity_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Int32(n)] if *n > 0 && *n as usize <= MAX_IDENTITY_ORDER => {
+                Scalar(Value::Matrix(Matrix::Float64(Dense::identity(*n as usize))))
+            }
+            [Value::Int32(_)] => Failure(TypeError::DomainError),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn trace_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => match m.trace() {
+                Ok(trace) => Scalar(trace.into()),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn rank_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => Scalar(Value::Int32(m.rank() as i32)),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn lu_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => match m.lu() {
+                Ok(factors) => List(factors.iter().cloned().map(Value::Matrix).collect()),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn eigen_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Matrix(m)] => match m.symmetric_eigen() {
+                Ok((values, vectors)) => List(vec![Value::Matrix(values), Value::Matrix(vectors)]),
+                Err(e) => Failure(e),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    // Maps
+    pub fn get_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Map(map), key] => match Key::from_value(key).and_then(|k| map.get(&k)) {
+                Some(val) => Scalar(val.clone()),
+                None => Failure(TypeError::MissingKey),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn put_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Map(map), key, val] => {
+                let mut map = map.clone();
+                map.insert(Key::from_value(key).unwrap(), val.clone());
+                Scalar(Value::Map(map))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn has_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Map(map), key] => Scalar(Value::Bool(
+                map.contains_key(&Key::from_value(key).unwrap()),
+            )),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn keys_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Map(map)] => Scalar(Value::List(map.keys().map(Key::to_value).collect())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn values_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Map(map)] => Scalar(Value::List(map.values().cloned().collect())),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn remove_impl(args: &[Value]) -> FunctionResult {
+        match args {
+            [Value::Map(map), key] => {
+                let mut map = map.clone();
+                map.remove(&Key::from_value(key).unwrap());
+                Scalar(Value::Map(map))
+            }
             _ => unreachable!(),
         }
     }
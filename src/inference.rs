@@ -0,0 +1,740 @@
+//! Static stack-effect inference
+//!
+//! This module provides an abstract interpreter, which
+//! walks a word or quotation over ``Type``s instead of
+//! ``Value``s. It computes the net stack effect without
+//! running anything, finds some type errors before the
+//! code is ever run, and finds the builtins whose
+//! arguments need not be checked when it does run.
+
+use super::{
+    functions::{AngleMode, BuiltinFun},
+    numerical::Method,
+    parser::ParsedToken,
+    units::Unit,
+    value::{Type, Value, INTEGER_SUFFIXES},
+    words::{Definition, Node, Quotation},
+};
+use std::{fmt, rc::Rc};
+
+/// The net effect of running some code, as the types
+/// it takes from the stack and the types it leaves
+///
+/// Both lists are ordered deepest first, like the
+/// parts of a ``Signiture``.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackEffect {
+    pub inputs: Vec<Type>,
+    pub outputs: Vec<Type>,
+}
+
+impl fmt::Display for StackEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for typ in &self.inputs {
+            write!(f, " {}", typ)?;
+        }
+        write!(f, " --")?;
+        for typ in &self.outputs {
+            write!(f, " {}", typ)?;
+        }
+        write!(f, " )")
+    }
+}
+
+/// Represents the type errors that inference can prove.
+#[derive(Debug)]
+pub enum InferenceError {
+    /// A word would be given a value of a type that it
+    /// can never accept.
+    Mismatch {
+        word: String,
+        expected: Type,
+        found: Type,
+    },
+
+    /// A word would take more values than the declared
+    /// stack effect provides.
+    TooManyInputs(String),
+
+    /// The code would leave a different number of values
+    /// than the declared stack effect.
+    WrongOutputs { declared: usize, inferred: usize },
+}
+
+impl fmt::Display for InferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferenceError::Mismatch {
+                word,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} expects {} but would be given {}",
+                word, expected, found
+            ),
+            InferenceError::TooManyInputs(word) => {
+                write!(f, "{} would take more values than declared", word)
+            }
+            InferenceError::WrongOutputs { declared, inferred } => write!(
+                f,
+                "would leave {} values but {} are declared",
+                inferred, declared
+            ),
+        }
+    }
+}
+
+/// What is known about a name, as returned by the
+/// ``lookup`` functions given to inference
+#[derive(Clone, Debug, PartialEq)]
+pub enum WordEffect {
+    /// The name refers to a user-defined word with a
+    /// known stack effect.
+    Known(StackEffect),
+
+    /// The name refers to a user-defined word, but its
+    /// stack effect is not known.
+    Unknown,
+
+    /// The name refers to a variable, which pushes its
+    /// value. Builtins are never shadowed by variables.
+    Variable,
+
+    /// The name has no user definition, though it may
+    /// still be a builtin.
+    Undefined,
+}
+
+/// Infer the stack effect of a word definition.
+///
+/// ``lookup`` describes any other name that the
+/// definition refers to, including the names of
+/// builtins, which user-defined words may shadow. A
+/// word that refers to itself uses its declared stack
+/// effect.
+///
+/// Returns ``Ok(None)`` if the effect cannot be known
+/// without running the word, such as when it calls an
+/// unknown word or a quotation from its inputs.
+pub fn infer_definition<F>(
+    def: &Definition,
+    lookup: F,
+) -> Result<Option<StackEffect>, InferenceError>
+where
+    F: Fn(&str) -> WordEffect,
+{
+    let declared = def.effect.as_ref().map(|effect| StackEffect {
+        inputs: effect.input_types(),
+        outputs: effect.output_types(),
+    });
+
+    let lookup = |name: &str| {
+        if name != def.name {
+            lookup(name)
+        } else if let Some(declared) = &declared {
+            WordEffect::Known(declared.clone())
+        } else {
+            WordEffect::Unknown
+        }
+    };
+
+    let mut machine = Machine::new(&lookup);
+    let mut frame = Vec::new();
+
+    if let (Some(effect), Some(declared)) = (&def.effect, &declared) {
+        // The declared inputs are the only inputs.
+        machine.fixed_inputs = true;
+        machine.inputs = declared.inputs.iter().rev().cloned().collect();
+
+        let count = declared.inputs.len();
+        let args = declared
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, typ)| AbstractValue {
+                typ: typ.clone(),
+                input: Some(count - idx - 1),
+                quotation: None,
+                certain: true,
+            });
+
+        if def.binds_locals {
+            let names = effect.inputs.iter().map(|(name, _)| name.clone());
+            frame = names.zip(args).collect();
+        } else {
+            machine.stack = args.collect();
+        }
+    }
+
+    match machine.run(&def.body, &frame) {
+        Ok(()) => {}
+        Err(Stop::Indeterminate) => return Ok(declared),
+        Err(Stop::Error(err)) => return Err(err),
+    }
+
+    if let Some(declared) = &declared {
+        if machine.stack.len() != declared.outputs.len() {
+            return Err(InferenceError::WrongOutputs {
+                declared: declared.outputs.len(),
+                inferred: machine.stack.len(),
+            });
+        }
+
+        for (val, expected) in machine.stack.iter_mut().zip(declared.outputs.iter()) {
+            if !overlaps(&val.typ, expected) {
+                return Err(InferenceError::Mismatch {
+                    word: def.name.clone(),
+                    expected: expected.clone(),
+                    found: val.typ.clone(),
+                });
+            }
+
+            val.typ = narrow(&val.typ, expected);
+        }
+    }
+
+    Ok(Some(machine.effect()))
+}
+
+/// Infer the stack effect of a quotation.
+///
+/// Any locals the quotation captured are treated as
+/// having the types of their captured values. See
+/// ``infer_definition`` for the meaning of ``lookup``
+/// and the result.
+pub fn infer_quotation<F>(
+    quot: &Quotation,
+    lookup: F,
+) -> Result<Option<StackEffect>, InferenceError>
+where
+    F: Fn(&str) -> WordEffect,
+{
+    let frame = quot
+        .captured
+        .iter()
+        .map(|(name, val)| (name.clone(), AbstractValue::of(val)))
+        .collect::<Vec<_>>();
+
+    let mut machine = Machine::new(&lookup);
+
+    match machine.run(&quot.body, &frame) {
+        Ok(()) => Ok(Some(machine.effect())),
+        Err(Stop::Indeterminate) => Ok(None),
+        Err(Stop::Error(err)) => Err(err),
+    }
+}
+
+/// Check that a quotation can be used as a function
+/// from ``input`` to ``output``, as ``name`` uses it:
+/// given one value of type ``input``, it must leave
+/// exactly one value, which may be of type ``output``.
+///
+/// Code that cannot be followed without running it is
+/// assumed to be correct. See ``infer_definition`` for
+/// the meaning of ``lookup``.
+pub fn check_function<F>(
+    quot: &Quotation,
+    name: &str,
+    input: &Type,
+    output: &Type,
+    lookup: F,
+) -> Result<(), InferenceError>
+where
+    F: Fn(&str) -> WordEffect,
+{
+    let frame = quot
+        .captured
+        .iter()
+        .map(|(name, val)| (name.clone(), AbstractValue::of(val)))
+        .collect::<Vec<_>>();
+
+    let machine = Machine::new(&lookup);
+
+    match machine.function(name, &quot.body, &frame, input, output) {
+        Ok(()) | Err(Stop::Indeterminate) => Ok(()),
+        Err(Stop::Error(err)) => Err(err),
+    }
+}
+
+/// Find the builtins in ``body`` whose arguments are
+/// certain to have the right types whenever it runs, so
+/// that they need not be checked.
+///
+/// ``inputs`` are the types of the values on the stack
+/// when the code starts, deepest first, and ``locals``
+/// the types of the locals it can see. Both must be
+/// checked before the code runs. Values that come from
+/// anywhere else, such as the stack below the inputs,
+/// variables, or user-defined words, which can be
+/// redefined, are never certain.
+///
+/// Returns a flag for each node of ``body``, which is
+/// ``true`` for the builtins that need no checks.
+pub fn proven_builtins<F>(
+    body: &[Node],
+    inputs: &[Type],
+    locals: &[(String, Type)],
+    lookup: F,
+) -> Vec<bool>
+where
+    F: Fn(&str) -> WordEffect,
+{
+    let certain = |typ: &Type| AbstractValue {
+        certain: true,
+        ..AbstractValue::new(typ.clone())
+    };
+
+    let frame = locals
+        .iter()
+        .map(|(name, typ)| (name.clone(), certain(typ)))
+        .collect::<Vec<_>>();
+
+    let mut machine = Machine::new(&lookup);
+    machine.stack = inputs.iter().map(certain).collect();
+
+    // The nodes before any stop have been seen exactly
+    // once, so what was proven about them still holds.
+    let _ = machine.run(body, &frame);
+
+    let mut proven = vec![false; body.len()];
+    for idx in machine.proven {
+        proven[idx] = true;
+    }
+
+    proven
+}
+
+/// What is statically known about one value on the
+/// stack
+#[derive(Clone)]
+struct AbstractValue {
+    typ: Type,
+
+    /// If the value is one of the inputs, its index in
+    /// ``Machine::inputs``
+    input: Option<usize>,
+
+    /// If the value is a literal quotation, its body
+    quotation: Option<Rc<Vec<Node>>>,
+
+    /// Whether the value is certain to have its type
+    /// when the code runs, rather than only being
+    /// expected to
+    certain: bool,
+}
+
+impl AbstractValue {
+    fn new(typ: Type) -> AbstractValue {
+        AbstractValue {
+            typ,
+            input: None,
+            quotation: None,
+            certain: true,
+        }
+    }
+
+    fn of(val: &Value) -> AbstractValue {
+        AbstractValue {
+            typ: val.type_of(),
+            input: None,
+            quotation: match val {
+                Value::Quotation(quot) if quot.captured.is_empty() => Some(Rc::clone(&quot.body)),
+                _ => None,
+            },
+            certain: true,
+        }
+    }
+
+    fn uncertain(typ: Type) -> AbstractValue {
+        AbstractValue {
+            certain: false,
+            ..AbstractValue::new(typ)
+        }
+    }
+}
+
+/// The reasons that inference can stop early
+enum Stop {
+    /// The effect depends on something only known at
+    /// run time.
+    Indeterminate,
+
+    Error(InferenceError),
+}
+
+type AbstractFrame = Vec<(String, AbstractValue)>;
+
+struct Machine<'a> {
+    stack: Vec<AbstractValue>,
+
+    /// The types of the inputs found so far, shallowest
+    /// first, since each newly found input is deeper
+    /// than the last
+    inputs: Vec<Type>,
+
+    /// Whether the inputs were declared, so that no
+    /// more may be found
+    fixed_inputs: bool,
+
+    /// The indices of the nodes at the top level of the
+    /// code being run that are builtins whose arguments
+    /// are certain to have the right types
+    proven: Vec<usize>,
+
+    /// How many literal quotations are being run inside
+    /// the code
+    depth: usize,
+
+    lookup: &'a dyn Fn(&str) -> WordEffect,
+}
+
+impl<'a> Machine<'a> {
+    fn new(lookup: &'a dyn Fn(&str) -> WordEffect) -> Machine<'a> {
+        Machine {
+            stack: Vec::new(),
+            inputs: Vec::new(),
+            fixed_inputs: false,
+            proven: Vec::new(),
+            depth: 0,
+            lookup,
+        }
+    }
+
+    fn effect(&self) -> StackEffect {
+        StackEffect {
+            inputs: self.inputs.iter().rev().cloned().collect(),
+            outputs: self.stack.iter().map(|val| val.typ.clone()).collect(),
+        }
+    }
+
+    fn run(&mut self, nodes: &[Node], frame: &AbstractFrame) -> Result<(), Stop> {
+        for (idx, node) in nodes.iter().enumerate() {
+            match node {
+                Node::Token(tok, parsed_tok) => {
+                    if self.token(tok, parsed_tok, frame)? && self.depth == 0 {
+                        self.proven.push(idx);
+                    }
+                }
+                Node::Local(name) => {
+                    let val = frame
+                        .iter()
+                        .find(|(local, _)| local == name)
+                        .map(|(_, val)| val.clone())
+                        .ok_or(Stop::Indeterminate)?;
+
+                    self.stack.push(val);
+                }
+                Node::Quotation(body) => self.stack.push(AbstractValue {
+                    quotation: Some(Rc::clone(body)),
+                    ..AbstractValue::new(Type::Quotation)
+                }),
+                // Definitions and directives only happen at
+                // the top level.
+                Node::Definition(_) | Node::Include(_) | Node::Use(_) | Node::Vocab(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // Run one token, returning ``true`` if it is a
+    // builtin whose arguments are certain to have the
+    // right types.
+    fn token(
+        &mut self,
+        tok: &str,
+        parsed_tok: &ParsedToken,
+        frame: &AbstractFrame,
+    ) -> Result<bool, Stop> {
+        match parsed_tok {
+            ParsedToken::Literal(val) => self.stack.push(AbstractValue::of(val)),
+            // These take the whole stack, however deep it is.
+            ParsedToken::Intrinsic(fun) if fun.gathers_stack => return Err(Stop::Indeterminate),
+            // Only unqualified builtins can be shadowed.
+            ParsedToken::Intrinsic(fun) if tok == fun.name => match (self.lookup)(tok) {
+                WordEffect::Known(effect) => self.apply_word(tok, &effect)?,
+                WordEffect::Unknown => return Err(Stop::Indeterminate),
+                WordEffect::Variable | WordEffect::Undefined => return self.apply_builtin(fun),
+            },
+            ParsedToken::Intrinsic(fun) => return self.apply_builtin(fun),
+            ParsedToken::Unit(unit) => {
+                let arg = self.pop(&Type::Number, tok)?;
+                self.stack
+                    .push(AbstractValue::new(unit_result(&arg.typ, unit)));
+            }
+            ParsedToken::Conversion(unit) => {
+                let dim = Type::Dimension(unit.dimension());
+                self.pop(&dim, tok)?;
+                self.stack.push(AbstractValue::new(dim));
+            }
+            ParsedToken::Store(_) => {
+                self.pop(&Type::Any, tok)?;
+            }
+            ParsedToken::Recall(_) => self.stack.push(AbstractValue::uncertain(Type::Any)),
+            ParsedToken::Word(name) if name == "call" => {
+                let quot = self.pop(&Type::Quotation, name)?;
+                let body = quot.quotation.ok_or(Stop::Indeterminate)?;
+
+                self.depth += 1;
+                let result = self.run(&body, frame);
+                self.depth -= 1;
+                result?;
+            }
+            ParsedToken::Word(name) if AngleMode::from_name(name).is_some() => {}
+            ParsedToken::Word(name) if Method::from_name(name).is_some() => {
+                for _ in 0..Method::from_name(name).unwrap().points() {
+                    self.pop(&Type::Fractional, name)?;
+                }
+
+                let quot = self.pop(&Type::Quotation, name)?;
+                if let Some(body) = quot.quotation {
+                    let fractional = Type::Fractional;
+                    match self.function(name, &body, frame, &fractional, &fractional) {
+                        Ok(()) | Err(Stop::Indeterminate) => {}
+                        Err(err) => return Err(err),
+                    }
+                }
+
+                self.stack.push(AbstractValue::new(Type::Fractional));
+            }
+            ParsedToken::Word(name) if name == "tolerance" => {
+                self.pop(&Type::Fractional, name)?;
+            }
+            ParsedToken::Word(name) if name == "iterations" => {
+                self.pop(&Type::Int32, name)?;
+            }
+            ParsedToken::Word(_) | ParsedToken::Qualified(_, _) => match (self.lookup)(tok) {
+                WordEffect::Known(effect) => self.apply_word(tok, &effect)?,
+                WordEffect::Variable => self.stack.push(AbstractValue::uncertain(Type::Any)),
+                _ => return Err(Stop::Indeterminate),
+            },
+            _ => unreachable!(), // Structural tokens are handled by ``words::compile``.
+        }
+
+        Ok(false)
+    }
+
+    // Apply a builtin, returning ``true`` if its
+    // arguments are certain to have the right types.
+    fn apply(&mut self, word: &str, inputs: &[Type], outputs: &[Type]) -> Result<bool, Stop> {
+        let proven = self.proves(inputs);
+
+        for typ in inputs.iter().rev() {
+            self.pop(typ, word)?;
+        }
+
+        for typ in outputs {
+            self.stack.push(AbstractValue::new(typ.clone()));
+        }
+
+        Ok(proven)
+    }
+
+    // Apply a builtin. One with overloads can only be
+    // followed when the stack is certain to match one of
+    // them.
+    fn apply_builtin(&mut self, fun: &BuiltinFun) -> Result<bool, Stop> {
+        let fun = if fun.overloads.is_empty() {
+            fun
+        } else {
+            std::iter::once(fun)
+                .chain(&fun.overloads)
+                .find(|fun| self.proves(fun.signiture.0))
+                .ok_or(Stop::Indeterminate)?
+        };
+
+        self.apply(&fun.name, fun.signiture.0, fun.signiture.1)
+    }
+
+    // Whether the top of the stack is certain to have
+    // the types of ``inputs``
+    fn proves(&self, inputs: &[Type]) -> bool {
+        self.stack.len() >= inputs.len()
+            && self
+                .stack
+                .iter()
+                .rev()
+                .zip(inputs.iter().rev())
+                .all(|(val, typ)| val.certain && subset(&val.typ, typ))
+    }
+
+    // Apply a user-defined word or variable, whose
+    // results are never certain.
+    fn apply_word(&mut self, word: &str, effect: &StackEffect) -> Result<(), Stop> {
+        for typ in effect.inputs.iter().rev() {
+            self.pop(typ, word)?;
+        }
+
+        for typ in &effect.outputs {
+            self.stack.push(AbstractValue::uncertain(typ.clone()));
+        }
+
+        Ok(())
+    }
+
+    // Run the body of a literal quotation that ``name``
+    // uses as a function from ``input`` to ``output``,
+    // apart from the code around it.
+    fn function(
+        &self,
+        name: &str,
+        body: &[Node],
+        frame: &AbstractFrame,
+        input: &Type,
+        output: &Type,
+    ) -> Result<(), Stop> {
+        let mut machine = Machine::new(self.lookup);
+        machine.fixed_inputs = true;
+        machine.inputs = vec![input.clone()];
+        machine.stack = vec![AbstractValue {
+            input: Some(0),
+            ..AbstractValue::uncertain(input.clone())
+        }];
+        machine.depth = self.depth + 1;
+        machine.run(body, frame)?;
+
+        if machine.stack.len() != 1 {
+            return Err(Stop::Error(InferenceError::WrongOutputs {
+                declared: 1,
+                inferred: machine.stack.len(),
+            }));
+        }
+
+        let result = &machine.stack[0];
+        if !overlaps(&result.typ, output) {
+            return Err(Stop::Error(InferenceError::Mismatch {
+                word: name.to_string(),
+                expected: output.clone(),
+                found: result.typ.clone(),
+            }));
+        }
+
+        Ok(())
+    }
+
+    // Take a value that a word expects to be of type
+    // ``expected``, which narrows what is known about
+    // that value.
+    fn pop(&mut self, expected: &Type, word: &str) -> Result<AbstractValue, Stop> {
+        let val = match self.stack.pop() {
+            Some(val) => val,
+            None if self.fixed_inputs => {
+                return Err(Stop::Error(InferenceError::TooManyInputs(word.to_string())));
+            }
+            None => {
+                self.inputs.push(expected.clone());
+
+                return Ok(AbstractValue {
+                    input: Some(self.inputs.len() - 1),
+                    ..AbstractValue::uncertain(expected.clone())
+                });
+            }
+        };
+
+        if !overlaps(&val.typ, expected) {
+            return Err(Stop::Error(InferenceError::Mismatch {
+                word: word.to_string(),
+                expected: expected.clone(),
+                found: val.typ,
+            }));
+        }
+
+        if let Some(idx) = val.input {
+            self.inputs[idx] = narrow(&self.inputs[idx], expected);
+        }
+
+        Ok(AbstractValue {
+            typ: narrow(&val.typ, expected),
+            ..val
+        })
+    }
+}
+
+// The type that results from attaching a unit to
+// a value of the given type.
+fn unit_result(arg: &Type, unit: &Unit) -> Type {
+    let dim = match arg {
+        Type::Dimension(dim) => match dim.mul(&unit.dimension()) {
+            Ok(dim) => dim,
+            // Attaching the unit will fail.
+            Err(_) => return Type::Number,
+        },
+        Type::Float32 | Type::Float64 | Type::Fractional | Type::Integer => unit.dimension(),
+        typ if typ.is_integer() => unit.dimension(),
+        _ => return Type::Number,
+    };
+
+    if dim.is_none() {
+        Type::Float64
+    } else {
+        Type::Dimension(dim)
+    }
+}
+
+// The most specific types that a general type stands
+// for. ``Any`` is handled separately.
+fn members(typ: &Type) -> Vec<Type> {
+    match typ {
+        Type::Fractional => vec![Type::Float32, Type::Float64],
+        Type::Key => vec![Type::Str, Type::Symbol],
+        Type::Integer => INTEGER_SUFFIXES
+            .iter()
+            .map(|(_, typ)| typ.clone())
+            .collect(),
+        Type::Number => {
+            let mut numbers = members(&Type::Integer);
+            numbers.extend(vec![Type::Float32, Type::Float64, Type::Quantity]);
+            numbers
+        }
+        _ => vec![typ.clone()],
+    }
+}
+
+// Returns ``true`` if some value has both types.
+fn overlaps(a: &Type, b: &Type) -> bool {
+    if *a == Type::Any || *b == Type::Any {
+        return true;
+    }
+
+    let compatible = |x: &Type, y: &Type| match (x, y) {
+        (Type::Quantity, Type::Dimension(_)) | (Type::Dimension(_), Type::Quantity) => true,
+        _ => x == y,
+    };
+
+    let b_members = members(b);
+    members(a)
+        .iter()
+        .any(|x| b_members.iter().any(|y| compatible(x, y)))
+}
+
+// Returns ``true`` if every value of type ``a`` is
+// also of type ``b``.
+fn subset(a: &Type, b: &Type) -> bool {
+    if *b == Type::Any {
+        return true;
+    } else if *a == Type::Any {
+        return false;
+    }
+
+    let contains = |y: &Type, x: &Type| match (y, x) {
+        (Type::Quantity, Type::Dimension(_)) => true,
+        _ => x == y,
+    };
+
+    let b_members = members(b);
+    members(a)
+        .iter()
+        .all(|x| b_members.iter().any(|y| contains(y, x)))
+}
+
+// Combine what is known about a value of type ``a``
+// with the knowledge that it must be a ``b``.
+fn narrow(a: &Type, b: &Type) -> Type {
+    if subset(b, a) {
+        b.clone()
+    } else {
+        a.clone()
+    }
+}
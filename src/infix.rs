@@ -0,0 +1,272 @@
+//! Infix expressions
+//!
+//! Code written as ``$( 2 * (3 + 4) ^ 2 )`` uses the
+//! conventional notation, with operator precedence and
+//! calls such as ``sin(x)``. This module translates an
+//! expression to the postfix tokens that it stands for,
+//! here ``2 3 4 + 2 pow *``, which then run like any
+//! other code, with the same builtins and type checks.
+//!
+//! From loosest to tightest, the operators are ``+``
+//! and ``-``, then ``*`` and ``/``, then negation, and
+//! then ``^``, which groups to the right, so ``-2^2`` is
+//! ``-(2^2)``. A call pushes its arguments in order, so
+//! ``logn(x, 2.0)`` becomes ``x 2.0 logn``. A name that
+//! is not called, such as a variable, is pushed as is.
+
+/// Translate an infix expression to postfix tokens.
+///
+/// Returns a description of the problem if the
+/// expression is malformed. The tokens themselves are
+/// not checked, so a misspelled name is only found
+/// when the tokens are parsed.
+pub fn translate(expr: &str) -> Result<Vec<String>, String> {
+    let mut parser = Parser {
+        lexemes: lex(expr)?,
+        pos: 0,
+        output: Vec::new(),
+    };
+
+    parser.sum()?;
+
+    match parser.peek() {
+        None => Ok(parser.output),
+        Some(lexeme) => Err(format!("unexpected {} in infix expression", lexeme)),
+    }
+}
+
+/// A piece of an infix expression
+#[derive(Clone, Copy, PartialEq)]
+enum Lexeme<'a> {
+    /// A literal, such as a number or string
+    Literal(&'a str),
+
+    /// A name, which may be called
+    Name(&'a str),
+
+    /// An operator, a parenthesis, or a comma
+    Punct(char),
+}
+
+impl<'a> std::fmt::Display for Lexeme<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lexeme::Literal(text) | Lexeme::Name(text) => write!(f, "{}", text),
+            Lexeme::Punct(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+// Names may be qualified, as in ``stats:mean``, and may
+// use the punctuation found in the names of words such
+// as ``deg>rad`` or ``prime?``.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || "_:.>?".contains(c)
+}
+
+fn lex(expr: &str) -> Result<Vec<Lexeme<'_>>, String> {
+    let mut lexemes = Vec::new();
+    let mut rest = expr.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = if "+-*/^(),".contains(c) {
+            lexemes.push(Lexeme::Punct(c));
+            1
+        } else if c == '"' {
+            let len = rest[1..]
+                .find('"')
+                .map(|end| end + 2)
+                .ok_or("missing \" in infix expression")?;
+
+            lexemes.push(Lexeme::Literal(&rest[..len]));
+            len
+        } else if c.is_ascii_digit() || c == '.' {
+            let len = number_len(rest);
+            lexemes.push(Lexeme::Literal(&rest[..len]));
+            len
+        } else if c.is_alphabetic() || c == '_' || c == '\'' {
+            let len = rest[1..]
+                .find(|c| !is_name_char(c))
+                .map_or(rest.len(), |end| end + 1);
+            let text = &rest[..len];
+
+            lexemes.push(if c == '\'' || text == "true" || text == "false" {
+                Lexeme::Literal(text)
+            } else {
+                Lexeme::Name(text)
+            });
+            len
+        } else {
+            return Err(format!("unexpected {} in infix expression", c));
+        };
+
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(lexemes)
+}
+
+// The length of the number at the start of ``text``,
+// which runs until something that cannot be part of a
+// number literal. A sign only continues a number after
+// the ``e`` of a decimal exponent.
+fn number_len(text: &str) -> usize {
+    let radix = text.starts_with("0x") || text.starts_with("0b") || text.starts_with("0o");
+    let mut prev = ' ';
+
+    for (idx, c) in text.char_indices() {
+        let exponent_sign = (c == '+' || c == '-') && (prev == 'e' || prev == 'E') && !radix;
+
+        if !(c.is_ascii_alphanumeric() || c == '_' || c == '.' || exponent_sign) {
+            return idx;
+        }
+
+        prev = c;
+    }
+
+    text.len()
+}
+
+struct Parser<'a> {
+    lexemes: Vec<Lexeme<'a>>,
+    pos: usize,
+
+    /// The postfix tokens translated so far
+    output: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Lexeme<'a>> {
+        self.lexemes.get(self.pos).copied()
+    }
+
+    // Take the next lexeme if it is the given
+    // punctuation.
+    fn accept(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(Lexeme::Punct(c));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.accept(c) {
+            Ok(())
+        } else {
+            Err(format!("missing {} in infix expression", c))
+        }
+    }
+
+    fn emit(&mut self, token: &str) {
+        self.output.push(token.to_string());
+    }
+
+    // sum := product (("+" | "-") product)*
+    fn sum(&mut self) -> Result<(), String> {
+        self.product()?;
+
+        loop {
+            if self.accept('+') {
+                self.product()?;
+                self.emit("+");
+            } else if self.accept('-') {
+                self.product()?;
+                self.emit("-");
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // product := negation (("*" | "/") negation)*
+    fn product(&mut self) -> Result<(), String> {
+        self.negation()?;
+
+        loop {
+            if self.accept('*') {
+                self.negation()?;
+                self.emit("*");
+            } else if self.accept('/') {
+                self.negation()?;
+                self.emit("/");
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // negation := "-" negation | power
+    fn negation(&mut self) -> Result<(), String> {
+        if !self.accept('-') {
+            return self.power();
+        }
+
+        let start = self.output.len();
+        self.negation()?;
+
+        // A negated number is written as a negative
+        // literal, rather than negated when it runs.
+        let number = &self.output[start..];
+        match number {
+            [number] if number.starts_with(|c: char| c.is_ascii_digit() || c == '.') => {
+                self.output[start] = format!("-{}", number);
+            }
+            _ => self.emit("negate"),
+        }
+
+        Ok(())
+    }
+
+    // power := atom ("^" negation)?
+    fn power(&mut self) -> Result<(), String> {
+        self.atom()?;
+
+        if self.accept('^') {
+            self.negation()?;
+            self.emit("pow");
+        }
+
+        Ok(())
+    }
+
+    // atom := literal | name | name "(" arguments ")" | "(" sum ")"
+    fn atom(&mut self) -> Result<(), String> {
+        let lexeme = self.peek().ok_or("missing value in infix expression")?;
+        self.pos += 1;
+
+        match lexeme {
+            Lexeme::Literal(text) => self.emit(text),
+            Lexeme::Name(name) => {
+                if self.accept('(') {
+                    self.arguments()?;
+                }
+
+                self.emit(name);
+            }
+            Lexeme::Punct('(') => {
+                self.sum()?;
+                self.expect(')')?;
+            }
+            Lexeme::Punct(c) => return Err(format!("unexpected {} in infix expression", c)),
+        }
+
+        Ok(())
+    }
+
+    // arguments := (sum ("," sum)*)?
+    //
+    // The opening parenthesis has been taken already.
+    fn arguments(&mut self) -> Result<(), String> {
+        if self.accept(')') {
+            return Ok(());
+        }
+
+        self.sum()?;
+        while self.accept(',') {
+            self.sum()?;
+        }
+
+        self.expect(')')
+    }
+}
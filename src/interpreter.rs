@@ -0,0 +1,1146 @@
+//! Evaluation of parsed input
+//!
+//! This module provides the ``Interpreter``, which owns
+//! the calculator's stack, named variables, and user-
+//! defined words, and runs parsed tokens against them.
+//!
+//! User-defined words are grouped into vocabularies.
+//! Names are looked up in the vocabulary that the code
+//! using them belongs to, then in the vocabularies it
+//! uses, with the most recent ``use`` first, and then in
+//! the ``builtins`` vocabulary.
+//!
+//! Code is compiled before it runs, and names are
+//! resolved when it is compiled. Redefining a word
+//! changes what existing code that calls it runs, but
+//! a new word that shadows a name does not change what
+//! existing code refers to. Names that are not defined
+//! when the code is compiled are looked up each time
+//! they run.
+//!
+//! The trigonometric builtins measure angles in the
+//! interpreter's angle mode, which the words ``rad``,
+//! ``deg``, and ``grad`` set. Their implementations use
+//! radians, so angles are converted on the way in or
+//! out.
+//!
+//! The ``stack-`` statistics builtins, such as
+//! ``stack-mean``, take every value on the stack beneath
+//! their other arguments, gathered into a list.
+//!
+//! The random number builtins draw from a generator
+//! owned by the interpreter. It starts from a different
+//! seed each time, unless it is given one with ``seed``
+//! or ``reseed``.
+//!
+//! The numerical methods, such as ``bisect``,
+//! ``minimize``, and ``integrate``, run a quotation as
+//! a function of one ``Fractional`` number. They work to
+//! the tolerance and iteration limit that the words
+//! ``tolerance`` and ``iterations`` set.
+
+use super::{
+    bytecode::{Code, Instr, Word, WordRef},
+    functions::{AngleMode, Angles, BuiltinFun, BUILTINS, INTRINSIC_FNS},
+    inference::{self, InferenceError, StackEffect, WordEffect},
+    numerical::{self, Failure, Limits, Method},
+    parser::{self, ParsedToken},
+    random::Generator,
+    script::{self, ScriptError},
+    stack::Stack,
+    typecheck::{self, TypeError},
+    value::{Type, Value},
+    words::{self, Definition, Frame, Node, Quotation},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// The deepest that calls to words and quotations
+/// may nest before evaluation is abandoned.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// The vocabulary that words are defined in unless
+/// another is chosen with ``vocab``.
+pub const DEFAULT_VOCABULARY: &str = "user";
+
+/// The vocabulary that the prelude defines its words
+/// in, which the default vocabulary uses.
+pub const PRELUDE_VOCABULARY: &str = "prelude";
+
+/// The source of the prelude, a library of words that
+/// are written in the language itself
+const PRELUDE: &str = include_str!("prelude.cw");
+
+/// Represents the ways that evaluating input can fail.
+#[derive(Debug)]
+pub enum EvalError {
+    /// Arises when a token could not be parsed. Holds
+    /// the offending token.
+    BadToken(String),
+
+    /// Arises when a word fails to type check. Holds the
+    /// error and a description of the word.
+    Type(TypeError, String),
+
+    /// Arises when a name refers to nothing that has been
+    /// defined. Holds the name.
+    UnknownWord(String),
+
+    /// Arises when tokens are not arranged properly, such
+    /// as an unclosed quotation. Holds a description.
+    Syntax(String),
+
+    /// Arises when words or quotations call each other
+    /// too deeply. Holds the name of the innermost word.
+    TooDeep(String),
+
+    /// Arises when a definition can be shown not to type
+    /// check before it runs. Holds the name of the word
+    /// and the error.
+    IllTyped(String, InferenceError),
+
+    /// Arises when ``use`` names a vocabulary that does
+    /// not exist. Holds the name.
+    UnknownVocabulary(String),
+
+    /// Arises when a file cannot be included. Holds the
+    /// path and a description of the problem.
+    Include(String, String),
+
+    /// Arises when an included file fails to run. Holds
+    /// the path and the error.
+    InFile(String, Box<ScriptError>),
+
+    /// Arises when a quotation given to a numerical
+    /// method is not a function of one number. Holds
+    /// the name of the method and the error.
+    IllTypedFunction(String, InferenceError),
+
+    /// Arises when a numerical method does not find an
+    /// answer within its iteration limit. Holds the name
+    /// of the method and the limit.
+    NoConvergence(String, u32),
+}
+
+impl EvalError {
+    /// Returns ``true`` if the error was found while
+    /// parsing, before anything was evaluated.
+    pub fn is_parse_error(&self) -> bool {
+        match self {
+            EvalError::InFile(_, err) => err.error.is_parse_error(),
+            _ => matches!(self, EvalError::BadToken(_) | EvalError::Syntax(_)),
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::BadToken(tok) => write!(f, "Invalid token: {}", tok),
+            EvalError::Type(err, word) => write!(f, "{:?}: {}", err, word),
+            EvalError::UnknownWord(name) => write!(f, "Unknown word: {}", name),
+            EvalError::Syntax(msg) => write!(f, "Syntax error: {}", msg),
+            EvalError::TooDeep(name) => write!(f, "Calls nested too deeply: {}", name),
+            EvalError::IllTyped(name, err) => {
+                write!(f, "Ill-typed definition of {}: {}", name, err)
+            }
+            EvalError::UnknownVocabulary(name) => write!(f, "Unknown vocabulary: {}", name),
+            EvalError::Include(path, msg) => write!(f, "Cannot include {}: {}", path, msg),
+            EvalError::InFile(path, err) => write!(f, "In {}, {}", path, err),
+            EvalError::IllTypedFunction(name, err) => {
+                write!(f, "Ill-typed function for {}: {}", name, err)
+            }
+            EvalError::NoConvergence(name, iterations) => write!(
+                f,
+                "{} did not converge within {} iterations",
+                name, iterations
+            ),
+        }
+    }
+}
+
+/// The state of a calculator session
+///
+/// This holds the ``Stack``, the values of any
+/// variables (or registers) that have been stored,
+/// and any words that have been defined.
+pub struct Interpreter {
+    stack: Stack,
+    variables: BTreeMap<String, Value>,
+    vocabularies: HashMap<Rc<str>, Vocabulary>,
+
+    /// The vocabulary that names are looked up in, and
+    /// that new words are defined in
+    current: Rc<str>,
+
+    /// The directories that ``include`` searches for
+    /// files with relative paths
+    search_path: Vec<PathBuf>,
+
+    /// The files being included, innermost last
+    including: Vec<PathBuf>,
+
+    /// The names of the words defined since the last
+    /// call to ``take_new_words``
+    new_words: Vec<String>,
+
+    /// The warnings given since the last call to
+    /// ``take_warnings``
+    warnings: Vec<String>,
+
+    /// How many definitions have been made, used to
+    /// keep track of the order of the dictionary
+    definition_count: usize,
+
+    /// How many word or quotation calls are in progress
+    depth: usize,
+
+    /// The unit that the trigonometric builtins measure
+    /// angles in
+    angle_mode: AngleMode,
+
+    /// Where the random number builtins draw from
+    generator: Generator,
+
+    /// How closely, and for how long, the numerical
+    /// methods work
+    limits: Limits,
+
+    /// Whether the words of the prelude were defined
+    /// when the interpreter was made
+    has_prelude: bool,
+}
+
+/// A named group of user-defined words
+#[derive(Default)]
+struct Vocabulary {
+    words: HashMap<String, UserWord>,
+
+    /// The vocabularies whose words are visible here
+    /// without qualification, most recently used last
+    using: Vec<Rc<str>>,
+}
+
+/// A word in the dictionary, along with its inferred
+/// stack effect, if that could be found
+struct UserWord {
+    def: Rc<Definition>,
+    word: WordRef,
+    effect: Option<StackEffect>,
+
+    /// The value of ``definition_count`` when the word
+    /// was defined
+    order: usize,
+}
+
+impl Interpreter {
+    /// Create a new ``Interpreter`` with an empty
+    /// stack and no variables, and with the words of
+    /// the prelude defined.
+    pub fn new() -> Interpreter {
+        let mut interp = Interpreter::without_prelude();
+
+        let saved = interp.save_scope();
+        script::eval_source(&mut interp, PRELUDE).expect("the prelude should run");
+        interp.restore_scope(saved);
+
+        interp
+            .use_vocabulary(PRELUDE_VOCABULARY)
+            .expect("the prelude should define its vocabulary");
+
+        interp.take_new_words();
+        interp.take_warnings();
+        interp.has_prelude = true;
+        interp
+    }
+
+    /// Create a new ``Interpreter`` with an empty
+    /// stack, no variables, and no user-defined words.
+    pub fn without_prelude() -> Interpreter {
+        let current: Rc<str> = Rc::from(DEFAULT_VOCABULARY);
+
+        let mut vocabularies = HashMap::new();
+        vocabularies.insert(Rc::clone(&current), Vocabulary::default());
+
+        Interpreter {
+            stack: Stack::new(),
+            variables: BTreeMap::new(),
+            vocabularies,
+            current,
+            search_path: vec![PathBuf::from(".")],
+            including: Vec::new(),
+            new_words: Vec::new(),
+            warnings: Vec::new(),
+            definition_count: 0,
+            depth: 0,
+            angle_mode: AngleMode::default(),
+            generator: Generator::from_entropy(),
+            limits: Limits::default(),
+            has_prelude: false,
+        }
+    }
+
+    /// Return the stack.
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// Return the stack for modification.
+    pub fn stack_mut(&mut self) -> &mut Stack {
+        &mut self.stack
+    }
+
+    /// Return the directories that ``include`` searches,
+    /// in order, for modification.
+    pub fn search_path_mut(&mut self) -> &mut Vec<PathBuf> {
+        &mut self.search_path
+    }
+
+    /// Returns an iterator over the variables and their
+    /// values, sorted by name.
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.variables
+            .iter()
+            .map(|(name, val)| (name.as_str(), val))
+    }
+
+    /// Returns the unit that angles are measured in.
+    pub fn angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    /// Change the unit that angles are measured in.
+    pub fn set_angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    /// Returns the tolerance and iteration limit of the
+    /// numerical methods.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Change the tolerance and iteration limit of the
+    /// numerical methods.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Restart the random number generator from a seed,
+    /// as the word ``seed`` does.
+    pub fn reseed(&mut self, seed: u64) {
+        self.generator = Generator::new(seed);
+    }
+
+    /// Returns ``true`` if the interpreter was made with
+    /// the words of the prelude.
+    pub fn has_prelude(&self) -> bool {
+        self.has_prelude
+    }
+
+    /// Returns the name of the vocabulary that new words
+    /// are defined in.
+    pub fn current_vocabulary(&self) -> &str {
+        &self.current
+    }
+
+    /// Returns the names of the vocabularies, sorted,
+    /// along with the vocabularies each one uses.
+    pub fn vocabularies(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut vocabs = self
+            .vocabularies
+            .iter()
+            .map(|(name, vocab)| {
+                let using = vocab.using.iter().map(|used| used.as_ref()).collect();
+                (name.as_ref(), using)
+            })
+            .collect::<Vec<_>>();
+
+        vocabs.sort();
+        vocabs
+    }
+
+    /// Returns the user-defined words, along with their
+    /// vocabularies, in the order they were last defined.
+    pub fn definitions(&self) -> Vec<(&str, &Definition)> {
+        let mut words = self
+            .vocabularies
+            .iter()
+            .flat_map(|(name, vocab)| vocab.words.values().map(move |word| (name, word)))
+            .collect::<Vec<_>>();
+
+        words.sort_by_key(|(_, word)| word.order);
+        words
+            .into_iter()
+            .map(|(name, word)| (name.as_ref(), word.def.as_ref()))
+            .collect()
+    }
+
+    /// Returns the stack effect of a user-defined word or
+    /// variable, if it is known without running it.
+    pub fn word_effect(&self, name: &str) -> Option<StackEffect> {
+        match self.effect_of(name) {
+            WordEffect::Known(effect) => Some(effect),
+            WordEffect::Variable => Some(StackEffect {
+                inputs: Vec::new(),
+                outputs: vec![Type::Any],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of the words defined since the
+    /// last call, in the order they were defined.
+    pub fn take_new_words(&mut self) -> Vec<String> {
+        mem::take(&mut self.new_words)
+    }
+
+    /// Returns the warnings given since the last call,
+    /// such as when a name is defined in two places.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        mem::take(&mut self.warnings)
+    }
+
+    /// Parse and evaluate a single line of input.
+    pub fn eval_line(&mut self, line: &str) -> Result<(), EvalError> {
+        self.eval_tokens(parser::parse_line(line))
+    }
+
+    /// Evaluate tokens that have already been parsed.
+    ///
+    /// If any token is invalid, or the tokens do not
+    /// nest properly, nothing is evaluated. Otherwise,
+    /// tokens are evaluated in order until one fails,
+    /// and the tokens before it keep their effect on
+    /// the stack.
+    pub fn eval_tokens<'a, I>(&mut self, tokens: I) -> Result<(), EvalError>
+    where
+        I: IntoIterator<Item = (&'a str, ParsedToken)>,
+    {
+        let nodes = words::compile(tokens)?;
+        let mut rest = nodes.as_slice();
+
+        // Definitions and directives take effect before
+        // the code after them is compiled.
+        while !rest.is_empty() {
+            let end = rest.iter().position(is_top_level).unwrap_or(rest.len());
+            let (nodes, tail) = rest.split_at(end);
+
+            if !nodes.is_empty() {
+                let code = self.compile(nodes, &[], &[], None);
+                self.run(&code, &Frame::new())?;
+            }
+
+            match tail.first() {
+                Some(Node::Definition(def)) => self.define(def)?,
+                Some(Node::Include(path)) => self.include(path)?,
+                Some(Node::Use(name)) => self.use_vocabulary(name)?,
+                Some(Node::Vocab(name)) => self.set_vocabulary(name),
+                _ => {}
+            }
+
+            rest = tail.get(1..).unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    /// Run a file, adding its words to the dictionary.
+    ///
+    /// A relative path is looked for next to the file
+    /// that is including it, if any, and then in each
+    /// directory of the search path. The file starts in
+    /// the current vocabulary, and any ``vocab`` or
+    /// ``use`` in it lasts until the end of the file.
+    pub fn include(&mut self, path: &str) -> Result<(), EvalError> {
+        let include_err = |msg: &str| EvalError::Include(path.to_string(), msg.to_string());
+
+        let file = self
+            .find_file(Path::new(path))
+            .ok_or_else(|| include_err("file not found"))?;
+
+        let file = file
+            .canonicalize()
+            .map_err(|err| include_err(&err.to_string()))?;
+
+        if self.including.contains(&file) {
+            return Err(include_err("file includes itself"));
+        }
+
+        let source = fs::read_to_string(&file).map_err(|err| include_err(&err.to_string()))?;
+
+        self.including.push(file);
+        let saved = self.save_scope();
+        let result = script::eval_source(self, &source);
+        self.restore_scope(saved);
+        self.including.pop();
+
+        result.map_err(|err| EvalError::InFile(path.to_string(), Box::new(err)))
+    }
+
+    fn find_file(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_absolute() {
+            return Some(path.to_path_buf()).filter(|file| file.is_file());
+        }
+
+        let including_dir = self.including.last().and_then(|file| file.parent());
+
+        including_dir
+            .into_iter()
+            .chain(self.search_path.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(path))
+            .find(|file| file.is_file())
+    }
+
+    // Remember the current vocabulary and what it uses,
+    // so that an included file cannot change them.
+    fn save_scope(&self) -> (Rc<str>, Vec<Rc<str>>) {
+        let using = self.vocabularies[&self.current].using.clone();
+        (Rc::clone(&self.current), using)
+    }
+
+    fn restore_scope(&mut self, (current, using): (Rc<str>, Vec<Rc<str>>)) {
+        if let Some(vocab) = self.vocabularies.get_mut(&current) {
+            vocab.using = using;
+        }
+
+        self.current = current;
+    }
+
+    // Compile code that belongs to the current
+    // vocabulary. ``inputs`` are the types of the values
+    // that are checked to be on the stack when the code
+    // starts, ``locals`` the names and types of the
+    // locals it can see, and ``this`` the word being
+    // defined, if any, which the code may call.
+    fn compile(
+        &self,
+        nodes: &[Node],
+        inputs: &[Type],
+        locals: &[(String, Type)],
+        this: Option<(&str, &WordRef)>,
+    ) -> Code {
+        let lookup = |name: &str| match this {
+            Some((this_name, _)) if name == this_name => WordEffect::Unknown,
+            _ => self.effect_of(name),
+        };
+
+        let proven = inference::proven_builtins(nodes, inputs, locals, lookup);
+
+        let find_word = |name: &str| match this {
+            Some((this_name, word)) if name == this_name => Some(word.clone()),
+            _ => self.resolve(name).map(|(_, word)| word.word.clone()),
+        };
+
+        nodes
+            .iter()
+            .zip(proven)
+            .map(|(node, proven)| match node {
+                Node::Token(tok, parsed_tok) => match parsed_tok {
+                    ParsedToken::Literal(val) => Instr::Push(val.clone()),
+                    // Unqualified builtins may be shadowed.
+                    ParsedToken::Intrinsic(fun) if tok == &fun.name && find_word(tok).is_some() => {
+                        Instr::Invoke(find_word(tok).unwrap())
+                    }
+                    ParsedToken::Intrinsic(fun) if proven => Instr::ApplyUnchecked(fun),
+                    ParsedToken::Intrinsic(fun) => Instr::Apply(fun),
+                    ParsedToken::Unit(unit) => Instr::AttachUnit(unit.clone()),
+                    ParsedToken::Conversion(unit) => Instr::Convert(unit.clone()),
+                    ParsedToken::Store(name) => Instr::Store(name.clone()),
+                    ParsedToken::Recall(name) => Instr::Recall(name.clone()),
+                    ParsedToken::Word(name) if name == "call" => Instr::Call,
+                    ParsedToken::Word(name) if AngleMode::from_name(name).is_some() => {
+                        Instr::SetAngleMode(AngleMode::from_name(name).unwrap())
+                    }
+                    ParsedToken::Word(name) if Method::from_name(name).is_some() => {
+                        Instr::Numerical(Method::from_name(name).unwrap())
+                    }
+                    ParsedToken::Word(name) if name == "tolerance" => Instr::SetTolerance,
+                    ParsedToken::Word(name) if name == "iterations" => Instr::SetIterations,
+                    ParsedToken::Word(_) | ParsedToken::Qualified(_, _) => match find_word(tok) {
+                        Some(word) => Instr::Invoke(word),
+                        None => Instr::Lookup(tok.clone()),
+                    },
+                    _ => unreachable!(), // Structural tokens are handled by ``words::compile``.
+                },
+                Node::Local(name) => Instr::Local(
+                    locals
+                        .iter()
+                        .position(|(local, _)| local == name)
+                        .expect("locals are bound by their definition"),
+                ),
+                Node::Quotation(body) => {
+                    Instr::Quote(Rc::clone(body), self.compile(body, &[], locals, this))
+                }
+                // Only run at the top level, by ``eval_tokens``.
+                Node::Definition(_) | Node::Include(_) | Node::Use(_) | Node::Vocab(_) => {
+                    unreachable!()
+                }
+            })
+            .collect()
+    }
+
+    fn run(&mut self, code: &[Instr], frame: &Frame) -> Result<(), EvalError> {
+        for instr in code {
+            match instr {
+                Instr::Push(val) => self.stack.push(val.clone()),
+                Instr::Apply(fun) => self.apply(fun, true)?,
+                Instr::ApplyUnchecked(fun) => self.apply(fun, false)?,
+                Instr::AttachUnit(unit) => typecheck::apply_unit(unit, &mut self.stack)
+                    .map_err(|err| EvalError::Type(err, unit.to_string()))?,
+                Instr::Convert(unit) => typecheck::convert_unit(unit, &mut self.stack)
+                    .map_err(|err| EvalError::Type(err, format!(">{}", unit)))?,
+                Instr::Store(name) => {
+                    // Storing consumes the value, as in Forth.
+                    let val = self.stack.pop().ok_or_else(|| {
+                        EvalError::Type(TypeError::WrongArity, format!("{}!", name))
+                    })?;
+
+                    self.variables.insert(name.clone(), val);
+                }
+                Instr::Recall(name) => self.recall(name)?,
+                Instr::Local(idx) => self.stack.push(frame[*idx].1.clone()),
+                Instr::Quote(body, code) => self.stack.push(Value::Quotation(Quotation {
+                    body: Rc::clone(body),
+                    code: Rc::clone(code),
+                    captured: frame.clone(),
+                    vocabulary: Rc::clone(&self.current),
+                })),
+                Instr::Call => self.call()?,
+                Instr::Invoke(word) => self.invoke(word)?,
+                Instr::SetAngleMode(mode) => self.angle_mode = *mode,
+                Instr::Numerical(method) => self.numerical(*method)?,
+                Instr::SetTolerance => self.set_tolerance()?,
+                Instr::SetIterations => self.set_iterations()?,
+                Instr::Lookup(name) => self.eval_word(name)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    // Apply a builtin, measuring any angle that it takes
+    // or gives in the current angle mode.
+    fn apply(&mut self, fun: &BuiltinFun, checked: bool) -> Result<(), EvalError> {
+        let fun = fun.resolve(&self.stack);
+        let type_err = |err| EvalError::Type(err, format!("{:?}", fun));
+        let mode = self.angle_mode;
+
+        // The values beneath the other arguments become
+        // the list, and are put back if the builtin fails.
+        let others = fun.signiture.0.len().saturating_sub(1);
+        let gathered = fun.gathers_stack && self.stack.height() >= others;
+
+        if gathered {
+            let args = self.stack.take_n(others);
+            let items = self.stack.take_n(self.stack.height());
+            self.stack.push(Value::List(items));
+            self.stack.extend(args);
+        }
+
+        if fun.angles == Angles::Argument && mode != AngleMode::Radians {
+            typecheck::check_args(fun.signiture.0, &self.stack).map_err(type_err)?;
+
+            // Whole turns are taken off first, so that they
+            // are exact.
+            let angle = self.stack.pop().unwrap();
+            let angle = convert_angle(angle, |x| mode.to_radians(x % mode.full_turn()));
+            self.stack.push(angle);
+        }
+
+        let result = if checked {
+            typecheck::checked_apply(fun, &mut self.stack, &mut self.generator)
+        } else {
+            typecheck::unchecked_apply(fun, &mut self.stack, &mut self.generator)
+        };
+
+        if result.is_err() && gathered {
+            let args = self.stack.take_n(others);
+            if let Some(Value::List(items)) = self.stack.pop() {
+                self.stack.extend(items);
+            }
+            self.stack.extend(args);
+        }
+
+        result.map_err(type_err)?;
+
+        if fun.angles == Angles::Result && mode != AngleMode::Radians {
+            let angle = self.stack.pop().unwrap();
+            self.stack
+                .push(convert_angle(angle, |x| mode.from_radians(x)));
+        }
+
+        Ok(())
+    }
+
+    // Add a word to the current vocabulary, unless
+    // inference shows that it cannot type check.
+    fn define(&mut self, def: &Rc<Definition>) -> Result<(), EvalError> {
+        let effect = inference::infer_definition(def, |name| self.effect_of(name))
+            .map_err(|err| EvalError::IllTyped(def.name.clone(), err))?;
+
+        if let Some(other) = self.hidden_definition(&self.current, &def.name) {
+            let vocab = Rc::clone(&self.current);
+            self.warn_shadowing(&vocab, &def.name, &other);
+        }
+
+        // Code that already calls the word will run the
+        // new definition.
+        let vocabulary = Rc::clone(&self.current);
+        let word = match self.vocabularies[&self.current].words.get(&def.name) {
+            Some(old) => old.word.clone(),
+            None => WordRef::new(Word {
+                def: Rc::clone(def),
+                vocabulary: Rc::clone(&vocabulary),
+                code: Rc::new([]),
+            }),
+        };
+
+        let (inputs, locals) = match &def.effect {
+            Some(effect) if def.binds_locals => {
+                let locals = effect
+                    .inputs
+                    .iter()
+                    .map(|(name, typ)| (name.clone(), typ.clone().unwrap_or(Type::Any)));
+
+                (Vec::new(), locals.collect())
+            }
+            Some(effect) => (effect.input_types(), Vec::new()),
+            None => (Vec::new(), Vec::new()),
+        };
+
+        let code = self.compile(&def.body, &inputs, &locals, Some((&def.name, &word)));
+        word.set(Word {
+            def: Rc::clone(def),
+            vocabulary,
+            code,
+        });
+
+        let word = UserWord {
+            def: Rc::clone(def),
+            word,
+            effect,
+            order: self.definition_count,
+        };
+
+        self.definition_count += 1;
+        self.current_vocab_mut()
+            .words
+            .insert(def.name.clone(), word);
+        self.new_words.push(def.name.clone());
+        Ok(())
+    }
+
+    // Make later definitions go into the named
+    // vocabulary, creating it if needed.
+    fn set_vocabulary(&mut self, name: &str) {
+        let name: Rc<str> = Rc::from(name);
+        self.vocabularies.entry(Rc::clone(&name)).or_default();
+        self.current = name;
+    }
+
+    // Make the words of a vocabulary visible from the
+    // current one, warning about any names that
+    // collide.
+    fn use_vocabulary(&mut self, name: &str) -> Result<(), EvalError> {
+        let name = match self.vocabularies.get_key_value(name) {
+            Some((name, _)) => Rc::clone(name),
+            None if name == BUILTINS => return Ok(()),
+            None => return Err(EvalError::UnknownVocabulary(name.to_string())),
+        };
+
+        let current = &self.vocabularies[&self.current];
+        if name == self.current || current.using.contains(&name) {
+            return Ok(());
+        }
+
+        let mut collisions = Vec::new();
+        for word in self.vocabularies[&name].words.keys() {
+            if current.words.contains_key(word) {
+                // The current vocabulary's own words win.
+                let other = format!("{}:{}", name, word);
+                collisions.push((self.current.to_string(), word.clone(), other));
+            } else if let Some(other) = self.hidden_definition(&self.current, word) {
+                collisions.push((name.to_string(), word.clone(), other));
+            }
+        }
+
+        collisions.sort();
+        for (winner, word, other) in collisions {
+            self.warn_shadowing(&winner, &word, &other);
+        }
+
+        self.current_vocab_mut().using.push(name);
+        Ok(())
+    }
+
+    // Returns the qualified name of the definition that
+    // ``name`` would refer to from ``vocab`` if ``vocab``
+    // did not define it.
+    fn hidden_definition(&self, vocab: &str, name: &str) -> Option<String> {
+        let used = self.vocabularies.get(vocab)?.using.iter().rev();
+
+        used.filter(|used| self.vocabularies[*used].words.contains_key(name))
+            .map(|used| format!("{}:{}", used, name))
+            .next()
+            .or_else(|| {
+                INTRINSIC_FNS
+                    .get(name)
+                    .map(|_| format!("{}:{}", BUILTINS, name))
+            })
+    }
+
+    fn warn_shadowing(&mut self, vocab: &str, name: &str, other: &str) {
+        self.warnings
+            .push(format!("{}:{} shadows {}", vocab, name, other));
+    }
+
+    fn current_vocab_mut(&mut self) -> &mut Vocabulary {
+        self.vocabularies.get_mut(&self.current).unwrap()
+    }
+
+    // Find the vocabulary that a qualifier refers to,
+    // either by its full name or, if that is unique, by
+    // the last part of its name.
+    fn find_vocabulary(&self, qualifier: &str) -> Option<&Rc<str>> {
+        if let Some((name, _)) = self.vocabularies.get_key_value(qualifier) {
+            return Some(name);
+        }
+
+        let mut matches = self
+            .vocabularies
+            .keys()
+            .filter(|name| name.rsplit('.').next() == Some(qualifier));
+
+        match (matches.next(), matches.next()) {
+            (Some(name), None) => Some(name),
+            _ => None,
+        }
+    }
+
+    // Find the user-defined word that a name refers to
+    // from the current vocabulary, along with the
+    // vocabulary it is in.
+    fn resolve(&self, name: &str) -> Option<(&Rc<str>, &UserWord)> {
+        if let Some((qualifier, name)) = name.rsplit_once(':') {
+            let vocab = self.find_vocabulary(qualifier)?;
+            return self.vocabularies[vocab]
+                .words
+                .get(name)
+                .map(|word| (vocab, word));
+        }
+
+        let current = self.vocabularies.get_key_value(&self.current)?;
+
+        std::iter::once(current.0)
+            .chain(current.1.using.iter().rev())
+            .find_map(|vocab| {
+                let (vocab, words) = self.vocabularies.get_key_value(vocab)?;
+                words.words.get(name).map(|word| (vocab, word))
+            })
+    }
+
+    fn effect_of(&self, name: &str) -> WordEffect {
+        match self.resolve(name) {
+            Some((_, word)) => match &word.effect {
+                Some(effect) => WordEffect::Known(effect.clone()),
+                None => WordEffect::Unknown,
+            },
+            None if self.variables.contains_key(name) => WordEffect::Variable,
+            None => WordEffect::Undefined,
+        }
+    }
+
+    // Names refer to user-defined words, then to
+    // variables.
+    fn eval_word(&mut self, name: &str) -> Result<(), EvalError> {
+        match self.resolve(name) {
+            Some((_, word)) => {
+                let word = word.word.clone();
+                self.invoke(&word)
+            }
+            None if name.contains(':') => Err(EvalError::UnknownWord(name.to_string())),
+            None => self.recall(name),
+        }
+    }
+
+    fn recall(&mut self, name: &str) -> Result<(), EvalError> {
+        match self.variables.get(name) {
+            Some(val) => {
+                self.stack.push(val.clone());
+                Ok(())
+            }
+            None => Err(EvalError::UnknownWord(name.to_string())),
+        }
+    }
+
+    // Run the quotation on top of the stack.
+    fn call(&mut self) -> Result<(), EvalError> {
+        typecheck::check_args(&[Type::Quotation], &self.stack)
+            .map_err(|err| EvalError::Type(err, "call".to_string()))?;
+
+        let quot = match self.stack.pop() {
+            Some(Value::Quotation(quot)) => quot,
+            _ => unreachable!(),
+        };
+
+        self.nested("call", &quot.vocabulary, |interp| {
+            interp.run(&quot.code, &quot.captured)
+        })
+    }
+
+    // Run a numerical method on the quotation and
+    // numbers on top of the stack, leaving its answer in
+    // their type.
+    fn numerical(&mut self, method: Method) -> Result<(), EvalError> {
+        let name = method.name();
+        let type_err = |err| EvalError::Type(err, name.to_string());
+
+        let mut arg_types = vec![Type::Quotation];
+        arg_types.resize(method.points() + 1, Type::Fractional);
+        typecheck::check_args(&arg_types, &self.stack).map_err(type_err)?;
+
+        let points = self.stack.take_n(method.points());
+        let quot = match self.stack.pop() {
+            Some(Value::Quotation(quot)) => quot,
+            _ => unreachable!(),
+        };
+
+        let restore = |interp: &mut Interpreter, quot: Quotation, points: Vec<Value>| {
+            interp.stack.push(Value::Quotation(quot));
+            interp.stack.extend(points);
+        };
+
+        // The numbers must all be Float32 or all Float64.
+        let (xs, epsilon) = match points.as_slice() {
+            [Value::Float32(_), ..] if points.iter().all(|x| matches!(x, Value::Float32(_))) => {
+                (fractionals(&points), f64::from(f32::EPSILON))
+            }
+            [Value::Float64(_), ..] if points.iter().all(|x| matches!(x, Value::Float64(_))) => {
+                (fractionals(&points), f64::EPSILON)
+            }
+            _ => {
+                restore(self, quot, points);
+                return Err(type_err(TypeError::TypeMismatch));
+            }
+        };
+
+        let fractional = Type::Fractional;
+        let checked = inference::check_function(&quot, name, &fractional, &fractional, |name| {
+            self.effect_of(name)
+        });
+
+        if let Err(err) = checked {
+            restore(self, quot, points);
+            return Err(EvalError::IllTypedFunction(name.to_string(), err));
+        }
+
+        let limits = self.limits.at_precision(method.finest_tolerance(epsilon));
+        let float32 = matches!(points[0], Value::Float32(_));
+        let f = |x| self.evaluate(name, &quot, float32, x);
+
+        let result = match method {
+            Method::Bisect => numerical::bisect(f, xs[0], xs[1], limits),
+            Method::Newton => numerical::newton(f, xs[0], limits),
+            Method::Brent => numerical::brent(f, xs[0], xs[1], limits),
+            Method::Minimize => numerical::minimize(f, xs[0], xs[1], limits),
+            Method::Integrate => numerical::integrate(f, xs[0], xs[1], limits),
+            Method::Derivative => numerical::derivative(f, xs[0], limits),
+        };
+
+        let answer = match result {
+            Ok(x) => x,
+            Err(failure) => {
+                restore(self, quot, points);
+                return Err(match failure {
+                    Failure::Function(err) => err,
+                    Failure::NotBracketed | Failure::NotFinite => type_err(TypeError::DomainError),
+                    Failure::NoConvergence => {
+                        EvalError::NoConvergence(name.to_string(), limits.iterations)
+                    }
+                });
+            }
+        };
+
+        self.stack.push(if float32 {
+            Value::Float32(answer as f32)
+        } else {
+            Value::Float64(answer)
+        });
+
+        Ok(())
+    }
+
+    // Run a quotation as a function of one number,
+    // which must leave exactly one number in its place.
+    fn evaluate(
+        &mut self,
+        name: &str,
+        quot: &Quotation,
+        float32: bool,
+        x: f64,
+    ) -> Result<f64, EvalError> {
+        let base = self.stack.height();
+        self.stack.push(if float32 {
+            Value::Float32(x as f32)
+        } else {
+            Value::Float64(x)
+        });
+
+        let result = self.nested(name, &quot.vocabulary, |interp| {
+            interp.run(&quot.code, &quot.captured)
+        });
+
+        // Whatever the function left is taken off, so
+        // that it cannot pile up across evaluations.
+        let height = self.stack.height();
+        let results = self.stack.take_n(height.saturating_sub(base));
+        result?;
+
+        let y = match results.as_slice() {
+            [Value::Float32(y)] if height == base + 1 => f64::from(*y),
+            [Value::Float64(y)] if height == base + 1 => *y,
+            _ => return Err(EvalError::Type(TypeError::BrokenCallee, name.to_string())),
+        };
+
+        if y.is_nan() {
+            return Err(EvalError::Type(TypeError::DomainError, name.to_string()));
+        }
+
+        Ok(y)
+    }
+
+    fn set_tolerance(&mut self) -> Result<(), EvalError> {
+        let type_err = |err| EvalError::Type(err, "tolerance".to_string());
+        typecheck::check_args(&[Type::Fractional], &self.stack).map_err(type_err)?;
+
+        let val = self.stack.pop().unwrap();
+        let tolerance = fractionals(std::slice::from_ref(&val))[0];
+
+        // A tolerance of one or more would allow any
+        // answer at all.
+        if !(tolerance > 0.0 && tolerance < 1.0) {
+            self.stack.push(val);
+            return Err(type_err(TypeError::DomainError));
+        }
+
+        self.limits.tolerance = tolerance;
+        Ok(())
+    }
+
+    fn set_iterations(&mut self) -> Result<(), EvalError> {
+        let type_err = |err| EvalError::Type(err, "iterations".to_string());
+        typecheck::check_args(&[Type::Int32], &self.stack).map_err(type_err)?;
+
+        let iterations = match self.stack.pop() {
+            Some(Value::Int32(n)) => n,
+            _ => unreachable!(),
+        };
+
+        if iterations < 1 {
+            self.stack.push(Value::Int32(iterations));
+            return Err(type_err(TypeError::DomainError));
+        }
+
+        self.limits.iterations = iterations as u32;
+        Ok(())
+    }
+
+    // Run a user-defined word, enforcing its declared
+    // stack effect.
+    fn invoke(&mut self, word: &WordRef) -> Result<(), EvalError> {
+        let (def, vocab, code) = word.get();
+        let type_err = |err| EvalError::Type(err, def.name.clone());
+
+        let effect = match &def.effect {
+            Some(effect) => effect,
+            None => {
+                return self.nested(&def.name, &vocab, |interp| interp.run(&code, &Frame::new()))
+            }
+        };
+
+        typecheck::check_args(&effect.input_types(), &self.stack).map_err(type_err)?;
+        let base = self.stack.height() - effect.inputs.len();
+
+        let frame = if def.binds_locals {
+            let args = self.stack.take_n(effect.inputs.len());
+            let names = effect.inputs.iter().map(|(name, _)| name.clone());
+            names.zip(args).collect()
+        } else {
+            Frame::new()
+        };
+
+        self.nested(&def.name, &vocab, |interp| interp.run(&code, &frame))?;
+
+        // The word must leave exactly its declared
+        // outputs above the values it was given.
+        if self.stack.height() != base + effect.outputs.len() {
+            return Err(type_err(TypeError::BrokenCallee));
+        }
+
+        let results = &self.stack.iter().as_slice()[base..];
+        typecheck::check_results(&effect.output_types(), results).map_err(type_err)
+    }
+
+    // Run code belonging to ``vocab``, so that names in
+    // it are looked up there.
+    fn nested<F>(&mut self, name: &str, vocab: &Rc<str>, body: F) -> Result<(), EvalError>
+    where
+        F: FnOnce(&mut Interpreter) -> Result<(), EvalError>,
+    {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(EvalError::TooDeep(name.to_string()));
+        }
+
+        let caller = mem::replace(&mut self.current, Rc::clone(vocab));
+        self.depth += 1;
+        let result = body(self);
+        self.depth -= 1;
+        self.current = caller;
+
+        result
+    }
+}
+
+// Returns ``true`` for the nodes that only appear at
+// the top level.
+fn is_top_level(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Definition(_) | Node::Include(_) | Node::Use(_) | Node::Vocab(_)
+    )
+}
+
+// The values of Float32 or Float64 numbers
+fn fractionals(vals: &[Value]) -> Vec<f64> {
+    vals.iter()
+        .map(|val| match val {
+            Value::Float32(x) => f64::from(*x),
+            Value::Float64(x) => *x,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+// Convert a Float32 or Float64 angle, keeping its
+// type.
+fn convert_angle<F>(angle: Value, convert: F) -> Value
+where
+    F: Fn(f64) -> f64,
+{
+    match angle {
+        Value::Float32(x) => Value::Float32(convert(x as f64) as f32),
+        Value::Float64(x) => Value::Float64(convert(x)),
+        _ => unreachable!(), // Angles are always Fractional.
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
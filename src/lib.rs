@@ -3,8 +3,22 @@
 //! This is the root library crate for the cattywampus stack
 //! calculator and mini-language.
 
+pub mod bytecode;
 pub mod functions;
+pub mod inference;
+pub mod infix;
+pub mod interpreter;
+pub mod matrix;
+pub mod number_theory;
+pub mod numerical;
 pub mod parser;
+pub mod random;
+pub mod script;
+pub mod session;
+pub mod special;
 pub mod stack;
+pub mod statistics;
 pub mod typecheck;
+pub mod units;
 pub mod value;
+pub mod words;
@@ -1,81 +1,327 @@
 use cattywampus::{
-    parser::{self, ParsedToken},
+    functions::AngleMode,
+    infix,
+    interpreter::{EvalError, Interpreter},
+    parser,
+    script::{self, PendingLines},
+    session,
     stack::Stack,
-    typecheck,
+    value::Radix,
 };
 use editline;
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Read},
+    process,
+};
+
+const USAGE: &str =
+    "usage: cattywampus [--quiet] [--all] [--no-prelude] [--seed n] [-I dir]... [-e program | script.cw]";
+
+// Exit statuses, following the BSD sysexits
+// conventions for usage and input errors.
+const EXIT_EVAL_ERROR: i32 = 1;
+const EXIT_PARSE_ERROR: i32 = 2;
+const EXIT_USAGE: i32 = 64;
+const EXIT_NO_INPUT: i32 = 66;
+
+/// How the final stack is shown after running a
+/// program non-interactively
+#[derive(Clone, Copy)]
+enum Output {
+    /// The same table that the REPL prints
+    Table,
+
+    /// Only the top value, on its own line
+    Top,
+
+    /// Every value, one per line, from the bottom up
+    All,
+
+    Nothing,
+}
 
 fn main() {
-    repl();
+    let mut quiet = false;
+    let mut all = false;
+    let mut program = None;
+    let mut paths = Vec::new();
+    let mut prelude = true;
+    let mut search_path = Vec::new();
+    let mut seed = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--quiet" => quiet = true,
+            "--all" => all = true,
+            "--no-prelude" => prelude = false,
+            "-e" => match args.next() {
+                Some(source) if program.is_none() => program = Some(source),
+                _ => usage_error(),
+            },
+            "-I" => match args.next() {
+                Some(dir) => search_path.push(dir.into()),
+                None => usage_error(),
+            },
+            "--seed" => match args.next().and_then(|n| n.parse::<i64>().ok()) {
+                Some(n) => seed = Some(n as u64),
+                None => usage_error(),
+            },
+            _ if arg.starts_with('-') => usage_error(),
+            _ => paths.push(arg),
+        }
+    }
+
+    let mut interp = if prelude {
+        Interpreter::new()
+    } else {
+        Interpreter::without_prelude()
+    };
+
+    interp.search_path_mut().extend(search_path);
+
+    if let Some(seed) = seed {
+        interp.reseed(seed);
+    }
+
+    // Programs given on the command line or piped in
+    // print only their results, for use in shell scripts.
+    let output = match (quiet, all) {
+        (true, _) => Output::Nothing,
+        (false, true) => Output::All,
+        (false, false) => Output::Top,
+    };
+
+    // Scripts print the same table as the REPL.
+    let script_output = if quiet {
+        Output::Nothing
+    } else {
+        Output::Table
+    };
+
+    let status = match (program, paths.as_slice()) {
+        (Some(source), []) => run(&mut interp, "-e", &source, output),
+        (None, [path]) => run_script(&mut interp, path, script_output),
+        (None, []) if io::stdin().is_terminal() => {
+            repl(interp);
+            0
+        }
+        (None, []) => run_stdin(&mut interp, output),
+        _ => usage_error(),
+    };
+
+    process::exit(status);
 }
 
-fn repl() {
-    let mut stack = Stack::new();
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(EXIT_USAGE);
+}
+
+// Run a script file, returning the exit status.
+fn run_script(interp: &mut Interpreter, path: &str, output: Output) -> i32 {
+    match fs::read_to_string(path) {
+        Ok(source) => run(interp, path, &source, output),
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            EXIT_NO_INPUT
+        }
+    }
+}
+
+// Run the program piped to standard input, returning
+// the exit status.
+fn run_stdin(interp: &mut Interpreter, output: Output) -> i32 {
+    let mut source = String::new();
+
+    match io::stdin().read_to_string(&mut source) {
+        Ok(_) => run(interp, "<stdin>", &source, output),
+        Err(err) => {
+            eprintln!("<stdin>: {}", err);
+            EXIT_NO_INPUT
+        }
+    }
+}
+
+// Run a program, naming it ``name`` in any error
+// message, and return the exit status.
+fn run(interp: &mut Interpreter, name: &str, source: &str, output: Output) -> i32 {
+    let result = script::eval_source(interp, source);
+
+    for warning in interp.take_warnings() {
+        eprintln!("{}: warning: {}", name, warning);
+    }
+
+    if let Err(err) = result {
+        eprintln!("{}:{}: {}", name, err.line, err.error);
+
+        return if err.error.is_parse_error() {
+            EXIT_PARSE_ERROR
+        } else {
+            EXIT_EVAL_ERROR
+        };
+    }
+
+    match output {
+        Output::Table => print_stack(interp.stack(), Radix::Decimal),
+        Output::Top => {
+            if let Some(val) = interp.stack().iter().last() {
+                println!("{}", val);
+            }
+        }
+        Output::All => {
+            for val in interp.stack() {
+                println!("{}", val);
+            }
+        }
+        Output::Nothing => {}
+    }
+
+    0
+}
+
+fn repl(mut interp: Interpreter) {
+    // In infix mode, each line is an infix expression.
+    let mut infix_mode = false;
+
+    // The radix in which the stack shows integers
+    let mut radix = Radix::Decimal;
+
+    // Lines that leave a quotation, definition, or
+    // comment open wait for the lines that close it.
+    let mut pending = PendingLines::default();
 
     loop {
-        let line = editline::readline("> ").unwrap();
+        let prompt = if !pending.is_empty() {
+            "... ".to_string()
+        } else if infix_mode {
+            format!("{} infix> ", interp.angle_mode())
+        } else {
+            format!("{}> ", interp.angle_mode())
+        };
+        let line = editline::readline(&prompt).unwrap();
 
         editline::add_history(line);
 
         let input = line.trim();
 
-        if input == ":p" {
-            println!("{:?}", stack);
+        if !pending.is_empty() {
+            // Commands are not read in the middle of code.
+        } else if input == ":p" {
+            println!("{:?}", interp.stack());
             continue;
         } else if input == ":r" {
-            stack.clear();
+            interp.stack_mut().clear();
 
             println!("Stack cleared.");
             continue;
+        } else if input == ":vars" {
+            print_variables(&interp);
+            continue;
+        } else if let Some(path) = input.strip_prefix(":save ") {
+            match session::save(&interp, path.trim()) {
+                Ok(()) => println!("Session saved."),
+                Err(err) => println!("Error - {}", err),
+            }
+            continue;
+        } else if let Some(path) = input.strip_prefix(":load ") {
+            match session::load(path.trim()) {
+                Ok(mut loaded) => {
+                    *loaded.search_path_mut() = interp.search_path_mut().clone();
+                    interp = loaded;
+                    println!("Session loaded.");
+                    print_stack(interp.stack(), radix);
+                }
+                Err(err) => println!("Error - {}", err),
+            }
+            continue;
+        } else if input == ":infix" {
+            infix_mode = !infix_mode;
+
+            println!("Infix mode {}.", if infix_mode { "on" } else { "off" });
+            continue;
+        } else if let Some(mode) = input.strip_prefix(':').and_then(AngleMode::from_name) {
+            interp.set_angle_mode(mode);
+
+            println!("Angle mode set to {}.", mode);
+            continue;
+        } else if let Some(new_radix) = input.strip_prefix(':').and_then(Radix::from_name) {
+            radix = new_radix;
+
+            println!("Integers shown in {}.", radix);
+            continue;
+        } else if let Some(expr) = input.strip_prefix(":rpn ") {
+            match infix::translate(expr) {
+                Ok(postfix) => println!("{}", postfix.join(" ")),
+                Err(msg) => println!("Error - {}", EvalError::Syntax(msg)),
+            }
+            continue;
         } else if input == ":q" {
             return;
         }
 
-        let parsed_tokens = parser::parse_line(input).collect::<Vec<_>>();
-
-        // Just for debugging.
-        println!(
-            "{:?}",
-            parsed_tokens
-                .iter()
-                .map(|(_, parsed_tok)| parsed_tok)
-                .collect::<Vec<_>>()
-        );
-
-        // First, verify the input.
-        let mut invalid = false;
-        for (tok, parsed_tok) in &parsed_tokens {
-            if *parsed_tok == ParsedToken::BadToken {
-                println!("Error - Invalid token: {}", tok);
-                invalid = true;
+        let source = if infix_mode {
+            match infix::translate(input) {
+                Ok(postfix) => postfix.join(" "),
+                Err(msg) => {
+                    println!("Error - {}", EvalError::Syntax(msg));
+                    continue;
+                }
             }
+        } else {
+            input.to_string()
+        };
+
+        let lines = match pending.push(&source) {
+            Some(lines) => lines,
+            None => continue,
+        };
+
+        let result = interp.eval_tokens(lines.iter().flat_map(|line| parser::parse_line(line)));
+        print_new_words(&mut interp);
+
+        for warning in interp.take_warnings() {
+            println!("Warning - {}", warning);
         }
 
-        if !invalid {
-            for (_, parsed_tok) in parsed_tokens {
-                match parsed_tok {
-                    ParsedToken::Literal(val) => stack.push(val),
-                    ParsedToken::Intrinsic(fun) => {
-                        if let Err(typ_err) = typecheck::checked_apply(fun, &mut stack) {
-                            println!("Error - {:?}: {:?}", typ_err, fun);
-                        }
-                    }
-                    ParsedToken::BadToken => unreachable!(), // Bad tokens are handled above.
-                }
-            }
+        if let Err(err) = result {
+            println!("Error - {}", err);
+        }
+
+        print_stack(interp.stack(), radix);
+    }
+}
+
+fn print_variables(interp: &Interpreter) {
+    let mut any = false;
+
+    for (name, val) in interp.variables() {
+        println!("{} = {} ({})", name, val, val.type_str());
+        any = true;
+    }
+
+    if !any {
+        println!("No variables.");
+    }
+}
 
-            print_stack(&stack);
+fn print_new_words(interp: &mut Interpreter) {
+    for name in interp.take_new_words() {
+        match interp.word_effect(&name) {
+            Some(effect) => println!("Defined {} {}", name, effect),
+            None => println!("Defined {} (stack effect unknown)", name),
         }
     }
 }
 
-fn print_stack(stack: &Stack) {
+fn print_stack(stack: &Stack, radix: Radix) {
     let mut types = String::new();
     let mut values = String::new();
     
     for i in stack {
         let type_repr = i.type_str();
-        let val_repr = i.to_string();
+        let val_repr = i.in_radix(radix).to_string();
         
         let max_length = type_repr.len().max(val_repr.len()) + 1;
         
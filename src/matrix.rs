@@ -0,0 +1,817 @@
+//! Dense matrices and linear algebra
+//!
+//! This module provides the ``Matrix`` type stored
+//! inside ``Value::Matrix``, along with the numerical
+//! routines behind the linear algebra builtins.
+//!
+//! The routines are written once, for a ``Dense``
+//! matrix of any ``Entry`` type. A ``Matrix`` holds
+//! ``Float64``, ``Rational``, or ``Complex`` entries,
+//! and mixing them gives the less exact of the two.
+
+use super::typecheck::TypeError;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// Pivots smaller than this (relative to the largest
+/// entry of the matrix) are treated as zero.
+const PIVOT_EPSILON: f64 = 1e-12;
+
+/// The maximum number of Jacobi sweeps used by
+/// ``Dense::symmetric_eigen``.
+const MAX_JACOBI_SWEEPS: usize = 100;
+
+/// The largest ``n`` for which the ``identity``
+/// builtin makes an ``n`` by ``n`` matrix, which
+/// keeps its entries to 8 MiB.
+pub const MAX_IDENTITY_ORDER: usize = 1024;
+
+/// The numbers that a ``Dense`` matrix may hold
+pub trait Entry:
+    Clone
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Whether arithmetic on the entries is exact, so
+    /// that only a true zero is treated as zero.
+    const EXACT: bool;
+
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    /// The absolute value of an inexact entry, used to
+    /// choose pivots. Any exact entry but zero is as
+    /// good a pivot as another, so they give ``1.0``.
+    fn magnitude(&self) -> f64;
+
+    /// Write the entry so that it reads back as the
+    /// same number of the same type.
+    fn write_literal(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+/// Whole numbers too large for an ``Int32`` literal are
+/// written with an exponent, so that they read back.
+impl Entry for f64 {
+    const EXACT: bool = false;
+
+    fn zero() -> f64 {
+        0.0
+    }
+
+    fn one() -> f64 {
+        1.0
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.abs()
+    }
+
+    fn write_literal(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.fract() == 0.0 && self.abs() > f64::from(i32::MAX) {
+            write!(f, "{:e}", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Rationals are always written with a denominator,
+/// so that whole numbers are not read as integers.
+impl Entry for BigRational {
+    const EXACT: bool = true;
+
+    fn zero() -> BigRational {
+        Zero::zero()
+    }
+
+    fn one() -> BigRational {
+        One::one()
+    }
+
+    fn magnitude(&self) -> f64 {
+        if self.is_zero() {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    fn write_literal(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numer(), self.denom())
+    }
+}
+
+/// Complex numbers are written with both parts, as
+/// in ``1+2i`` or ``0-1i``.
+impl Entry for Complex64 {
+    const EXACT: bool = false;
+
+    fn zero() -> Complex64 {
+        Zero::zero()
+    }
+
+    fn one() -> Complex64 {
+        One::one()
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.norm()
+    }
+
+    fn write_literal(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:+}i", self.re, self.im)
+    }
+}
+
+/// A dense, row-major matrix of any ``Entry`` type
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dense<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T: Entry> Dense<T> {
+    /// Create a matrix from its dimensions and its
+    /// entries in row-major order.
+    ///
+    /// Returns ``None`` if the number of entries does
+    /// not equal ``rows * cols``, or if only one
+    /// dimension is zero. The empty matrix has no rows
+    /// and no columns.
+    pub fn new(rows: usize, cols: usize, data: Vec<T>) -> Option<Dense<T>> {
+        if (rows == 0) != (cols == 0) || data.len() != rows * cols {
+            None
+        } else {
+            Some(Dense { rows, cols, data })
+        }
+    }
+
+    /// Create a matrix of the given shape, filled
+    /// with zeroes.
+    fn zeroes(rows: usize, cols: usize) -> Dense<T> {
+        Dense {
+            rows,
+            cols,
+            data: vec![T::zero(); rows * cols],
+        }
+    }
+
+    /// Create the ``n`` by ``n`` identity matrix.
+    pub fn identity(n: usize) -> Dense<T> {
+        let mut result = Dense::zeroes(n, n);
+
+        for i in 0..n {
+            result[(i, i)] = T::one();
+        }
+
+        result
+    }
+
+    /// Return the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Return the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns ``true`` if the matrix has as many
+    /// rows as columns.
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// Return a matrix of the same shape, with ``f``
+    /// applied to each entry.
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> Dense<U> {
+        Dense {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(f).collect(),
+        }
+    }
+
+    /// Return the transpose of the matrix.
+    pub fn transpose(&self) -> Dense<T> {
+        let mut result = Dense::zeroes(self.cols, self.rows);
+
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                result[(c, r)] = self[(r, c)].clone();
+            }
+        }
+
+        result
+    }
+
+    /// Return the matrix product ``self * rhs``.
+    ///
+    /// The number of columns in ``self`` must equal
+    /// the number of rows in ``rhs``.
+    pub fn mul(&self, rhs: &Dense<T>) -> Result<Dense<T>, TypeError> {
+        if self.cols != rhs.rows {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        let mut result = Dense::zeroes(self.rows, rhs.cols);
+
+        for r in 0..self.rows {
+            for c in 0..rhs.cols {
+                result[(r, c)] =
+                    sum((0..self.cols).map(|k| self[(r, k)].clone() * rhs[(k, c)].clone()));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Return the sum of the diagonal entries of a
+    /// square matrix.
+    pub fn trace(&self) -> Result<T, TypeError> {
+        if !self.is_square() {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        Ok(sum((0..self.rows).map(|i| self[(i, i)].clone())))
+    }
+
+    /// Compute the LU decomposition with partial
+    /// pivoting of a square matrix.
+    ///
+    /// The result satisfies ``P * A = L * U``, where
+    /// ``L`` is unit lower triangular and ``U`` is
+    /// upper triangular. Singular matrices are allowed;
+    /// their ``U`` has a zero on the diagonal.
+    pub fn lu(&self) -> Result<Lu<T>, TypeError> {
+        if !self.is_square() {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        let n = self.rows;
+        let tolerance = self.tolerance();
+        let mut u = self.clone();
+        let mut l = Dense::zeroes(n, n);
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut swaps = 0;
+
+        for k in 0..n {
+            // Choose the largest pivot in this column.
+            let pivot = largest(k..n, |r| u[(r, k)].magnitude());
+
+            if pivot != k {
+                u.swap_rows(pivot, k);
+                l.swap_rows(pivot, k);
+                perm.swap(pivot, k);
+                swaps += 1;
+            }
+
+            l[(k, k)] = T::one();
+
+            if u[(k, k)].magnitude() <= tolerance {
+                continue;
+            }
+
+            for r in (k + 1)..n {
+                let factor = u[(r, k)].clone() / u[(k, k)].clone();
+                l[(r, k)] = factor.clone();
+
+                for c in k..n {
+                    let delta = factor.clone() * u[(k, c)].clone();
+                    u[(r, c)] = u[(r, c)].clone() - delta;
+                }
+            }
+        }
+
+        let mut p = Dense::zeroes(n, n);
+        for (r, &c) in perm.iter().enumerate() {
+            p[(r, c)] = T::one();
+        }
+
+        Ok(Lu {
+            l,
+            u,
+            p,
+            odd_permutation: swaps % 2 == 1,
+        })
+    }
+
+    /// Return the determinant of a square matrix.
+    pub fn det(&self) -> Result<T, TypeError> {
+        let lu = self.lu()?;
+        let product = (0..self.rows).fold(T::one(), |acc, i| acc * lu.u[(i, i)].clone());
+
+        Ok(if lu.odd_permutation {
+            -product
+        } else {
+            product
+        })
+    }
+
+    /// Solve ``self * x = rhs`` for ``x``.
+    ///
+    /// ``self`` must be square and nonsingular, and
+    /// ``rhs`` must have the same number of rows. Each
+    /// column of ``rhs`` is solved independently.
+    pub fn solve(&self, rhs: &Dense<T>) -> Result<Dense<T>, TypeError> {
+        if !self.is_square() || rhs.rows != self.rows {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        let n = self.rows;
+        let tolerance = self.tolerance();
+        let lu = self.lu()?;
+
+        if (0..n).any(|i| lu.u[(i, i)].magnitude() <= tolerance) {
+            return Err(TypeError::DomainError);
+        }
+
+        let b = lu.p.mul(rhs)?;
+        let mut x: Dense<T> = Dense::zeroes(n, rhs.cols);
+
+        for col in 0..rhs.cols {
+            // Forward substitution with the unit lower triangle.
+            let mut y = vec![T::zero(); n];
+            for r in 0..n {
+                let known = sum((0..r).map(|k| lu.l[(r, k)].clone() * y[k].clone()));
+                y[r] = b[(r, col)].clone() - known;
+            }
+
+            // Back substitution with the upper triangle.
+            for r in (0..n).rev() {
+                let known = sum(((r + 1)..n).map(|k| lu.u[(r, k)].clone() * x[(k, col)].clone()));
+                x[(r, col)] = (y[r].clone() - known) / lu.u[(r, r)].clone();
+            }
+        }
+
+        Ok(x)
+    }
+
+    /// Return the inverse of a square, nonsingular
+    /// matrix.
+    pub fn inv(&self) -> Result<Dense<T>, TypeError> {
+        if !self.is_square() {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        self.solve(&Dense::identity(self.rows))
+    }
+
+    /// Return the rank of the matrix, computed by
+    /// Gaussian elimination with partial pivoting.
+    pub fn rank(&self) -> usize {
+        let tolerance = self.tolerance();
+        let mut m = self.clone();
+        let mut rank = 0;
+
+        for c in 0..m.cols {
+            if rank == m.rows {
+                break;
+            }
+
+            let pivot = largest(rank..m.rows, |r| m[(r, c)].magnitude());
+
+            if m[(pivot, c)].magnitude() <= tolerance {
+                continue;
+            }
+
+            m.swap_rows(pivot, rank);
+
+            for r in (rank + 1)..m.rows {
+                let factor = m[(r, c)].clone() / m[(rank, c)].clone();
+
+                for k in c..m.cols {
+                    let delta = factor.clone() * m[(rank, k)].clone();
+                    m[(r, k)] = m[(r, k)].clone() - delta;
+                }
+            }
+
+            rank += 1;
+        }
+
+        rank
+    }
+
+    /// Exchange two rows in place.
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for c in 0..self.cols {
+            self.data.swap(a * self.cols + c, b * self.cols + c);
+        }
+    }
+
+    /// The magnitude below which an entry is considered
+    /// to be zero, scaled to the size of the matrix.
+    /// Exact entries are only zero when they are zero.
+    fn tolerance(&self) -> f64 {
+        if T::EXACT {
+            return 0.0;
+        }
+
+        let largest = self
+            .data
+            .iter()
+            .fold(0.0f64, |acc, x| acc.max(x.magnitude()));
+        PIVOT_EPSILON * largest.max(1.0) * (self.rows.max(self.cols) as f64)
+    }
+}
+
+impl Dense<f64> {
+    /// Find the ``x`` that makes ``self * x`` closest to
+    /// ``rhs``, in the least-squares sense.
+    ///
+    /// ``self`` must have at least as many rows as
+    /// columns, and its columns must be independent.
+    /// This uses Householder QR, which avoids squaring
+    /// the condition number as the normal equations do.
+    pub fn least_squares(&self, rhs: &Dense<f64>) -> Result<Dense<f64>, TypeError> {
+        if self.rows < self.cols || rhs.rows != self.rows {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        let (m, n) = (self.rows, self.cols);
+        let tolerance = self.tolerance();
+        let mut a = self.clone();
+        let mut b = rhs.clone();
+
+        for k in 0..n {
+            let norm = (k..m).map(|i| a[(i, k)] * a[(i, k)]).sum::<f64>().sqrt();
+            if norm <= tolerance {
+                return Err(TypeError::DomainError);
+            }
+
+            // Reflect the column onto the diagonal, choosing
+            // the sign that avoids cancellation.
+            let alpha = if a[(k, k)] > 0.0 { -norm } else { norm };
+            let mut v: Vec<f64> = (k..m).map(|i| a[(i, k)]).collect();
+            v[0] -= alpha;
+            let v_norm2: f64 = v.iter().map(|x| x * x).sum();
+
+            for target in [&mut a, &mut b] {
+                for j in 0..target.cols {
+                    let dot: f64 = (k..m).map(|i| v[i - k] * target[(i, j)]).sum();
+                    let scale = 2.0 * dot / v_norm2;
+
+                    for i in k..m {
+                        target[(i, j)] -= scale * v[i - k];
+                    }
+                }
+            }
+        }
+
+        // Back substitution with the upper triangle.
+        let mut x = Dense::zeroes(n, b.cols);
+        for col in 0..b.cols {
+            for r in (0..n).rev() {
+                let known: f64 = ((r + 1)..n).map(|k| a[(r, k)] * x[(k, col)]).sum();
+                x[(r, col)] = (b[(r, col)] - known) / a[(r, r)];
+            }
+        }
+
+        Ok(x)
+    }
+
+    /// Compute the eigenvalues and eigenvectors of a
+    /// symmetric matrix using the cyclic Jacobi method.
+    ///
+    /// Returns a column matrix of eigenvalues in
+    /// ascending order, and a matrix whose columns are
+    /// the corresponding unit eigenvectors.
+    pub fn symmetric_eigen(&self) -> Result<(Dense<f64>, Dense<f64>), TypeError> {
+        if !self.is_square() {
+            return Err(TypeError::ShapeMismatch);
+        }
+
+        let n = self.rows;
+        let tolerance = self.tolerance();
+
+        for r in 0..n {
+            for c in (r + 1)..n {
+                if (self[(r, c)] - self[(c, r)]).abs() > tolerance {
+                    return Err(TypeError::DomainError);
+                }
+            }
+        }
+
+        let mut a = self.clone();
+        let mut v = Dense::identity(n);
+
+        for _ in 0..MAX_JACOBI_SWEEPS {
+            let off_diagonal: f64 = (0..n)
+                .flat_map(|r| ((r + 1)..n).map(move |c| (r, c)))
+                .map(|(r, c)| a[(r, c)] * a[(r, c)])
+                .sum();
+
+            if off_diagonal.sqrt() <= tolerance {
+                break;
+            }
+
+            for p in 0..n {
+                for q in (p + 1)..n {
+                    if a[(p, q)] == 0.0 {
+                        continue;
+                    }
+
+                    // Choose the rotation that zeroes a[p][q].
+                    let theta = (a[(q, q)] - a[(p, p)]) / (2.0 * a[(p, q)]);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                    let cos = 1.0 / (t * t + 1.0).sqrt();
+                    let sin = t * cos;
+
+                    for k in 0..n {
+                        let (akp, akq) = (a[(k, p)], a[(k, q)]);
+                        a[(k, p)] = cos * akp - sin * akq;
+                        a[(k, q)] = sin * akp + cos * akq;
+                    }
+
+                    for k in 0..n {
+                        let (apk, aqk) = (a[(p, k)], a[(q, k)]);
+                        a[(p, k)] = cos * apk - sin * aqk;
+                        a[(q, k)] = sin * apk + cos * aqk;
+                    }
+
+                    for k in 0..n {
+                        let (vkp, vkq) = (v[(k, p)], v[(k, q)]);
+                        v[(k, p)] = cos * vkp - sin * vkq;
+                        v[(k, q)] = sin * vkp + cos * vkq;
+                    }
+                }
+            }
+        }
+
+        // Sort the eigenpairs by ascending eigenvalue.
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| a[(i, i)].partial_cmp(&a[(j, j)]).unwrap_or(Ordering::Equal));
+
+        let mut values = Dense::zeroes(n, 1);
+        let mut vectors = Dense::zeroes(n, n);
+        for (dest, &src) in order.iter().enumerate() {
+            values[(dest, 0)] = a[(src, src)];
+
+            for k in 0..n {
+                vectors[(k, dest)] = v[(k, src)];
+            }
+        }
+
+        Ok((values, vectors))
+    }
+}
+
+// Add up entries, starting from zero.
+fn sum<T: Entry>(terms: impl Iterator<Item = T>) -> T {
+    terms.fold(T::zero(), |acc, x| acc + x)
+}
+
+// Find the row of the range whose entry has the
+// largest magnitude.
+fn largest(rows: std::ops::Range<usize>, magnitude: impl Fn(usize) -> f64) -> usize {
+    rows.max_by(|&a, &b| {
+        magnitude(a)
+            .partial_cmp(&magnitude(b))
+            .unwrap_or(Ordering::Equal)
+    })
+    .unwrap()
+}
+
+impl<T> std::ops::Index<(usize, usize)> for Dense<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl<T> std::ops::IndexMut<(usize, usize)> for Dense<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+/// Displays the matrix in the same form as its
+/// literal syntax, with rows separated by ``;``
+/// and columns by ``,``.
+impl<T: Entry> fmt::Display for Dense<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+
+        for r in 0..self.rows {
+            if r > 0 {
+                write!(f, ";")?;
+            }
+
+            for c in 0..self.cols {
+                if c > 0 {
+                    write!(f, ",")?;
+                }
+
+                self[(r, c)].write_literal(f)?;
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// The result of ``Dense::lu``
+pub struct Lu<T> {
+    /// The unit lower triangular factor
+    pub l: Dense<T>,
+
+    /// The upper triangular factor
+    pub u: Dense<T>,
+
+    /// The row permutation matrix
+    pub p: Dense<T>,
+
+    /// Whether ``p`` represents an odd number
+    /// of row exchanges
+    pub odd_permutation: bool,
+}
+
+/// A matrix stored inside ``Value::Matrix``, with
+/// entries of one of three types
+#[derive(Clone, Debug, PartialEq)]
+pub enum Matrix {
+    /// A matrix of 64-bit floating point numbers
+    Float64(Dense<f64>),
+
+    /// A matrix of exact fractions
+    Rational(Dense<BigRational>),
+
+    /// A matrix of complex numbers with 64-bit
+    /// floating point parts
+    Complex(Dense<Complex64>),
+}
+
+/// A single entry of a ``Matrix``, as ``det`` and
+/// ``trace`` give
+#[derive(Clone, Debug, PartialEq)]
+pub enum Scalar {
+    Float64(f64),
+    Rational(BigRational),
+    Complex(Complex64),
+}
+
+impl Matrix {
+    /// Return the number of rows.
+    pub fn rows(&self) -> usize {
+        match self {
+            Matrix::Float64(m) => m.rows(),
+            Matrix::Rational(m) => m.rows(),
+            Matrix::Complex(m) => m.rows(),
+        }
+    }
+
+    /// Return the number of columns.
+    pub fn cols(&self) -> usize {
+        match self {
+            Matrix::Float64(m) => m.cols(),
+            Matrix::Rational(m) => m.cols(),
+            Matrix::Complex(m) => m.cols(),
+        }
+    }
+
+    /// Return the matrix with ``Float64`` entries, which
+    /// only ``Complex`` matrices cannot be.
+    pub fn to_float64(&self) -> Result<Dense<f64>, TypeError> {
+        match self {
+            Matrix::Float64(m) => Ok(m.clone()),
+            Matrix::Rational(m) => Ok(m.map(|x| x.to_f64().unwrap_or(f64::NAN))),
+            Matrix::Complex(_) => Err(TypeError::TypeMismatch),
+        }
+    }
+
+    /// Return the matrix with ``Complex`` entries.
+    pub fn to_complex(&self) -> Dense<Complex64> {
+        match self {
+            Matrix::Float64(m) => m.map(|&x| Complex64::new(x, 0.0)),
+            Matrix::Rational(m) => m.map(|x| Complex64::new(x.to_f64().unwrap_or(f64::NAN), 0.0)),
+            Matrix::Complex(m) => m.clone(),
+        }
+    }
+
+    /// Return the transpose of the matrix.
+    pub fn transpose(&self) -> Matrix {
+        match self {
+            Matrix::Float64(m) => Matrix::Float64(m.transpose()),
+            Matrix::Rational(m) => Matrix::Rational(m.transpose()),
+            Matrix::Complex(m) => Matrix::Complex(m.transpose()),
+        }
+    }
+
+    /// Return the matrix product ``self * rhs``, with
+    /// the less exact entry type of the two.
+    pub fn mul(&self, rhs: &Matrix) -> Result<Matrix, TypeError> {
+        match (self, rhs) {
+            (Matrix::Rational(a), Matrix::Rational(b)) => a.mul(b).map(Matrix::Rational),
+            (Matrix::Complex(_), _) | (_, Matrix::Complex(_)) => self
+                .to_complex()
+                .mul(&rhs.to_complex())
+                .map(Matrix::Complex),
+            _ => self
+                .to_float64()?
+                .mul(&rhs.to_float64()?)
+                .map(Matrix::Float64),
+        }
+    }
+
+    /// Solve ``self * x = rhs`` for ``x``, with the
+    /// less exact entry type of the two.
+    pub fn solve(&self, rhs: &Matrix) -> Result<Matrix, TypeError> {
+        match (self, rhs) {
+            (Matrix::Rational(a), Matrix::Rational(b)) => a.solve(b).map(Matrix::Rational),
+            (Matrix::Complex(_), _) | (_, Matrix::Complex(_)) => self
+                .to_complex()
+                .solve(&rhs.to_complex())
+                .map(Matrix::Complex),
+            _ => self
+                .to_float64()?
+                .solve(&rhs.to_float64()?)
+                .map(Matrix::Float64),
+        }
+    }
+
+    /// Return the sum of the diagonal entries of a
+    /// square matrix.
+    pub fn trace(&self) -> Result<Scalar, TypeError> {
+        match self {
+            Matrix::Float64(m) => m.trace().map(Scalar::Float64),
+            Matrix::Rational(m) => m.trace().map(Scalar::Rational),
+            Matrix::Complex(m) => m.trace().map(Scalar::Complex),
+        }
+    }
+
+    /// Return the determinant of a square matrix.
+    pub fn det(&self) -> Result<Scalar, TypeError> {
+        match self {
+            Matrix::Float64(m) => m.det().map(Scalar::Float64),
+            Matrix::Rational(m) => m.det().map(Scalar::Rational),
+            Matrix::Complex(m) => m.det().map(Scalar::Complex),
+        }
+    }
+
+    /// Return the inverse of a square, nonsingular
+    /// matrix.
+    pub fn inv(&self) -> Result<Matrix, TypeError> {
+        match self {
+            Matrix::Float64(m) => m.inv().map(Matrix::Float64),
+            Matrix::Rational(m) => m.inv().map(Matrix::Rational),
+            Matrix::Complex(m) => m.inv().map(Matrix::Complex),
+        }
+    }
+
+    /// Return the rank of the matrix.
+    pub fn rank(&self) -> usize {
+        match self {
+            Matrix::Float64(m) => m.rank(),
+            Matrix::Rational(m) => m.rank(),
+            Matrix::Complex(m) => m.rank(),
+        }
+    }
+
+    /// Return the ``L``, ``U``, and ``P`` factors of
+    /// ``Dense::lu``.
+    pub fn lu(&self) -> Result<[Matrix; 3], TypeError> {
+        match self {
+            Matrix::Float64(m) => m.lu().map(|lu| lu.factors(Matrix::Float64)),
+            Matrix::Rational(m) => m.lu().map(|lu| lu.factors(Matrix::Rational)),
+            Matrix::Complex(m) => m.lu().map(|lu| lu.factors(Matrix::Complex)),
+        }
+    }
+
+    /// Return the eigenvalues and eigenvectors of a
+    /// real, symmetric matrix, as ``Float64``s.
+    pub fn symmetric_eigen(&self) -> Result<(Matrix, Matrix), TypeError> {
+        let (values, vectors) = self.to_float64()?.symmetric_eigen()?;
+        Ok((Matrix::Float64(values), Matrix::Float64(vectors)))
+    }
+}
+
+impl<T> Lu<T> {
+    // Wrap each factor as a ``Matrix``.
+    fn factors(self, wrap: fn(Dense<T>) -> Matrix) -> [Matrix; 3] {
+        [wrap(self.l), wrap(self.u), wrap(self.p)]
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Matrix::Float64(m) => write!(f, "{}", m),
+            Matrix::Rational(m) => write!(f, "{}", m),
+            Matrix::Complex(m) => write!(f, "{}", m),
+        }
+    }
+}
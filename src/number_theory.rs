@@ -0,0 +1,295 @@
+//! Number theory
+//!
+//! This module provides the integer routines behind the
+//! number theory builtins. They work on integers of any
+//! size, so that nothing overflows in intermediate
+//! results; the builtins check that the results fit
+//! back into their type.
+
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+/// Witnesses for the Miller-Rabin test that together
+/// decide primality for every number below 3.3e24
+const WITNESSES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// The primes below this are divided out by trial
+/// before factoring by Pollard's rho method.
+const TRIAL_DIVISION_LIMIT: u32 = 1000;
+
+/// The steps of Pollard's rho method taken between
+/// each check for a factor
+const RHO_BATCH: u32 = 100;
+
+/// The most bits a power, factorial, or binomial
+/// coefficient may grow to, which bounds the work of
+/// computing one even as a ``BigInt``
+const MAX_BITS: u64 = 1 << 16;
+
+/// The greatest common divisor of two numbers, which
+/// is never negative and is zero only if both are.
+pub fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    a.gcd(b)
+}
+
+/// The least common multiple of two numbers, which is
+/// never negative and is zero if either is.
+pub fn lcm(a: &BigInt, b: &BigInt) -> BigInt {
+    a.lcm(b)
+}
+
+/// Returns ``true`` if the number is prime.
+///
+/// This uses the Miller-Rabin test with enough
+/// witnesses that the answer is never wrong below
+/// 3.3e24. Above that, a composite number is reported
+/// as prime with a chance of less than 4^-12.
+pub fn is_prime(n: &BigInt) -> bool {
+    match n.to_biguint() {
+        Some(n) => is_prime_unsigned(&n),
+        None => false,
+    }
+}
+
+fn is_prime_unsigned(n: &BigUint) -> bool {
+    if *n < BigUint::from(2u32) {
+        return false;
+    }
+
+    for &p in &WITNESSES {
+        if (n % p).is_zero() {
+            return *n == BigUint::from(p);
+        }
+    }
+
+    // Write n - 1 as d * 2^s, with d odd.
+    let n_minus_one = n - 1u32;
+    let s = n_minus_one.trailing_zeros().unwrap();
+    let d = &n_minus_one >> s;
+
+    WITNESSES.iter().all(|&a| {
+        let mut x = BigUint::from(a).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            return true;
+        }
+
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                return true;
+            }
+        }
+
+        false
+    })
+}
+
+/// The smallest prime greater than ``n``
+pub fn next_prime(n: &BigInt) -> BigInt {
+    let mut candidate = n.max(&BigInt::one()) + 1;
+
+    while !is_prime(&candidate) {
+        candidate += 1;
+    }
+
+    candidate
+}
+
+/// The prime factors of ``n``, smallest first, with
+/// each repeated as often as it divides ``n``. One has
+/// no prime factors.
+///
+/// Small factors are found by trial division, and the
+/// rest by Pollard's rho method, which is fast for any
+/// number whose second-largest prime factor is below
+/// about 1e12.
+pub fn factor(n: &BigUint) -> Vec<BigUint> {
+    let mut factors = Vec::new();
+    let mut n = n.clone();
+    let mut p = 2u32;
+
+    while p < TRIAL_DIVISION_LIMIT && BigUint::from(p * p) <= n {
+        while (&n % p).is_zero() {
+            factors.push(BigUint::from(p));
+            n /= p;
+        }
+
+        p += if p == 2 { 1 } else { 2 };
+    }
+
+    if !n.is_one() && !n.is_zero() {
+        split(n, &mut factors);
+    }
+
+    factors.sort();
+    factors
+}
+
+// Add the prime factors of ``n``, which is greater
+// than one, in no particular order.
+fn split(n: BigUint, factors: &mut Vec<BigUint>) {
+    if is_prime_unsigned(&n) {
+        factors.push(n);
+    } else {
+        let divisor = rho(&n);
+        let cofactor = &n / &divisor;
+        split(divisor, factors);
+        split(cofactor, factors);
+    }
+}
+
+// A factor of ``n``, which must be composite, other
+// than one and ``n``. Each step follows x -> x^2 + c
+// modulo ``n`` until two values meet modulo a factor,
+// trying again with the next ``c`` if they only meet
+// modulo ``n`` itself.
+//
+// The distances are multiplied together so that only
+// one gcd is needed for a batch of steps, which are
+// taken again one at a time when the batch finds a
+// factor.
+fn rho(n: &BigUint) -> BigUint {
+    if n.is_even() {
+        return BigUint::from(2u32);
+    }
+
+    let distance = |x: &BigUint, y: &BigUint| if x > y { x - y } else { y - x };
+
+    'retry: for c in 1u32.. {
+        let step = |x: &BigUint| (x * x + c) % n;
+        let mut slow = BigUint::from(2u32);
+        let mut fast = slow.clone();
+
+        loop {
+            let start = (slow.clone(), fast.clone());
+            let mut product = BigUint::one();
+
+            for _ in 0..RHO_BATCH {
+                slow = step(&slow);
+                fast = step(&step(&fast));
+                product = product * distance(&slow, &fast) % n;
+            }
+
+            if product.gcd(n).is_one() {
+                continue;
+            }
+
+            let (mut slow, mut fast) = start;
+            loop {
+                slow = step(&slow);
+                fast = step(&step(&fast));
+
+                let divisor = distance(&slow, &fast).gcd(n);
+                if divisor == *n {
+                    continue 'retry;
+                } else if !divisor.is_one() {
+                    return divisor;
+                }
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// Euler's totient of ``n``: how many numbers from 1
+/// to ``n`` have no factor in common with it.
+pub fn totient(n: &BigUint) -> BigUint {
+    let mut factors = factor(n);
+    factors.dedup();
+
+    factors
+        .iter()
+        .fold(n.clone(), |phi, p| phi / p * (p - 1u32))
+}
+
+/// ``base`` to the power of ``exp``, modulo ``m``,
+/// from zero up to ``m``, which must be positive.
+/// ``exp`` must not be negative.
+pub fn mod_pow(base: &BigInt, exp: &BigInt, m: &BigInt) -> BigInt {
+    base.modpow(exp, m)
+}
+
+/// The inverse of ``a`` modulo ``m``, from 0 to
+/// ``m - 1``, if ``a`` and ``m`` have no factor in
+/// common. ``m`` must be positive.
+pub fn mod_inv(a: &BigInt, m: &BigInt) -> Option<BigInt> {
+    let gcd = a.mod_floor(m).extended_gcd(m);
+
+    if gcd.gcd.is_one() {
+        Some(gcd.x.mod_floor(m))
+    } else {
+        None
+    }
+}
+
+/// The largest number whose square is at most ``n``,
+/// which must not be negative.
+pub fn isqrt(n: &BigInt) -> BigInt {
+    n.sqrt()
+}
+
+/// The number of ways to choose ``k`` things from
+/// ``n``, unless it is too large to compute. ``n``
+/// must not be negative, but ``k`` may be, in which
+/// case there are no ways.
+pub fn binomial(n: &BigInt, k: &BigInt) -> Option<BigInt> {
+    if k.is_negative() || k > n {
+        return Some(BigInt::zero());
+    }
+
+    // Each partial product is itself a binomial
+    // coefficient, and they only grow up to the
+    // middle, so the division is exact and the size
+    // can be checked as it goes.
+    let k = k.min(&(n - k)).to_u64()?;
+    let mut result = BigInt::one();
+
+    for i in 0..k {
+        result = result * (n - i) / (i + 1);
+        if result.bits() > MAX_BITS {
+            return None;
+        }
+    }
+
+    Some(result)
+}
+
+/// The factorial of ``n``, which must not be negative,
+/// unless it is too large to compute.
+pub fn factorial(n: &BigInt) -> Option<BigInt> {
+    let mut result = BigInt::one();
+
+    for i in 2..=n.to_u64()? {
+        result *= i;
+        if result.bits() > MAX_BITS {
+            return None;
+        }
+    }
+
+    Some(result)
+}
+
+/// ``x`` to the power of ``y``, which must not be
+/// negative, unless it is too large to compute.
+pub fn pow(x: &BigInt, y: &BigInt) -> Option<BigInt> {
+    if x.magnitude().is_one() || x.is_zero() {
+        // Powers of -1, 0, and 1 stay small however large
+        // the exponent, so only whether it is zero or odd
+        // matters.
+        let y = if y.is_zero() {
+            0
+        } else if y.is_odd() {
+            1
+        } else {
+            2
+        };
+        return Some(x.pow(y));
+    }
+
+    let y = y
+        .to_u32()
+        .filter(|&y| (x.bits() - 1) * u64::from(y) <= MAX_BITS)?;
+    Some(x.pow(y))
+}
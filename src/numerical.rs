@@ -0,0 +1,631 @@
+//! Numerical methods
+//!
+//! This module provides the routines behind the words
+//! that find the roots, minima, integrals, and
+//! derivatives of functions given as quotations. The
+//! functions are closures that may fail, as running a
+//! quotation can, and a failure stops the method and
+//! is passed on.
+//!
+//! Every method stops once it has found its answer to
+//! within the ``Limits``, or gives up once it has taken
+//! as many iterations as they allow.
+
+use std::fmt;
+
+/// The numerical methods that take a function as a
+/// quotation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// Find a root within an interval by bisection.
+    Bisect,
+
+    /// Find a root near a guess by Newton's method.
+    Newton,
+
+    /// Find a root within an interval by Brent's
+    /// method.
+    Brent,
+
+    /// Find a minimum within an interval by Brent's
+    /// method.
+    Minimize,
+
+    /// Integrate over an interval by adaptive
+    /// Gauss-Kronrod quadrature.
+    Integrate,
+
+    /// Differentiate at a point by Richardson
+    /// extrapolation.
+    Derivative,
+}
+
+impl Method {
+    /// Returns the method with the given name, if
+    /// there is one.
+    pub fn from_name(name: &str) -> Option<Method> {
+        match name {
+            "bisect" => Some(Method::Bisect),
+            "newton" => Some(Method::Newton),
+            "brent" => Some(Method::Brent),
+            "minimize" => Some(Method::Minimize),
+            "integrate" => Some(Method::Integrate),
+            "derivative" => Some(Method::Derivative),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the word that runs the
+    /// method.
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::Bisect => "bisect",
+            Method::Newton => "newton",
+            Method::Brent => "brent",
+            Method::Minimize => "minimize",
+            Method::Integrate => "integrate",
+            Method::Derivative => "derivative",
+        }
+    }
+
+    /// Returns how many numbers the method takes after
+    /// its quotation: two for the ends of an interval,
+    /// or one for a guess or point.
+    pub fn points(self) -> usize {
+        match self {
+            Method::Newton | Method::Derivative => 1,
+            Method::Bisect | Method::Brent | Method::Minimize | Method::Integrate => 2,
+        }
+    }
+
+    /// Returns the finest tolerance that the method can
+    /// reach with numbers of the given machine epsilon.
+    ///
+    /// Roots can be found to the last digit, but a
+    /// minimum only to half the digits, since the
+    /// function is too flat there to tell points apart.
+    /// Rounding limits the error estimates of integrals,
+    /// and differences lose about a third of the digits
+    /// of a derivative.
+    pub fn finest_tolerance(self, epsilon: f64) -> f64 {
+        match self {
+            Method::Bisect | Method::Newton | Method::Brent => epsilon,
+            Method::Minimize => epsilon.sqrt(),
+            Method::Integrate => 50.0 * epsilon,
+            Method::Derivative => epsilon.powf(2.0 / 3.0),
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How closely, and for how long, the numerical
+/// methods work toward an answer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// The error allowed in an answer, relative to its
+    /// size, or absolute for answers smaller than one
+    pub tolerance: f64,
+
+    /// The most iterations a method may take
+    pub iterations: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            tolerance: 1e-12,
+            iterations: 100,
+        }
+    }
+}
+
+impl Limits {
+    /// The limits for numbers with the given machine
+    /// epsilon, which no tolerance can be finer than.
+    pub fn at_precision(self, epsilon: f64) -> Limits {
+        Limits {
+            tolerance: self.tolerance.max(epsilon),
+            ..self
+        }
+    }
+
+    // The error allowed in an answer near ``x``
+    fn allowed(&self, x: f64) -> f64 {
+        self.tolerance * x.abs().max(1.0)
+    }
+}
+
+/// Represents the ways that a numerical method can
+/// fail.
+#[derive(Debug, PartialEq)]
+pub enum Failure<E> {
+    /// The function failed. Holds its error.
+    Function(E),
+
+    /// The function has the same sign at both ends of
+    /// the interval, so a root cannot be bracketed.
+    NotBracketed,
+
+    /// An end of the interval is infinite or not a
+    /// number.
+    NotFinite,
+
+    /// The method did not find an answer within the
+    /// iteration limit.
+    NoConvergence,
+}
+
+/// Find a root of ``f`` between ``a`` and ``b``, where
+/// it must change sign, by bisection.
+///
+/// This is slow but certain: each iteration halves the
+/// interval.
+pub fn bisect<F, E>(mut f: F, mut a: f64, mut b: f64, limits: Limits) -> Result<f64, Failure<E>>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    let mut f = |x| f(x).map_err(Failure::Function);
+    let fa = f(a)?;
+    let fb = f(b)?;
+
+    if fa == 0.0 {
+        return Ok(a);
+    } else if fb == 0.0 {
+        return Ok(b);
+    } else if fa.is_sign_negative() == fb.is_sign_negative() {
+        return Err(Failure::NotBracketed);
+    }
+
+    let negative_at_a = fa.is_sign_negative();
+
+    for _ in 0..limits.iterations {
+        let mid = a + (b - a) / 2.0;
+        if (b - a).abs() / 2.0 <= limits.allowed(mid) {
+            return Ok(mid);
+        }
+
+        let fm = f(mid)?;
+        if fm == 0.0 {
+            return Ok(mid);
+        } else if fm.is_sign_negative() == negative_at_a {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+
+    Err(Failure::NoConvergence)
+}
+
+/// Find a root of ``f`` near the guess ``x`` by
+/// Newton's method.
+///
+/// The derivative is estimated by central differences,
+/// over steps scaled to the tolerance. The method
+/// converges quickly near a simple root, but may
+/// wander off from a poor guess, and fails where the
+/// function is flat.
+pub fn newton<F, E>(mut f: F, mut x: f64, limits: Limits) -> Result<f64, Failure<E>>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    let mut f = |x| f(x).map_err(Failure::Function);
+
+    for _ in 0..limits.iterations {
+        let fx = f(x)?;
+        if fx == 0.0 {
+            return Ok(x);
+        }
+
+        let h = limits.tolerance.cbrt() * x.abs().max(1.0);
+        let slope = (f(x + h)? - f(x - h)?) / (2.0 * h);
+
+        let step = fx / slope;
+        if !step.is_finite() {
+            return Err(Failure::NoConvergence);
+        }
+
+        x -= step;
+        if step.abs() <= limits.allowed(x) {
+            return Ok(x);
+        }
+    }
+
+    Err(Failure::NoConvergence)
+}
+
+/// Find a root of ``f`` between ``a`` and ``b``, where
+/// it must change sign, by Brent's method.
+///
+/// This interpolates where it can and bisects where it
+/// must, so it is as certain as bisection but usually
+/// much faster.
+pub fn brent<F, E>(mut f: F, mut a: f64, mut b: f64, limits: Limits) -> Result<f64, Failure<E>>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    let mut f = |x| f(x).map_err(Failure::Function);
+    let mut fa = f(a)?;
+    let mut fb = f(b)?;
+
+    if fa == 0.0 {
+        return Ok(a);
+    } else if fb == 0.0 {
+        return Ok(b);
+    } else if fa.is_sign_negative() == fb.is_sign_negative() {
+        return Err(Failure::NotBracketed);
+    }
+
+    // The root is between ``b``, the best estimate so
+    // far, and ``c``; ``a`` is the previous estimate.
+    let (mut c, mut fc) = (b, fb);
+    let (mut d, mut e) = (0.0, 0.0);
+
+    for _ in 0..limits.iterations {
+        if fb.is_sign_negative() == fc.is_sign_negative() {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+
+        let tol = 2.0 * f64::EPSILON * b.abs() + limits.allowed(b) / 2.0;
+        let half = (c - b) / 2.0;
+        if half.abs() <= tol || fb == 0.0 {
+            return Ok(b);
+        }
+
+        if e.abs() >= tol && fa.abs() > fb.abs() {
+            // Interpolate, linearly from two points or
+            // inversely quadratically from three.
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * half * s, 1.0 - s)
+            } else {
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * half * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+
+            // Only accept the interpolation if it stays
+            // within the bracket and is shrinking fast
+            // enough.
+            if 2.0 * p < (3.0 * half * q - (tol * q).abs()).min((e * q).abs()) {
+                e = d;
+                d = p / q;
+            } else {
+                d = half;
+                e = d;
+            }
+        } else {
+            d = half;
+            e = d;
+        }
+
+        a = b;
+        fa = fb;
+        b += if d.abs() > tol { d } else { tol.copysign(half) };
+        fb = f(b)?;
+    }
+
+    Err(Failure::NoConvergence)
+}
+
+/// Find a minimum of ``f`` between ``a`` and ``b`` by
+/// Brent's method.
+///
+/// This takes parabolic steps where they can be trusted
+/// and golden-section steps where they cannot. If the
+/// function has several minima in the interval, any
+/// one of them may be found.
+///
+/// The tolerance is no finer than ``finest_tolerance``
+/// allows for ``f64``.
+pub fn minimize<F, E>(mut f: F, a: f64, b: f64, limits: Limits) -> Result<f64, Failure<E>>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    const GOLDEN: f64 = 0.381_966_011_250_105_1; // (3 - √5) / 2
+
+    let mut f = |x| f(x).map_err(Failure::Function);
+    let limits = limits.at_precision(Method::Minimize.finest_tolerance(f64::EPSILON));
+    let (mut a, mut b) = if a < b { (a, b) } else { (b, a) };
+
+    // ``x`` is the best point so far, ``w`` the second
+    // best, and ``v`` the previous value of ``w``.
+    let mut x = a + GOLDEN * (b - a);
+    let mut fx = f(x)?;
+    let (mut w, mut fw) = (x, fx);
+    let (mut v, mut fv) = (x, fx);
+    let (mut d, mut e) = (0.0f64, 0.0f64);
+
+    for _ in 0..limits.iterations {
+        let mid = (a + b) / 2.0;
+        let tol = limits.allowed(x) / 2.0;
+        if (x - mid).abs() <= 2.0 * tol - (b - a) / 2.0 {
+            return Ok(x);
+        }
+
+        let mut golden = true;
+        if e.abs() > tol {
+            // Fit a parabola through x, w, and v.
+            let r = (x - w) * (fx - fv);
+            let mut q = (x - v) * (fx - fw);
+            let mut p = (x - v) * q - (x - w) * r;
+            q = 2.0 * (q - r);
+            if q > 0.0 {
+                p = -p;
+            }
+            q = q.abs();
+
+            if p.abs() < (q * e / 2.0).abs() && p > q * (a - x) && p < q * (b - x) {
+                e = d;
+                d = p / q;
+                golden = false;
+
+                // Keep away from the ends.
+                let u = x + d;
+                if u - a < 2.0 * tol || b - u < 2.0 * tol {
+                    d = tol.copysign(mid - x);
+                }
+            }
+        }
+
+        if golden {
+            e = if x >= mid { a - x } else { b - x };
+            d = GOLDEN * e;
+        }
+
+        let u = if d.abs() >= tol {
+            x + d
+        } else {
+            x + tol.copysign(d)
+        };
+        let fu = f(u)?;
+
+        if fu <= fx {
+            if u >= x {
+                a = x;
+            } else {
+                b = x;
+            }
+            v = w;
+            fv = fw;
+            w = x;
+            fw = fx;
+            x = u;
+            fx = fu;
+        } else {
+            if u < x {
+                a = u;
+            } else {
+                b = u;
+            }
+
+            if fu <= fw || w == x {
+                v = w;
+                fv = fw;
+                w = u;
+                fw = fu;
+            } else if fu <= fv || v == x || v == w {
+                v = u;
+                fv = fu;
+            }
+        }
+    }
+
+    Err(Failure::NoConvergence)
+}
+
+// The nodes of the 15-point Kronrod rule on [-1, 1],
+// from the outside in. Those at odd indices are also
+// the nodes of the 7-point Gauss rule.
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6,
+    0.949_107_912_342_758_5,
+    0.864_864_423_359_769_1,
+    0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1,
+    0.405_845_151_377_397_2,
+    0.207_784_955_007_898_5,
+    0.0,
+];
+
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_22,
+    0.063_092_092_629_978_55,
+    0.104_790_010_322_250_18,
+    0.140_653_259_715_525_92,
+    0.169_004_726_639_267_9,
+    0.190_350_578_064_785_4,
+    0.204_432_940_075_298_9,
+    0.209_482_141_084_727_83,
+];
+
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.129_484_966_168_869_7,
+    0.279_705_391_489_276_7,
+    0.381_830_050_505_118_9,
+    0.417_959_183_673_469_4,
+];
+
+/// Integrate ``f`` from ``a`` to ``b``, which must both
+/// be finite, by adaptive Gauss-Kronrod quadrature.
+///
+/// Each piece of the interval is integrated by the
+/// 15-point Kronrod rule, and its error estimated from
+/// the embedded 7-point Gauss rule, as in QUADPACK. The
+/// piece with the largest error is halved in each
+/// iteration, until the total error is within the
+/// tolerance of the integral.
+pub fn integrate<F, E>(mut f: F, a: f64, b: f64, limits: Limits) -> Result<f64, Failure<E>>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    if !(a.is_finite() && b.is_finite()) {
+        return Err(Failure::NotFinite);
+    }
+
+    let mut f = |x| f(x).map_err(Failure::Function);
+    let limits = limits.at_precision(Method::Integrate.finest_tolerance(f64::EPSILON));
+
+    // Each piece, as its ends, its integral, and the
+    // estimated error of that integral
+    let mut pieces = vec![(a, b, kronrod(&mut f, a, b)?)];
+
+    for _ in 0..limits.iterations {
+        let integral: f64 = pieces.iter().map(|(_, _, (value, _))| value).sum();
+        let error: f64 = pieces.iter().map(|(_, _, (_, error))| error).sum();
+        if error <= limits.allowed(integral) {
+            return Ok(integral);
+        }
+
+        let worst = (0..pieces.len())
+            .max_by(|&i, &j| (pieces[i].2).1.total_cmp(&(pieces[j].2).1))
+            .unwrap();
+        let (lo, hi, _) = pieces[worst];
+        let mid = lo + (hi - lo) / 2.0;
+
+        // The piece is too small to halve.
+        if mid == lo || mid == hi {
+            break;
+        }
+
+        pieces[worst] = (lo, mid, kronrod(&mut f, lo, mid)?);
+        pieces.push((mid, hi, kronrod(&mut f, mid, hi)?));
+    }
+
+    Err(Failure::NoConvergence)
+}
+
+// Integrate ``f`` from ``a`` to ``b`` by the 15-point
+// Kronrod rule, giving the integral and an estimate of
+// its error.
+fn kronrod<F, E>(f: &mut F, a: f64, b: f64) -> Result<(f64, f64), E>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    let center = (a + b) / 2.0;
+    let half = (b - a) / 2.0;
+
+    let f_center = f(center)?;
+    let mut gauss = f_center * GAUSS_WEIGHTS[3];
+    let mut kronrod = f_center * KRONROD_WEIGHTS[7];
+    let mut values = [(0.0, 0.0); 7];
+
+    for (j, node) in KRONROD_NODES[..7].iter().enumerate() {
+        let pair = (f(center - half * node)?, f(center + half * node)?);
+        kronrod += KRONROD_WEIGHTS[j] * (pair.0 + pair.1);
+        if j % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[j / 2] * (pair.0 + pair.1);
+        }
+        values[j] = pair;
+    }
+
+    // QUADPACK's estimate, which scales the difference
+    // between the rules by how much the function varies
+    // over the piece, since the difference alone is far
+    // too pessimistic for smooth functions.
+    let mean = kronrod / 2.0;
+    let mut variation = KRONROD_WEIGHTS[7] * (f_center - mean).abs();
+    let mut magnitude = KRONROD_WEIGHTS[7] * f_center.abs();
+    for (j, (lo, hi)) in values.iter().enumerate() {
+        variation += KRONROD_WEIGHTS[j] * ((lo - mean).abs() + (hi - mean).abs());
+        magnitude += KRONROD_WEIGHTS[j] * (lo.abs() + hi.abs());
+    }
+
+    let (variation, magnitude) = (variation * half.abs(), magnitude * half.abs());
+    let mut error = ((kronrod - gauss) * half).abs();
+
+    if variation != 0.0 && error != 0.0 {
+        error = variation * (200.0 * error / variation).powf(1.5).min(1.0);
+    }
+
+    // Rounding limits how small the error can be.
+    error = error.max(50.0 * f64::EPSILON * magnitude);
+
+    Ok((kronrod * half, error))
+}
+
+/// The derivative of ``f`` at ``x``, by central
+/// differences with Richardson extrapolation.
+///
+/// This is Ridders' method: the step is shrunk in each
+/// iteration, and the differences extrapolated to a
+/// step of zero, stopping once the estimated error is
+/// within the tolerance, or once it starts to grow as
+/// rounding takes over.
+///
+/// The first step is a tenth of ``x``, or of one if
+/// ``x`` is smaller, so ``f`` must be defined that far
+/// either side.
+pub fn derivative<F, E>(mut f: F, x: f64, limits: Limits) -> Result<f64, Failure<E>>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    const SHRINK: f64 = 1.4;
+
+    let mut f = |x| f(x).map_err(Failure::Function);
+    let mut difference =
+        |h: f64| -> Result<f64, Failure<E>> { Ok((f(x + h)? - f(x - h)?) / (2.0 * h)) };
+
+    let mut h = 0.1 * x.abs().max(1.0);
+    let mut previous = vec![difference(h)?];
+    let mut best = (previous[0], f64::INFINITY);
+
+    for _ in 1..limits.iterations {
+        h /= SHRINK;
+
+        // Each row extrapolates the last to one more
+        // order.
+        let mut row = vec![difference(h)?];
+        let mut factor = SHRINK * SHRINK;
+        for j in 0..previous.len() {
+            let next = (row[j] * factor - previous[j]) / (factor - 1.0);
+            factor *= SHRINK * SHRINK;
+
+            let error = (next - row[j]).abs().max((next - previous[j]).abs());
+            if error <= best.1 {
+                best = (next, error);
+            }
+            row.push(next);
+        }
+
+        if best.1 <= limits.allowed(best.0) {
+            return Ok(best.0);
+        }
+
+        // Rounding has set in, and no better estimate
+        // will come.
+        let last = previous.len();
+        if (row[last] - previous[last - 1]).abs() >= 2.0 * best.1 {
+            break;
+        }
+
+        previous = row;
+    }
+
+    Err(Failure::NoConvergence)
+}
@@ -1,34 +1,27 @@
 //! Parsing of raw string input
 //!
 //! This module provides the parser, which processes one line
-//! of string input at a time, but could be expanded to process
-//! files.
+//! of string input at a time. Whole files are processed
+//! line by line, by the ``script`` module.
+//!
+//! The parser is a hand-written lexer, which splits a
+//! line into tokens and recognizes the form of each one
+//! in a single pass, without backtracking.
 
 use super::{
-    functions::{BuiltinFun, INTRINSIC_FNS},
-    value::Value,
+    functions::{BuiltinFun, BUILTINS, INTRINSIC_FNS},
+    matrix::{Dense, Matrix},
+    units::{Quantity, Unit},
+    value::{Key, Map, Type, Value, INTEGER_SUFFIXES},
 };
-use lazy_static::lazy_static;
-use regex::RegexSet;
-use std::str::FromStr;
-
-#[rustfmt::skip]
-lazy_static! {
-    // The patterns that represent different value literals
-    static ref VALUE_LITERALS: RegexSet = RegexSet::new(&[
-        r"^-?((\d+\.\d*)|(\d*\.\d+))f$", // Float32 literal
-        r"^-?((\d+\.\d*)|(\d*\.\d+))$", // Float64 literal
-        r"^-?\d+$",                     // Int32 literal
-    ]).unwrap();
-}
-
-// The following are indices into the lazy static RegexSet above.
-const FLOAT32_LITERAL_IDX: usize = 0;
-const FLOAT64_LITERAL_IDX: usize = 1;
-const INT32_LITERAL_IDX: usize = 2;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use std::{ops::Range, str::FromStr};
 
 /// The result of parsing a token
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ParsedToken {
     /// Results from parsing a ``Value`` literal
     Literal(Value),
@@ -36,49 +29,720 @@ pub enum ParsedToken {
     /// Results from parsing a builtin function
     Intrinsic(&'static BuiltinFun),
 
+    /// Results from parsing a unit expression, such
+    /// as ``m/s^2``, which attaches a unit to a number
+    Unit(Unit),
+
+    /// Results from parsing ``>`` followed by a unit
+    /// expression, such as ``>km/h``, which converts a
+    /// quantity to that unit
+    Conversion(Unit),
+
+    /// Results from parsing a name followed by ``!``,
+    /// such as ``x!``, which stores a variable
+    Store(String),
+
+    /// Results from parsing a name followed by ``@``,
+    /// such as ``x@``, which recalls a variable
+    Recall(String),
+
+    /// Results from parsing any other name, which is
+    /// looked up when it runs
+    Word(String),
+
+    /// Results from parsing a name qualified by its
+    /// vocabulary, such as ``stats:mean``, which holds
+    /// the vocabulary and the name
+    Qualified(String, String),
+
+    /// Results from parsing ``:``, which begins a word
+    /// definition
+    StartDefinition,
+
+    /// Results from parsing ``::``, which begins a word
+    /// definition that binds its inputs to local names
+    StartLocalsDefinition,
+
+    /// Results from parsing ``;``, which ends a word
+    /// definition
+    EndDefinition,
+
+    /// Results from parsing ``[``, which begins a
+    /// quotation
+    StartQuotation,
+
+    /// Results from parsing ``]``, which ends a
+    /// quotation
+    EndQuotation,
+
+    /// Results from parsing ``(``, which begins a
+    /// stack effect
+    StartEffect,
+
+    /// Results from parsing ``)``, which ends a
+    /// stack effect
+    EndEffect,
+
+    /// Results from parsing ``$(`` up to its matching
+    /// ``)``, an infix expression, which holds the
+    /// expression between them
+    Infix(String),
+
     /// Results from a failed parse
     BadToken,
 }
 
+/// A token of input, along with where it was found
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+
+    /// The byte offsets of the token within its line
+    pub span: Range<usize>,
+
+    pub parsed: ParsedToken,
+}
+
 /// Parse a single line of input, returning
 /// both the string tokens and their parsed
 /// values, if they were valid.
 ///
 /// It is assumed that the input does not contain any newlines.
+/// A ``\`` token begins a comment, which runs to the end of
+/// the line.
 pub fn parse_line<'a>(line: &'a str) -> impl Iterator<Item = (&str, ParsedToken)> + 'a {
-    split_tokens(line).map(|token| (token, analyze_token(token)))
+    tokenize(line).map(|token| (token.text, token.parsed))
 }
 
-fn split_tokens(line: &str) -> impl Iterator<Item = &str> {
-    line.split(char::is_whitespace).filter(|s| !s.is_empty())
+/// Parse a single line of input, as ``parse_line`` does,
+/// returning where each token was found.
+pub fn tokenize(line: &str) -> Tokens<'_> {
+    Tokens { line, pos: 0 }
+}
+
+/// An iterator over the tokens of a line, returned by
+/// ``tokenize``
+pub struct Tokens<'a> {
+    line: &'a str,
+
+    /// The byte offset of the rest of the line
+    pos: usize,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.line[self.pos..];
+        let start = self.pos + rest.find(|c: char| !c.is_whitespace())?;
+
+        if self.line[start..].starts_with("$(") {
+            return Some(self.infix(start));
+        }
+
+        // Tokens end at whitespace, except inside a
+        // string, which may hold spaces.
+        let mut in_str = false;
+        let len = self.line[start..]
+            .char_indices()
+            .find(|&(_, c)| {
+                in_str ^= c == '"';
+                c.is_whitespace() && !in_str
+            })
+            .map_or(self.line.len() - start, |(idx, _)| idx);
+
+        let end = start + len;
+        let text = &self.line[start..end];
+
+        if text == "\\" {
+            self.pos = self.line.len();
+            return None;
+        }
+
+        self.pos = end;
+        Some(Token {
+            text,
+            span: start..end,
+            parsed: analyze_token(text),
+        })
+    }
+}
+
+impl<'a> Tokens<'a> {
+    // Take an infix expression, which may hold spaces,
+    // up to the ``)`` that matches its ``$(``.
+    fn infix(&mut self, start: usize) -> Token<'a> {
+        let mut depth = 0;
+        let mut in_str = false;
+
+        let close = self.line[start..].char_indices().find(|&(_, c)| {
+            match c {
+                '"' => in_str = !in_str,
+                '(' if !in_str => depth += 1,
+                ')' if !in_str => depth -= 1,
+                _ => return false,
+            }
+
+            depth == 0 && !in_str
+        });
+
+        let (end, parsed) = match close {
+            Some((idx, _)) => {
+                let expr = &self.line[(start + 2)..(start + idx)];
+                (start + idx + 1, ParsedToken::Infix(expr.to_string()))
+            }
+            None => (self.line.len(), ParsedToken::BadToken),
+        };
+
+        let text = &self.line[start..end];
+        self.pos = end;
+
+        Token {
+            text,
+            span: start..end,
+            parsed,
+        }
+    }
 }
 
+// Recognize a token by its first characters, and then
+// parse it as that kind of token.
 fn analyze_token(token: &str) -> ParsedToken {
-    let matches = VALUE_LITERALS.matches(token);
-
-    if matches.iter().any(|idx| idx == FLOAT32_LITERAL_IDX) {
-        parse_float32(token)
-    } else if matches.iter().any(|idx| idx == FLOAT64_LITERAL_IDX) {
-        parse_float64(token)
-    } else if matches.iter().any(|idx| idx == INT32_LITERAL_IDX) {
-        parse_int32(token)
+    match token.as_bytes() {
+        b":" => ParsedToken::StartDefinition,
+        b"::" => ParsedToken::StartLocalsDefinition,
+        b";" => ParsedToken::EndDefinition,
+        b"[" => ParsedToken::StartQuotation,
+        b"]" => ParsedToken::EndQuotation,
+        b"(" => ParsedToken::StartEffect,
+        b")" => ParsedToken::EndEffect,
+        b"true" => ParsedToken::Literal(Value::Bool(true)),
+        b"false" => ParsedToken::Literal(Value::Bool(false)),
+        [b'"', .., b'"'] => parse_str(token),
+        [b'\'', ..] => parse_symbol(token),
+        [b'[', .., b']'] => parse_matrix(token),
+        [b'{', .., b'}'] => parse_list(token),
+        [b'#', b'{', .., b'}'] => parse_map(token),
+        _ => match scan_number(token) {
+            Some(parsed) => parsed,
+            None => match INTRINSIC_FNS.get(token) {
+                Some(fun) => ParsedToken::Intrinsic(fun),
+                None => analyze_name(token),
+            },
+        },
+    }
+}
+
+// Names that are not builtin functions may be units,
+// or may refer to variables.
+fn analyze_name(token: &str) -> ParsedToken {
+    if let Some(target) = token.strip_prefix('>') {
+        return match Unit::parse(target) {
+            Some(unit) => ParsedToken::Conversion(unit),
+            None => ParsedToken::BadToken,
+        };
+    }
+
+    if let Some(unit) = Unit::parse(token) {
+        return ParsedToken::Unit(unit);
+    }
+
+    if let Some((vocab, name)) = token.rsplit_once(':') {
+        return analyze_qualified(vocab, name);
+    }
+
+    if let Some(name) = token.strip_suffix('!').filter(|n| is_identifier(n)) {
+        ParsedToken::Store(name.to_string())
+    } else if let Some(name) = token.strip_suffix('@').filter(|n| is_identifier(n)) {
+        ParsedToken::Recall(name.to_string())
+    } else if is_identifier(token) {
+        ParsedToken::Word(token.to_string())
     } else {
-        match INTRINSIC_FNS.get(token) {
+        ParsedToken::BadToken
+    }
+}
+
+// Builtins are known now, but other qualified names
+// are looked up when they run.
+fn analyze_qualified(vocab: &str, name: &str) -> ParsedToken {
+    if vocab == BUILTINS {
+        match INTRINSIC_FNS.get(name) {
             Some(fun) => ParsedToken::Intrinsic(fun),
             None => ParsedToken::BadToken,
         }
+    } else if is_vocabulary_name(vocab) && is_identifier(name) {
+        ParsedToken::Qualified(vocab.to_string(), name.to_string())
+    } else {
+        ParsedToken::BadToken
     }
 }
 
-fn parse_float32(token: &str) -> ParsedToken {
+/// Returns ``true`` if the name can be used for a
+/// variable or word. Identifiers start with a letter
+/// or underscore, and continue with letters, digits,
+/// or underscores.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Returns ``true`` if the name can be used for a
+/// vocabulary. Vocabulary names are identifiers
+/// separated by ``.``, as in ``math.stats``.
+pub(crate) fn is_vocabulary_name(name: &str) -> bool {
+    name.split('.').all(is_identifier)
+}
+
+// Scan a number literal. Numbers are integers, such as
+// ``-42`` or ``0xff``, which are ``Int32``s unless a
+// suffix gives their type, as in ``255u8``, or decimals,
+// such as ``2.5``, ``.5``, or ``6.02e23``, which are
+// ``Float64``s. A decimal, or a decimal integer, with
+// an ``f`` suffix is a ``Float32``. ``inf`` and ``nan``
+// are decimals too. Digits may be grouped with ``_``,
+// as in ``1_000_000``, so long as each ``_`` is between
+// two digits.
+//
+// Two decimal integers joined by ``/``, as in ``-1/2``,
+// are a ``Rational``, and a decimal followed by ``i``,
+// as in ``2i``, or added to one, as in ``1-2.5i``, is
+// a ``Complex``.
+//
+// A decimal, or a decimal integer, followed at once by
+// a unit, as in ``90min`` or ``9.81m/s^2``, is a
+// ``Quantity``. This is the only way to attach a unit
+// that is also the name of a builtin, such as ``min``.
+//
+// Returns ``None`` if the token is not a number, so
+// that it may be parsed as something else. A number
+// that does not fit in its type is a bad token.
+fn scan_number(token: &str) -> Option<ParsedToken> {
+    let rational = token.split_once('/');
+    if let Some(parsed) = rational.and_then(|(numer, denom)| scan_rational(numer, denom)) {
+        return Some(parsed);
+    }
+
+    if let Some(parsed) = token.strip_suffix('i').and_then(scan_complex) {
+        return Some(parsed);
+    }
+
+    let suffixed = INTEGER_SUFFIXES.iter().find_map(|(suffix, typ)| {
+        let digits = token.strip_suffix(suffix)?;
+        scan_signed_integer(digits, typ)
+    });
+    if suffixed.is_some() {
+        return suffixed;
+    }
+
+    let (negative, rest) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+
+    if let Some((radix, digits)) = radix_prefix(rest) {
+        return scan_integer(digits, radix, negative, &Type::Int32);
+    }
+
+    let bytes = rest.as_bytes();
+
+    let mut pos = 0;
+    let skip_digits = |pos: &mut usize| {
+        let start = *pos;
+        while bytes.get(*pos).is_some_and(u8::is_ascii_digit)
+            || (*pos > start && bytes.get(*pos) == Some(&b'_') && bytes[*pos - 1] != b'_')
+        {
+            *pos += 1;
+        }
+        *pos - start
+    };
+
+    let mut digits = skip_digits(&mut pos);
+    let mut is_integer = true;
+
+    if bytes.get(pos) == Some(&b'.') {
+        pos += 1;
+        digits += skip_digits(&mut pos);
+        is_integer = false;
+    }
+
+    if digits == 0 {
+        match rest {
+            "inf" | "inff" => {}
+            "nan" | "nanf" if !negative => {}
+            _ => return None,
+        }
+
+        pos = 3;
+        is_integer = false;
+    } else if let Some(b'e') | Some(b'E') = bytes.get(pos) {
+        let before = pos;
+        pos += 1;
+        if let Some(b'+') | Some(b'-') = bytes.get(pos) {
+            pos += 1;
+        }
+
+        // Without digits, the ``e`` may begin a unit,
+        // as in ``3eV``.
+        if skip_digits(&mut pos) == 0 {
+            pos = before;
+        } else {
+            is_integer = false;
+        }
+    }
+
+    let mantissa = &token[..(token.len() - rest.len() + pos)];
+
+    // A separator must be followed by a digit, which
+    // ``skip_digits`` does not check.
+    let bad_separator = ["_.", "_e", "_E"]
+        .iter()
+        .any(|pair| mantissa.contains(pair));
+    if bad_separator || mantissa.ends_with('_') {
+        return None;
+    }
+
+    let parsed = match &rest[pos..] {
+        "" if is_integer => return scan_integer(rest, 10, negative, &Type::Int32),
+        "" => parse_float(mantissa).map(Value::Float64),
+        "f" => parse_float(mantissa).map(Value::Float32),
+        unit => {
+            let unit = Unit::parse(unit)?;
+            parse_float(mantissa).map(|x| Quantity::new(x, unit).into_value())
+        }
+    };
+
+    Some(parsed.map_or(ParsedToken::BadToken, ParsedToken::Literal))
+}
+
+// Parse a decimal that has already been scanned, unless
+// it is too large for its type.
+fn parse_float<F>(mantissa: &str) -> Option<F>
+where
+    F: FromStr + Into<f64> + Copy,
+{
+    let x = F::from_str(&mantissa.replace('_', "")).ok()?;
+    let x_f64: f64 = x.into();
+
+    if x_f64.is_infinite() && !mantissa.ends_with("inf") {
+        None
+    } else {
+        Some(x)
+    }
+}
+
+// Scan a fraction, whose denominator must be positive.
+// The fraction is kept in lowest terms.
+fn scan_rational(numer: &str, denom: &str) -> Option<ParsedToken> {
+    let (negative, numer) = match numer.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, numer),
+    };
+
+    let numer = big_decimal(numer)?;
+    let denom = big_decimal(denom)?;
+
+    Some(if denom.is_zero() {
+        ParsedToken::BadToken
+    } else {
+        let numer = if negative { -numer } else { numer };
+        ParsedToken::Literal(Value::Rational(BigRational::new(numer, denom)))
+    })
+}
+
+// Read decimal digits, grouped as in ``scan_integer``,
+// as an integer of any size.
+fn big_decimal(digits: &str) -> Option<BigInt> {
+    let is_group = |group: &str| !group.is_empty() && group.bytes().all(|b| b.is_ascii_digit());
+    if !digits.split('_').all(is_group) {
+        return None;
+    }
+
+    BigInt::parse_bytes(digits.replace('_', "").as_bytes(), 10)
+}
+
+// Scan a complex number, without its ``i``. Its
+// imaginary part comes after the last sign that does
+// not begin the number or an exponent.
+fn scan_complex(body: &str) -> Option<ParsedToken> {
+    let bytes = body.as_bytes();
+    let split = (1..bytes.len())
+        .rev()
+        .find(|&idx| matches!(bytes[idx], b'+' | b'-') && !matches!(bytes[idx - 1], b'e' | b'E'));
+
+    let (re, im) = match split {
+        Some(idx) => (
+            scan_part(&body[..idx])?,
+            scan_part(body[idx..].trim_start_matches('+'))?,
+        ),
+        None => (Some(0.0), scan_part(body)?),
+    };
+
+    Some(match (re, im) {
+        (Some(re), Some(im)) => ParsedToken::Literal(Value::Complex(Complex64::new(re, im))),
+        _ => ParsedToken::BadToken,
+    })
+}
+
+// Scan one part of a complex number, which may be an
+// integer or a ``Float64``. The inner ``None`` means
+// that the part is a bad token.
+fn scan_part(part: &str) -> Option<Option<f64>> {
+    match scan_number(part)? {
+        ParsedToken::Literal(Value::Int32(x)) => Some(Some(f64::from(x))),
+        ParsedToken::Literal(Value::Float64(x)) => Some(Some(x)),
+        ParsedToken::BadToken => Some(None),
+        _ => None,
+    }
+}
+
+// Scan an integer, which may be negative and may have
+// a radix prefix, as in ``-0x80``, as an integer of
+// the given type.
+fn scan_signed_integer(token: &str, typ: &Type) -> Option<ParsedToken> {
+    let (negative, rest) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+
+    match radix_prefix(rest) {
+        Some((radix, digits)) => scan_integer(digits, radix, negative, typ),
+        None => scan_integer(rest, 10, negative, typ),
+    }
+}
+
+// Split an integer into its radix, if it has a prefix
+// such as ``0x``, and its digits.
+fn radix_prefix(token: &str) -> Option<(u32, &str)> {
+    let radix = match token.as_bytes() {
+        [b'0', b'x', ..] => 16,
+        [b'0', b'o', ..] => 8,
+        [b'0', b'b', ..] => 2,
+        _ => return None,
+    };
+
+    Some((radix, &token[2..]))
+}
+
+// Scan the digits of an integer in the given radix, as
+// an integer of the given type. A number that is too
+// large for the type is a bad token.
+//
+// Positive numbers in other radixes than decimal may
+// give all the bits of a signed type, as they are
+// shown, so ``0xffffffff`` is ``-1`` as an ``Int32``.
+fn scan_integer(digits: &str, radix: u32, negative: bool, typ: &Type) -> Option<ParsedToken> {
+    let mut separated = digits.split('_');
+    if digits.is_empty() || separated.clone().any(str::is_empty) {
+        return None;
+    }
+
+    if !separated.all(|group| group.chars().all(|c| c.is_digit(radix))) {
+        return None;
+    }
+
+    let magnitude = BigInt::parse_bytes(digits.replace('_', "").as_bytes(), radix)?;
+    let value = if negative { -magnitude } else { magnitude };
+
+    let word = match Value::integer(value.clone(), typ) {
+        None if radix != 10 && !negative => typ
+            .bits()
+            .filter(|&bits| value.bits() <= u64::from(bits))
+            .and_then(|_| Value::from_word(value.to_u64()?, typ)),
+        word => word,
+    };
+
+    Some(match word {
+        Some(n) => ParsedToken::Literal(n),
+        None => ParsedToken::BadToken,
+    })
+}
+
+// Matrix literals are written without whitespace,
+// with rows separated by ``;`` and columns by ``,``.
+// For example, ``[1,2;3,4]`` is a 2x2 matrix, and
+// ``[]`` is the empty matrix. Entries may be written
+// as any number literal without a unit.
+//
+// A matrix with a ``Complex`` entry is ``Complex``.
+// Otherwise, one with a decimal entry is ``Float64``,
+// and one with a ``Rational`` entry is ``Rational``.
+// A matrix of integers is ``Float64``.
+fn parse_matrix(token: &str) -> ParsedToken {
     let len = token.len();
-    ParsedToken::Literal(Value::Float32(f32::from_str(&token[..(len - 1)]).unwrap()))
+    let body = &token[1..(len - 1)];
+
+    if body.is_empty() {
+        let empty = Dense::new(0, 0, vec![]).unwrap();
+        return ParsedToken::Literal(Value::Matrix(Matrix::Float64(empty)));
+    }
+
+    let mut rows = 0;
+    let mut cols = None;
+    let mut data = Vec::new();
+    for row in body.split(';') {
+        let row_start = data.len();
+
+        for entry in row.split(',') {
+            match scan_number(entry) {
+                Some(ParsedToken::Literal(Value::Quantity(_))) => return ParsedToken::BadToken,
+                Some(ParsedToken::Literal(val)) => data.push(val),
+                _ => return ParsedToken::BadToken,
+            }
+        }
+
+        // Every row must be as long as the first.
+        let row_len = data.len() - row_start;
+        if *cols.get_or_insert(row_len) != row_len {
+            return ParsedToken::BadToken;
+        }
+
+        rows += 1;
+    }
+
+    let cols = cols.unwrap_or(0);
+    let any = |f: fn(&Value) -> bool| data.iter().any(f);
+
+    let matrix = if any(|val| matches!(val, Value::Complex(_))) {
+        let data = data.iter().map(|val| match val {
+            Value::Complex(z) => *z,
+            Value::Rational(x) => Complex64::new(x.to_f64().unwrap_or(f64::NAN), 0.0),
+            val => Complex64::new(val.to_f64().unwrap(), 0.0),
+        });
+        Dense::new(rows, cols, data.collect()).map(Matrix::Complex)
+    } else if any(|val| matches!(val, Value::Rational(_)))
+        && !any(|val| val.matches(&Type::Fractional))
+    {
+        let data = data.iter().map(|val| match val {
+            Value::Rational(x) => x.clone(),
+            val => BigRational::from_integer(val.to_bigint().unwrap()),
+        });
+        Dense::new(rows, cols, data.collect()).map(Matrix::Rational)
+    } else {
+        let data = data.iter().map(|val| match val {
+            Value::Rational(x) => x.to_f64().unwrap_or(f64::NAN),
+            val => val.to_f64().unwrap(),
+        });
+        Dense::new(rows, cols, data.collect()).map(Matrix::Float64)
+    };
+
+    match matrix {
+        Some(m) => ParsedToken::Literal(Value::Matrix(m)),
+        None => ParsedToken::BadToken,
+    }
+}
+
+fn parse_str(token: &str) -> ParsedToken {
+    let len = token.len();
+    let body = &token[1..(len - 1)];
+
+    if body.contains('"') {
+        ParsedToken::BadToken
+    } else {
+        ParsedToken::Literal(Value::Str(body.to_string()))
+    }
+}
+
+// Symbols are written as ``'`` followed by a name that
+// starts with a letter or underscore, and may go on to
+// use ``-``, ``?``, and ``!``, as in ``'empty?``.
+fn parse_symbol(token: &str) -> ParsedToken {
+    let name = &token[1..];
+    let mut chars = name.chars();
+
+    let valid = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_alphanumeric() || "_-?!".contains(c))
+        }
+        _ => false,
+    };
+
+    if valid {
+        ParsedToken::Literal(Value::Symbol(name.to_string()))
+    } else {
+        ParsedToken::BadToken
+    }
 }
 
-fn parse_float64(token: &str) -> ParsedToken {
-    ParsedToken::Literal(Value::Float64(f64::from_str(token).unwrap()))
+// List literals are written without whitespace, with
+// elements separated by ``,``. Elements may be any
+// literal, including other lists, as in ``{1,{2.5,'x}}``.
+fn parse_list(token: &str) -> ParsedToken {
+    let len = token.len();
+    let body = &token[1..(len - 1)];
+
+    let mut items = Vec::new();
+    for elem in split_top_level(body, ',') {
+        match analyze_token(elem) {
+            ParsedToken::Literal(val) => items.push(val),
+            _ => return ParsedToken::BadToken,
+        }
+    }
+
+    ParsedToken::Literal(Value::List(items))
+}
+
+// Map literals are written like list literals, but
+// with a leading ``#`` and ``key:value`` elements, as in
+// ``#{'x:1,"label":"hi"}``. Keys are strings or symbols.
+fn parse_map(token: &str) -> ParsedToken {
+    let len = token.len();
+    let body = &token[2..(len - 1)];
+
+    let mut map = Map::new();
+    for entry in split_top_level(body, ',') {
+        let parts = split_top_level(entry, ':');
+        if parts.len() != 2 {
+            return ParsedToken::BadToken;
+        }
+
+        let key = match analyze_token(parts[0]) {
+            ParsedToken::Literal(val) => Key::from_value(&val),
+            _ => None,
+        };
+
+        match (key, analyze_token(parts[1])) {
+            (Some(key), ParsedToken::Literal(val)) => {
+                map.insert(key, val);
+            }
+            _ => return ParsedToken::BadToken,
+        }
+    }
+
+    ParsedToken::Literal(Value::Map(map))
 }
 
-fn parse_int32(token: &str) -> ParsedToken {
-    ParsedToken::Literal(Value::Int32(i32::from_str(token).unwrap()))
+// Split the body of a compound literal at each
+// ``separator`` that is not nested inside brackets,
+// braces, or a string. An empty body has no elements.
+fn split_top_level(body: &str, separator: char) -> Vec<&str> {
+    if body.is_empty() {
+        return Vec::new();
+    }
+
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut start = 0;
+
+    for (idx, c) in body.char_indices() {
+        match c {
+            '"' => in_str = !in_str,
+            '[' | '{' if !in_str => depth += 1,
+            ']' | '}' if !in_str => depth -= 1,
+            _ if c == separator && !in_str && depth == 0 => {
+                parts.push(&body[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&body[start..]);
+    parts
 }
@@ -0,0 +1,33 @@
+\ The cattywampus prelude
+\
+\ These words are defined in the language itself, and
+\ are loaded whenever an interpreter starts. Words that
+\ need a constant work on Float64 values; the others
+\ work on any numbers, including quantities.
+
+vocab prelude
+
+\ Constants
+: pi ( -- x:Float64 ) 3.141592653589793 ;
+: tau ( -- x:Float64 ) 6.283185307179586 ;
+: e ( -- x:Float64 ) 2.718281828459045 ;
+: phi ( -- x:Float64 ) 1.618033988749895 ;
+
+\ Powers
+:: sq ( x -- y ) x x * ;
+:: cube ( x -- y ) x x * x * ;
+
+\ Signs and halves
+:: neg ( x:Float64 -- y:Float64 ) x -1.0 * ;
+:: half ( x:Float64 -- y:Float64 ) x 2.0 / ;
+
+\ Means and interpolation
+:: avg ( a:Float64 b:Float64 -- m:Float64 ) a b + 2.0 / ;
+:: lerp ( a:Float64 b:Float64 t:Float64 -- x:Float64 ) b a - t * a + ;
+
+\ Distances
+:: dist ( x1:Fractional y1:Fractional x2:Fractional y2:Fractional -- d:Fractional )
+    x2 x1 - y2 y1 - hypot ;
+
+\ Logarithms
+:: logn ( x:Fractional base:Fractional -- y:Fractional ) x ln base ln / ;
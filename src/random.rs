@@ -0,0 +1,106 @@
+//! Pseudo-random numbers
+//!
+//! This module provides the ``Generator`` that the
+//! interpreter draws random numbers from. The same seed
+//! always gives the same numbers, on every platform, so
+//! that scripts that use them can be reproduced.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A seedable pseudo-random number generator
+///
+/// This is xoshiro256**, which is fast and passes the
+/// usual statistical tests, though it is not suitable
+/// for cryptography.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    state: [u64; 4],
+}
+
+impl Generator {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Generator {
+        // The state is filled with SplitMix64, so that
+        // similar seeds give unrelated states, and the
+        // state is never all zeroes.
+        let mut x = seed;
+        let mut split_mix = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+
+        Generator {
+            state: [split_mix(), split_mix(), split_mix(), split_mix()],
+        }
+    }
+
+    /// Create a generator with a seed that differs each
+    /// time the program runs.
+    pub fn from_entropy() -> Generator {
+        Generator::new(RandomState::new().build_hasher().finish())
+    }
+
+    /// Return the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /// Return a number from 0 up to, but not including,
+    /// 1, with every multiple of 2^-53 equally likely.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Return a number from 0 up to, but not including,
+    /// ``n``, which must not be zero, with each equally
+    /// likely.
+    pub fn below(&mut self, n: u64) -> u64 {
+        // Draws from the incomplete last block of ``n``
+        // are rejected, so that there is no bias.
+        let limit = u64::MAX - u64::MAX % n;
+
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return x % n;
+            }
+        }
+    }
+
+    /// Return a number from the standard normal
+    /// distribution, by the Box-Muller transform.
+    pub fn normal(&mut self) -> f64 {
+        // The first uniform is in (0, 1], so that its
+        // logarithm is finite.
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+
+    /// Put the items in a random order, with every order
+    /// equally likely.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
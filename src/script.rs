@@ -0,0 +1,98 @@
+//! Evaluation of whole programs
+//!
+//! This module runs source text that spans many lines,
+//! such as a script file, through an ``Interpreter``.
+
+use super::{
+    interpreter::{EvalError, Interpreter},
+    parser, words,
+};
+use std::fmt;
+
+/// An error in a program, along with where it happened
+#[derive(Debug)]
+pub struct ScriptError {
+    /// The line that the failing code begins on,
+    /// counting from 1
+    pub line: usize,
+
+    pub error: EvalError,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+/// Evaluate source text, stopping at the first error.
+///
+/// Lines are evaluated one at a time, except that a
+/// quotation, definition, or comment that is left open
+/// at the end of a line continues onto the next lines,
+/// and is evaluated once it is closed.
+pub fn eval_source(interp: &mut Interpreter, source: &str) -> Result<(), ScriptError> {
+    let mut pending = PendingLines::default();
+    let mut start_line = 1;
+
+    for (idx, line) in source.lines().enumerate() {
+        if pending.is_empty() {
+            start_line = idx + 1;
+        }
+
+        if let Some(lines) = pending.push(line) {
+            eval_lines(interp, &lines, start_line)?;
+        }
+    }
+
+    // Anything still open is reported as unclosed.
+    eval_lines(interp, &pending.take(), start_line)
+}
+
+fn eval_lines(interp: &mut Interpreter, lines: &[String], line: usize) -> Result<(), ScriptError> {
+    interp
+        .eval_tokens(lines.iter().flat_map(|line| parser::parse_line(line)))
+        .map_err(|error| ScriptError { line, error })
+}
+
+/// Lines that are held back while they leave a
+/// quotation, definition, or comment open
+///
+/// Both scripts and the REPL read a line at a time,
+/// and use this to find where each piece of code
+/// that may be evaluated ends.
+#[derive(Debug, Default)]
+pub struct PendingLines {
+    lines: Vec<String>,
+}
+
+impl PendingLines {
+    /// Add a line. Once nothing is left open, the lines
+    /// held so far are returned to be evaluated.
+    pub fn push(&mut self, line: &str) -> Option<Vec<String>> {
+        self.lines.push(line.to_string());
+
+        let tokens = self
+            .lines
+            .iter()
+            .flat_map(|line| parser::parse_line(line))
+            .collect::<Vec<_>>();
+
+        if words::is_incomplete(&tokens) {
+            None
+        } else {
+            Some(self.take())
+        }
+    }
+
+    /// Returns ``true`` if no lines are held back.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Return the lines held so far, even though they
+    /// leave something open, and hold nothing.
+    pub fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.lines)
+    }
+}
@@ -0,0 +1,213 @@
+//! Saving and loading sessions
+//!
+//! A session is saved as a program which, when run,
+//! defines the same words, stores the same variables,
+//! sets the same angle mode and numerical limits, and
+//! pushes the same values to the stack. This keeps
+//! saved sessions human-readable, and means they can be
+//! loaded like any other script.
+//!
+//! The one thing a script cannot do is leave out the
+//! prelude, so a session made without it says so in
+//! its header, which ``from_source`` reads.
+
+use super::{
+    interpreter::{Interpreter, PRELUDE_VOCABULARY},
+    script::{self, ScriptError},
+    value::{Value, INTEGER_SUFFIXES},
+};
+use std::{fmt, fs, io, path::Path};
+
+/// The first line of every saved session
+const HEADER: &str = "\\ cattywampus session";
+
+/// The line added to the header of a session made
+/// without the prelude
+const WITHOUT_PRELUDE: &str = "\\ without prelude";
+
+/// Represents the ways that saving or loading a
+/// session can fail.
+#[derive(Debug)]
+pub enum SessionError {
+    /// Arises when the file cannot be read or written.
+    Io(io::Error),
+
+    /// Arises when a value cannot be written as source,
+    /// such as a quotation that captured locals. Holds
+    /// the value.
+    Unsaveable(Value),
+
+    /// Arises when a saved session fails to run.
+    Script(ScriptError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(err) => write!(f, "{}", err),
+            SessionError::Unsaveable(val) => write!(f, "Cannot save value: {}", val),
+            SessionError::Script(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Save the state of the ``Interpreter`` to a file.
+pub fn save<P: AsRef<Path>>(interp: &Interpreter, path: P) -> Result<(), SessionError> {
+    let source = to_source(interp)?;
+    fs::write(path, source).map_err(SessionError::Io)
+}
+
+/// Load a session from a file into a new ``Interpreter``.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Interpreter, SessionError> {
+    let source = fs::read_to_string(path).map_err(SessionError::Io)?;
+    from_source(&source)
+}
+
+/// Write the state of the ``Interpreter`` as source
+/// code that recreates it.
+///
+/// The words of the prelude are left out, since every
+/// new ``Interpreter`` defines them.
+pub fn to_source(interp: &Interpreter) -> Result<String, SessionError> {
+    let mut source = format!("{}\n", HEADER);
+    if !interp.has_prelude() {
+        source += &format!("{}\n", WITHOUT_PRELUDE);
+    }
+
+    // Every vocabulary must exist before any can use
+    // another, and each use must come before the words
+    // that rely on it.
+    source += "\n\\ Vocabularies\n";
+    for (vocab, _) in interp.vocabularies() {
+        if vocab != PRELUDE_VOCABULARY {
+            source += &format!("vocab {}\n", vocab);
+        }
+    }
+
+    for (vocab, using) in interp.vocabularies() {
+        for used in using {
+            source += &format!("vocab {} use {}\n", vocab, used);
+        }
+    }
+
+    source += "\n\\ Words\n";
+    let mut current = None;
+    let definitions = interp.definitions().into_iter();
+    for (vocab, def) in definitions.filter(|(vocab, _)| *vocab != PRELUDE_VOCABULARY) {
+        if current != Some(vocab) {
+            source += &format!("vocab {}\n", vocab);
+            current = Some(vocab);
+        }
+
+        source += &format!("{}\n", def);
+    }
+
+    source += &format!("vocab {}\n", interp.current_vocabulary());
+
+    source += "\n\\ Angle mode\n";
+    source += &format!("{}\n", interp.angle_mode());
+
+    let limits = interp.limits();
+    source += "\n\\ Numerical limits\n";
+    source += &format!(
+        "{} tolerance\n",
+        literal(&Value::Float64(limits.tolerance), false)?
+    );
+    source += &format!("{} iterations\n", limits.iterations);
+
+    source += "\n\\ Variables\n";
+    for (name, val) in interp.variables() {
+        source += &format!("{} {}!\n", literal(val, false)?, name);
+    }
+
+    source += "\n\\ Stack\n";
+    for val in interp.stack() {
+        source += &format!("{}\n", literal(val, false)?);
+    }
+
+    Ok(source)
+}
+
+/// Run saved source code in a new ``Interpreter``,
+/// which has the prelude unless the header says
+/// otherwise.
+pub fn from_source(source: &str) -> Result<Interpreter, SessionError> {
+    let mut header = source.lines().take_while(|line| line.starts_with('\\'));
+
+    let mut interp = if header.any(|line| line == WITHOUT_PRELUDE) {
+        Interpreter::without_prelude()
+    } else {
+        Interpreter::new()
+    };
+    script::eval_source(&mut interp, source).map_err(SessionError::Script)?;
+
+    // The words were defined, and any warnings given,
+    // when the session was first made.
+    interp.take_new_words();
+    interp.take_warnings();
+
+    Ok(interp)
+}
+
+// Write a value as source code which pushes exactly
+// that value. Quantities and quotations take several
+// tokens, so they cannot be ``nested`` inside lists
+// or maps.
+fn literal(val: &Value, nested: bool) -> Result<String, SessionError> {
+    let source = match val {
+        Value::Int32(x) => x.to_string(),
+        _ if val.type_of().is_integer() => {
+            let typ = val.type_of();
+            let (suffix, _) = INTEGER_SUFFIXES.iter().find(|(_, t)| *t == typ).unwrap();
+            format!("{}{}", val, suffix)
+        }
+        Value::Float32(x) => format!("{}f", float_literal(x.to_string())),
+        Value::Float64(x) => float_literal(x.to_string()),
+        Value::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| literal(item, true))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            format!("{{{}}}", items.join(","))
+        }
+        Value::Map(map) => {
+            let entries = map
+                .iter()
+                .map(|(key, val)| Ok(format!("{}:{}", key, literal(val, true)?)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            format!("#{{{}}}", entries.join(","))
+        }
+        // Units are attached to their magnitude, since some,
+        // such as ``min``, are builtins on their own. Only
+        // finite numbers can be written that way.
+        Value::Quantity(q) if !nested && q.magnitude.is_finite() => {
+            format!("{}{}", float_literal(q.magnitude.to_string()), q.unit)
+        }
+        Value::Quantity(q) if !nested => {
+            format!("{} 1{} *", float_literal(q.magnitude.to_string()), q.unit)
+        }
+        Value::Quotation(q) if !nested && q.captured.is_empty() => q.to_string(),
+        Value::Quantity(_) | Value::Quotation(_) => {
+            return Err(SessionError::Unsaveable(val.clone()));
+        }
+        _ => val.to_string(),
+    };
+
+    Ok(source)
+}
+
+// Make a displayed float into a float literal, which
+// must have a decimal point so that it is not read as
+// an integer. The displayed form is the shortest that
+// reads back as the same number.
+fn float_literal(repr: String) -> String {
+    if repr == "NaN" {
+        "nan".to_string()
+    } else if repr.contains('.') || repr.ends_with("inf") {
+        repr
+    } else {
+        repr + ".0"
+    }
+}
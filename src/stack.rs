@@ -44,6 +44,14 @@ impl Stack {
         &self.elements[(len - n - 1)..]
     }
 
+    /// Return the top ``n`` elements, oldest
+    /// first. Panics if the provided ``n``
+    /// runs off the stack.
+    pub fn top_n(&self, n: usize) -> &[Value] {
+        let len = self.elements.len();
+        &self.elements[(len - n)..]
+    }
+
     /// Remove and drop the last ``n``
     /// elements. Panics if the provided ``n``
     /// runs off the stack.
@@ -53,6 +61,14 @@ impl Stack {
         self.elements.truncate(len - n);
     }
 
+    /// Remove the last ``n`` elements and return
+    /// them, oldest first. Panics if the provided
+    /// ``n`` runs off the stack.
+    pub fn take_n(&mut self, n: usize) -> Vec<Value> {
+        let len = self.elements.len();
+        self.elements.split_off(len - n)
+    }
+
     /// Reset the stack to completely empty,
     /// dropping all ``Value``s in the stack.
     pub fn clear(&mut self) {
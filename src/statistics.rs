@@ -0,0 +1,212 @@
+//! Statistics
+//!
+//! This module provides the routines behind the
+//! statistics builtins. They work on ``f64`` samples,
+//! and are chosen to keep their accuracy on long or
+//! badly scaled data: sums are compensated, and the
+//! moments are found in a single pass that never
+//! subtracts two large sums of powers.
+//!
+//! It also fits curves to points by least squares.
+
+use super::{matrix::Dense, typecheck::TypeError};
+
+/// The sum of the samples, with the rounding error of
+/// each addition carried along so that it is not lost
+///
+/// This is Neumaier's variant of Kahan summation,
+/// which stays accurate when a sample is larger than
+/// the running sum.
+pub fn sum(xs: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+
+    for &x in xs {
+        let t = sum + x;
+        if f64::abs(sum) >= f64::abs(x) {
+            compensation += (sum - t) + x;
+        } else {
+            compensation += (x - t) + sum;
+        }
+        sum = t;
+    }
+
+    sum + compensation
+}
+
+/// The central moments of some samples
+///
+/// ``m2``, ``m3``, and ``m4`` are the sums of the
+/// second, third, and fourth powers of the deviations
+/// from the mean.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Moments {
+    pub count: f64,
+    pub mean: f64,
+    pub m2: f64,
+    pub m3: f64,
+    pub m4: f64,
+}
+
+impl Moments {
+    /// Find the moments of the samples in one pass.
+    ///
+    /// This updates the moments with each sample, as in
+    /// Welford's algorithm for the variance, extended to
+    /// the higher moments by Terriberry.
+    pub fn of(xs: &[f64]) -> Moments {
+        let mut m = Moments::default();
+
+        for &x in xs {
+            let n1 = m.count;
+            m.count += 1.0;
+            let n = m.count;
+
+            let delta = x - m.mean;
+            let delta_n = delta / n;
+            let delta_n2 = delta_n * delta_n;
+            let term = delta * delta_n * n1;
+
+            m.mean += delta_n;
+            m.m4 += term * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * m.m2
+                - 4.0 * delta_n * m.m3;
+            m.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * m.m2;
+            m.m2 += term;
+        }
+
+        m
+    }
+
+    /// The variance of a sample, which divides by one
+    /// less than the count, or of a whole population,
+    /// which divides by the count.
+    pub fn variance(&self, sample: bool) -> f64 {
+        if sample {
+            self.m2 / (self.count - 1.0)
+        } else {
+            self.m2 / self.count
+        }
+    }
+
+    /// The skewness, as the third standardized moment of
+    /// the samples
+    pub fn skewness(&self) -> f64 {
+        self.count.sqrt() * self.m3 / self.m2.powf(1.5)
+    }
+
+    /// The excess kurtosis, as the fourth standardized
+    /// moment of the samples less three, so that the
+    /// normal distribution has none
+    pub fn kurtosis(&self) -> f64 {
+        self.count * self.m4 / (self.m2 * self.m2) - 3.0
+    }
+}
+
+/// The quantile ``p``, from 0 to 1, of the samples,
+/// which must be sorted and not empty
+///
+/// This interpolates linearly between the samples on
+/// either side, as spreadsheets do, so that the median
+/// of an even count is the mean of the middle two.
+pub fn quantile(sorted: &[f64], p: f64) -> f64 {
+    let h = (sorted.len() - 1) as f64 * p;
+    let lo = h.floor() as usize;
+
+    match sorted.get(lo + 1) {
+        Some(&hi) => sorted[lo] + (h - lo as f64) * (hi - sorted[lo]),
+        None => sorted[lo],
+    }
+}
+
+/// The most common of the samples, which must be
+/// sorted and not empty, choosing the smallest if
+/// several are equally common
+pub fn mode(sorted: &[f64]) -> f64 {
+    let mut best = (sorted[0], 0);
+    let mut start = 0;
+
+    for idx in 1..=sorted.len() {
+        if idx == sorted.len() || sorted[idx] != sorted[start] {
+            if idx - start > best.1 {
+                best = (sorted[start], idx - start);
+            }
+            start = idx;
+        }
+    }
+
+    best.0
+}
+
+/// A straight line fitted to some points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+
+    /// The coefficient of determination, which is 1
+    /// when the line passes through every point
+    pub r_squared: f64,
+}
+
+/// Fit a straight line to the points ``(xs[i], ys[i])``
+/// by least squares.
+///
+/// The sums are taken about the means, so that points
+/// far from the origin keep their accuracy. Returns
+/// ``None`` if there are fewer than two points, or if
+/// every ``x`` is the same.
+pub fn linear_fit(xs: &[f64], ys: &[f64]) -> Option<LinearFit> {
+    if xs.len() < 2 {
+        return None;
+    }
+
+    let n = xs.len() as f64;
+    let (x_mean, y_mean) = (sum(xs) / n, sum(ys) / n);
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        let (dx, dy) = (x - x_mean, y - y_mean);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+
+    Some(LinearFit {
+        slope,
+        intercept: y_mean - slope * x_mean,
+        r_squared,
+    })
+}
+
+/// Fit a polynomial of the given degree to the points
+/// ``(xs[i], ys[i])`` by least squares, returning its
+/// coefficients from the constant term up.
+///
+/// There must be more points than the degree, with
+/// enough different ``x``s to fix the polynomial.
+pub fn polynomial_fit(xs: &[f64], ys: &[f64], degree: usize) -> Result<Vec<f64>, TypeError> {
+    let terms = degree + 1;
+    if xs.len() < terms {
+        return Err(TypeError::DomainError);
+    }
+
+    let powers = xs
+        .iter()
+        .flat_map(|&x| (0..terms).map(move |k| x.powi(k as i32)));
+    let vandermonde = Dense::new(xs.len(), terms, powers.collect()).unwrap();
+    let rhs = Dense::new(ys.len(), 1, ys.to_vec()).unwrap();
+
+    let coefficients = vandermonde.least_squares(&rhs)?;
+    Ok((0..terms).map(|k| coefficients[(k, 0)]).collect())
+}
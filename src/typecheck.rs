@@ -5,7 +5,10 @@
 
 use super::{
     functions::{Function, FunctionResult},
+    random::Generator,
     stack::Stack,
+    units::{Quantity, Unit},
+    value::{Type, Value},
 };
 
 /// Represents various kinds of type errors.
@@ -22,20 +25,105 @@ pub enum TypeError {
     /// Arises when the function returns a ``Value``
     /// of an incorrect type.
     BrokenCallee,
+
+    /// Arises when compound ``Values``, such as
+    /// matrices, have incompatible dimensions.
+    ShapeMismatch,
+
+    /// Arises when the arguments have the right types,
+    /// but the function is undefined for them, such as
+    /// when inverting a singular matrix.
+    DomainError,
+
+    /// Arises when looking up a key that is not
+    /// present in a ``Map``.
+    MissingKey,
+
+    /// Arises when combining or converting quantities
+    /// whose units measure different things, such as
+    /// adding metres to seconds.
+    DimensionMismatch,
+
+    /// Arises when the result of integer arithmetic
+    /// does not fit in its type.
+    Overflow,
 }
 
 /// Apply the function to the stack, if possible.
 ///
 /// Returns ``false`` on failure.
-pub fn checked_apply<'a>(fun: &Function<'a>, stack: &mut Stack) -> Result<(), TypeError> {
-    // For development purposes, functions can only return
-    // one scalar, and must do so.
-    // This will change in the future.
-    assert!(fun.signiture.1.len() == 1);
+pub fn checked_apply<'a>(
+    fun: &Function<'a>,
+    stack: &mut Stack,
+    generator: &mut Generator,
+) -> Result<(), TypeError> {
+    // First, check the arity and argument types.
+    check_args(fun.signiture.0, stack)?;
+
+    // Finally, if we're here, the argument types match and we can apply
+    // the function.
+    let arity = fun.signiture.0.len();
+
+    // Borrow the arguments.
+    let args_slice = stack.top_n(arity);
+    //println!("{:?}", args_slice);
+
+    // Next, run the function.
+    let fun_result = fun.implementation.call(args_slice, generator);
+
+    // Make sure the function returned what it said it would.
+    let vals = match fun_result {
+        FunctionResult::Scalar(val) => vec![val],
+        FunctionResult::List(vals) => vals,
+        FunctionResult::Failure(err) => return Err(err),
+    };
+
+    check_results(fun.signiture.1, &vals)?;
+
+    // Now, remove the arguments from the stack.
+    stack.chop_n(arity);
+
+    // ...and push the results to the stack.
+    stack.extend(vals);
+
+    return Ok(());
+}
+
+/// Apply the function to the stack without checking
+/// its arguments or results.
+///
+/// This is only for code whose argument types have
+/// already been proven by inference, so that the checks
+/// in ``checked_apply`` could never fail. The function
+/// can still fail for other reasons, such as a domain
+/// error.
+pub fn unchecked_apply(
+    fun: &Function<'_>,
+    stack: &mut Stack,
+    generator: &mut Generator,
+) -> Result<(), TypeError> {
+    debug_assert!(check_args(fun.signiture.0, stack).is_ok());
+
+    let arity = fun.signiture.0.len();
+
+    let vals = match fun.implementation.call(stack.top_n(arity), generator) {
+        FunctionResult::Scalar(val) => vec![val],
+        FunctionResult::List(vals) => vals,
+        FunctionResult::Failure(err) => return Err(err),
+    };
+
+    stack.chop_n(arity);
+    stack.extend(vals);
+
+    Ok(())
+}
 
+/// Check that the top of the stack holds arguments
+/// of the given types, with the last type on top.
+pub fn check_args(arg_types: &[Type], stack: &Stack) -> Result<(), TypeError> {
     // First, check the arity of the function.
     // It needs to be less than or equal to the stack height.
-    let arity = fun.signiture.0.len();
+    let arity = arg_types.len();
     let arity_ok = arity <= stack.height();
 
     if !arity_ok {
@@ -44,7 +132,7 @@ pub fn checked_apply<'a>(fun: &Function<'a>, stack: &mut Stack) -> Result<(), Ty
 
     // Now, check the types of the function arguments
     // against the values on the stack.
-    for (idx, arg_type) in fun.signiture.0.iter().enumerate() {
+    for (idx, arg_type) in arg_types.iter().enumerate() {
         //println!("{} {:?} {:?}", idx, stack.peek_n(arity - idx - 1), arg_type);
 
         if !stack.peek_n(arity - idx - 1).matches(arg_type) {
@@ -52,32 +140,78 @@ pub fn checked_apply<'a>(fun: &Function<'a>, stack: &mut Stack) -> Result<(), Ty
         }
     }
 
-    // Finally, if we're here, the argument types match and we can apply
-    // the function.
-
-    // Borrow the arguments.
-    let args_slice = stack.slice_n(arity - 1);
-    //println!("{:?}", args_slice);
+    Ok(())
+}
 
-    // Next, run the function.
-    let fun_result = (fun.implementation)(args_slice);
+/// Check that a function produced exactly the
+/// results that it declared.
+pub fn check_results(ret_types: &[Type], vals: &[Value]) -> Result<(), TypeError> {
+    let results_ok = vals.len() == ret_types.len()
+        && vals
+            .iter()
+            .zip(ret_types.iter())
+            .all(|(val, ret_type)| val.matches(ret_type));
+
+    if results_ok {
+        Ok(())
+    } else {
+        Err(TypeError::BrokenCallee)
+    }
+}
 
-    // Make sure the function returned what it said it would.
-    let val = match fun_result {
-        FunctionResult::Scalar(val) => {
-            if !val.matches(&fun.signiture.1[0]) {
-                return Err(TypeError::BrokenCallee);
-            }
+/// Attach a unit to the number on top of the stack.
+///
+/// A plain number becomes a ``Quantity`` of the unit,
+/// while an existing ``Quantity`` is multiplied by it,
+/// so ``3 kg m`` is the same as ``3 kg*m``.
+pub fn apply_unit(unit: &Unit, stack: &mut Stack) -> Result<(), TypeError> {
+    let top = stack.pop().ok_or(TypeError::WrongArity)?;
+
+    let result = match (&top, top.to_f64()) {
+        (_, Some(magnitude)) => Ok(Quantity::new(magnitude, unit.clone()).into_value()),
+        (Value::Quantity(q), None) => q
+            .mul(&Quantity::new(1.0, unit.clone()))
+            .map(Quantity::into_value),
+        _ => Err(TypeError::TypeMismatch),
+    };
 
-            val
+    match result {
+        Ok(val) => {
+            stack.push(val);
+            Ok(())
         }
-    };
+        Err(err) => {
+            stack.push(top);
+            Err(err)
+        }
+    }
+}
 
-    // Now, remove the arguments from the stack.
-    stack.chop_n(arity);
+/// Convert the ``Quantity`` on top of the stack to
+/// another unit of the same dimension.
+pub fn convert_unit(unit: &Unit, stack: &mut Stack) -> Result<(), TypeError> {
+    if stack.height() == 0 {
+        return Err(TypeError::WrongArity);
+    }
 
-    // ...and push the result to the stack.
-    stack.push(val);
+    let top = stack.peek_n(0);
 
-    return Ok(());
+    if !top.matches(&Type::Number) {
+        return Err(TypeError::TypeMismatch);
+    }
+
+    // Plain numbers are dimensionless, so they never match.
+    if !top.matches(&Type::Dimension(unit.dimension())) {
+        return Err(TypeError::DimensionMismatch);
+    }
+
+    let converted = match top {
+        Value::Quantity(q) => q.convert(unit)?,
+        _ => unreachable!(),
+    };
+
+    stack.chop_n(1);
+    stack.push(Value::Quantity(converted));
+
+    Ok(())
 }
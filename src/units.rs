@@ -0,0 +1,534 @@
+//! Physical units and dimensional analysis
+//!
+//! This module provides ``Quantity``, a number tagged
+//! with a unit of measure, along with the database of
+//! known units and the parser for unit expressions
+//! such as ``m/s^2`` or ``kg*m^2``.
+
+use super::{typecheck::TypeError, value::Value};
+use lazy_static::lazy_static;
+use std::{collections::HashMap, convert::TryFrom, fmt};
+
+/// The number of SI base dimensions
+const BASE_COUNT: usize = 7;
+
+/// The symbols of the SI base dimensions, in the
+/// order that ``Dimension`` stores their exponents.
+const BASE_SYMBOLS: [&str; BASE_COUNT] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// The physical dimension of a quantity
+///
+/// This stores the exponent of each SI base dimension:
+/// length, mass, time, electric current, temperature,
+/// amount of substance, and luminous intensity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Dimension(pub [i8; BASE_COUNT]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0, 0, 0, 0, 0, 0, 0]);
+    pub const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0, 0, 0]);
+    pub const MASS: Dimension = Dimension([0, 1, 0, 0, 0, 0, 0]);
+    pub const TIME: Dimension = Dimension([0, 0, 1, 0, 0, 0, 0]);
+    pub const CURRENT: Dimension = Dimension([0, 0, 0, 1, 0, 0, 0]);
+    pub const TEMPERATURE: Dimension = Dimension([0, 0, 0, 0, 1, 0, 0]);
+    pub const AMOUNT: Dimension = Dimension([0, 0, 0, 0, 0, 1, 0]);
+    pub const LUMINOSITY: Dimension = Dimension([0, 0, 0, 0, 0, 0, 1]);
+    pub const AREA: Dimension = Dimension([2, 0, 0, 0, 0, 0, 0]);
+    pub const VOLUME: Dimension = Dimension([3, 0, 0, 0, 0, 0, 0]);
+    pub const FREQUENCY: Dimension = Dimension([0, 0, -1, 0, 0, 0, 0]);
+    pub const VELOCITY: Dimension = Dimension([1, 0, -1, 0, 0, 0, 0]);
+    pub const ACCELERATION: Dimension = Dimension([1, 0, -2, 0, 0, 0, 0]);
+    pub const FORCE: Dimension = Dimension([1, 1, -2, 0, 0, 0, 0]);
+    pub const PRESSURE: Dimension = Dimension([-1, 1, -2, 0, 0, 0, 0]);
+    pub const ENERGY: Dimension = Dimension([2, 1, -2, 0, 0, 0, 0]);
+    pub const POWER: Dimension = Dimension([2, 1, -3, 0, 0, 0, 0]);
+    pub const CHARGE: Dimension = Dimension([0, 0, 1, 1, 0, 0, 0]);
+    pub const VOLTAGE: Dimension = Dimension([2, 1, -3, -1, 0, 0, 0]);
+    pub const RESISTANCE: Dimension = Dimension([2, 1, -3, -2, 0, 0, 0]);
+    pub const CAPACITANCE: Dimension = Dimension([-2, -1, 4, 2, 0, 0, 0]);
+
+    /// Returns ``true`` if every exponent is zero.
+    pub fn is_none(&self) -> bool {
+        *self == Dimension::NONE
+    }
+
+    /// Return the dimension of a product of quantities,
+    /// or ``Overflow`` if an exponent is too large to
+    /// store.
+    pub fn mul(&self, rhs: &Dimension) -> Result<Dimension, TypeError> {
+        self.combine(rhs, i8::checked_add)
+    }
+
+    /// Return the dimension of a quotient of quantities,
+    /// or ``Overflow`` if an exponent is too large to
+    /// store.
+    pub fn div(&self, rhs: &Dimension) -> Result<Dimension, TypeError> {
+        self.combine(rhs, i8::checked_sub)
+    }
+
+    /// Return the dimension raised to an integer power,
+    /// or ``Overflow`` if an exponent is too large to
+    /// store.
+    pub fn pow(&self, exponent: i32) -> Result<Dimension, TypeError> {
+        self.combine(&Dimension::NONE, |a, _| {
+            i8::try_from(i32::from(a).checked_mul(exponent)?).ok()
+        })
+    }
+
+    /// Returns the conventional name of the dimension,
+    /// such as ``Velocity``, if it has one.
+    pub fn name(&self) -> Option<&'static str> {
+        DIMENSION_NAMES
+            .iter()
+            .find(|(dim, _)| dim == self)
+            .map(|(_, name)| *name)
+    }
+
+    /// Look up a dimension by its conventional name.
+    pub fn from_name(name: &str) -> Option<Dimension> {
+        DIMENSION_NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(dim, _)| *dim)
+    }
+
+    fn combine<F>(&self, rhs: &Dimension, op: F) -> Result<Dimension, TypeError>
+    where
+        F: Fn(i8, i8) -> Option<i8>,
+    {
+        let mut result = [0; BASE_COUNT];
+        for (idx, exp) in result.iter_mut().enumerate() {
+            *exp = op(self.0[idx], rhs.0[idx]).ok_or(TypeError::Overflow)?;
+        }
+        Ok(Dimension(result))
+    }
+}
+
+/// Displays the dimension in terms of SI base units,
+/// for example ``m*kg/s^2``.
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let factors = BASE_SYMBOLS
+            .iter()
+            .zip(self.0.iter())
+            .map(|(sym, &exp)| (sym.to_string(), i32::from(exp)))
+            .collect::<Vec<_>>();
+
+        write_factors(f, &factors)
+    }
+}
+
+#[rustfmt::skip]
+const DIMENSION_NAMES: [(Dimension, &str); 21] = [
+    (Dimension::LENGTH,       "Length"),
+    (Dimension::MASS,         "Mass"),
+    (Dimension::TIME,         "Time"),
+    (Dimension::CURRENT,      "Current"),
+    (Dimension::TEMPERATURE,  "Temperature"),
+    (Dimension::AMOUNT,       "Amount"),
+    (Dimension::LUMINOSITY,   "Luminosity"),
+    (Dimension::AREA,         "Area"),
+    (Dimension::VOLUME,       "Volume"),
+    (Dimension::FREQUENCY,    "Frequency"),
+    (Dimension::VELOCITY,     "Velocity"),
+    (Dimension::ACCELERATION, "Acceleration"),
+    (Dimension::FORCE,        "Force"),
+    (Dimension::PRESSURE,     "Pressure"),
+    (Dimension::ENERGY,       "Energy"),
+    (Dimension::POWER,        "Power"),
+    (Dimension::CHARGE,       "Charge"),
+    (Dimension::VOLTAGE,      "Voltage"),
+    (Dimension::RESISTANCE,   "Resistance"),
+    (Dimension::CAPACITANCE,  "Capacitance"),
+    (Dimension::NONE,         "Dimensionless"),
+];
+
+/// A single entry in the unit database
+struct UnitDef {
+    /// The size of one of this unit in SI base units
+    factor: f64,
+
+    dimension: Dimension,
+
+    /// Whether SI prefixes such as ``k`` may be applied
+    prefixable: bool,
+}
+
+impl UnitDef {
+    const fn new(factor: f64, dimension: Dimension, prefixable: bool) -> UnitDef {
+        UnitDef {
+            factor,
+            dimension,
+            prefixable,
+        }
+    }
+}
+
+#[rustfmt::skip]
+lazy_static! {
+    /// The units that can be named in unit expressions.
+    ///
+    /// Units that need an offset to convert, such as
+    /// degrees Celsius, are not supported.
+    static ref UNITS: HashMap<&'static str, UnitDef> = {
+        let mut units = HashMap::new();
+
+        // SI base units (the kilogram is prefixed from the gram)
+        units.insert("m",   UnitDef::new(1.0,   Dimension::LENGTH,      true));
+        units.insert("g",   UnitDef::new(1e-3,  Dimension::MASS,        true));
+        units.insert("s",   UnitDef::new(1.0,   Dimension::TIME,        true));
+        units.insert("A",   UnitDef::new(1.0,   Dimension::CURRENT,     true));
+        units.insert("K",   UnitDef::new(1.0,   Dimension::TEMPERATURE, true));
+        units.insert("mol", UnitDef::new(1.0,   Dimension::AMOUNT,      true));
+        units.insert("cd",  UnitDef::new(1.0,   Dimension::LUMINOSITY,  true));
+
+        // SI derived units
+        units.insert("Hz",  UnitDef::new(1.0,   Dimension::FREQUENCY,   true));
+        units.insert("N",   UnitDef::new(1.0,   Dimension::FORCE,       true));
+        units.insert("Pa",  UnitDef::new(1.0,   Dimension::PRESSURE,    true));
+        units.insert("J",   UnitDef::new(1.0,   Dimension::ENERGY,      true));
+        units.insert("W",   UnitDef::new(1.0,   Dimension::POWER,       true));
+        units.insert("C",   UnitDef::new(1.0,   Dimension::CHARGE,      true));
+        units.insert("V",   UnitDef::new(1.0,   Dimension::VOLTAGE,     true));
+        units.insert("Ohm", UnitDef::new(1.0,   Dimension::RESISTANCE,  true));
+        units.insert("F",   UnitDef::new(1.0,   Dimension::CAPACITANCE, true));
+
+        // Accepted for use with the SI
+        //
+        // The word ``min`` on its own is the builtin, so
+        // minutes are attached to their number, as in
+        // ``90min``, or named in conversions, such as
+        // ``>min``, and in compound units, such as ``km/min``.
+        units.insert("L",   UnitDef::new(1e-3,    Dimension::VOLUME,   true));
+        units.insert("min", UnitDef::new(60.0,    Dimension::TIME,     false));
+        units.insert("h",   UnitDef::new(3600.0,  Dimension::TIME,     false));
+        units.insert("d",   UnitDef::new(86400.0, Dimension::TIME,     false));
+        units.insert("t",   UnitDef::new(1e3,     Dimension::MASS,     false));
+        units.insert("bar", UnitDef::new(1e5,     Dimension::PRESSURE, true));
+        units.insert("eV",  UnitDef::new(1.602176634e-19, Dimension::ENERGY, true));
+
+        // Imperial and US customary
+        units.insert("in",   UnitDef::new(0.0254,           Dimension::LENGTH,   false));
+        units.insert("ft",   UnitDef::new(0.3048,           Dimension::LENGTH,   false));
+        units.insert("yd",   UnitDef::new(0.9144,           Dimension::LENGTH,   false));
+        units.insert("mi",   UnitDef::new(1609.344,         Dimension::LENGTH,   false));
+        units.insert("nmi",  UnitDef::new(1852.0,           Dimension::LENGTH,   false));
+        units.insert("acre", UnitDef::new(4046.8564224,     Dimension::AREA,     false));
+        units.insert("gal",  UnitDef::new(3.785411784e-3,   Dimension::VOLUME,   false));
+        units.insert("oz",   UnitDef::new(0.028349523125,   Dimension::MASS,     false));
+        units.insert("lb",   UnitDef::new(0.45359237,       Dimension::MASS,     false));
+        units.insert("lbf",  UnitDef::new(4.4482216152605,  Dimension::FORCE,    false));
+        units.insert("psi",  UnitDef::new(6894.757293168,   Dimension::PRESSURE, false));
+        units.insert("mph",  UnitDef::new(0.44704,          Dimension::VELOCITY, false));
+        units.insert("kn",   UnitDef::new(1852.0 / 3600.0,  Dimension::VELOCITY, false));
+        units.insert("atm",  UnitDef::new(101325.0,         Dimension::PRESSURE, false));
+        units.insert("cal",  UnitDef::new(4.184,            Dimension::ENERGY,   true));
+        units.insert("BTU",  UnitDef::new(1055.05585262,    Dimension::ENERGY,   false));
+        units.insert("hp",   UnitDef::new(745.69987158227,  Dimension::POWER,    false));
+
+        units
+    };
+}
+
+/// The SI prefixes, longest first so that ``da``
+/// is tried before ``d``.
+#[rustfmt::skip]
+const PREFIXES: [(&str, f64); 21] = [
+    ("da", 1e1),
+    ("Y", 1e24), ("Z", 1e21), ("E", 1e18), ("P", 1e15), ("T", 1e12),
+    ("G", 1e9),  ("M", 1e6),  ("k", 1e3),  ("h", 1e2),  ("d", 1e-1),
+    ("c", 1e-2), ("m", 1e-3), ("u", 1e-6), ("µ", 1e-6), ("n", 1e-9),
+    ("p", 1e-12), ("f", 1e-15), ("a", 1e-18), ("z", 1e-21), ("y", 1e-24),
+];
+
+/// A unit of measure, such as ``km/h``
+///
+/// A unit is a product of named units raised to
+/// integer powers, together with the dimension and
+/// size of the whole product.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit {
+    /// The named units and their exponents, as written
+    factors: Vec<(String, i32)>,
+
+    /// The size of one of this unit in SI base units
+    factor: f64,
+
+    dimension: Dimension,
+}
+
+impl Unit {
+    /// Parse a unit expression.
+    ///
+    /// Expressions are unit names, optionally raised to
+    /// an integer power with ``^``, joined by ``*`` or
+    /// ``/``. Operators apply left to right, so
+    /// ``kg*m/s^2`` is a unit of force. Returns ``None``
+    /// if any name is unknown, or if an exponent of the
+    /// whole unit is too large to store.
+    pub fn parse(expr: &str) -> Option<Unit> {
+        let mut unit = Unit::one();
+        let mut rest = expr;
+        let mut divide = false;
+
+        loop {
+            let end = rest.find(&['*', '/'][..]).unwrap_or(rest.len());
+            let (term, tail) = rest.split_at(end);
+
+            let term = Unit::parse_term(term)?;
+            let combined = if divide {
+                unit.div(&term)
+            } else {
+                unit.mul(&term)
+            };
+            unit = combined.ok()?;
+
+            if tail.is_empty() {
+                return Some(unit);
+            }
+
+            divide = tail.starts_with('/');
+            rest = &tail[1..];
+        }
+    }
+
+    /// Return the dimension of the unit.
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    /// Return the size of one of this unit in SI
+    /// base units.
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    /// Return the product of two units.
+    ///
+    /// Each exponent of the dimension must fit in an
+    /// ``i8``, and the exponents of the named units are
+    /// never larger, so this fails with ``Overflow``
+    /// before they can overflow.
+    pub fn mul(&self, rhs: &Unit) -> Result<Unit, TypeError> {
+        let dimension = self.dimension.mul(&rhs.dimension)?;
+        let mut factors = self.factors.clone();
+
+        for (name, exp) in &rhs.factors {
+            match factors.iter_mut().find(|(n, _)| n == name) {
+                Some((_, e)) => *e = e.checked_add(*exp).ok_or(TypeError::Overflow)?,
+                None => factors.push((name.clone(), *exp)),
+            }
+        }
+
+        factors.retain(|(_, exp)| *exp != 0);
+
+        Ok(Unit {
+            factors,
+            factor: self.factor * rhs.factor,
+            dimension,
+        })
+    }
+
+    /// Return the quotient of two units.
+    pub fn div(&self, rhs: &Unit) -> Result<Unit, TypeError> {
+        self.mul(&rhs.pow(-1)?)
+    }
+
+    /// Return the unit raised to an integer power.
+    pub fn pow(&self, exponent: i32) -> Result<Unit, TypeError> {
+        let factors = self
+            .factors
+            .iter()
+            .map(|(name, exp)| Some((name.clone(), exp.checked_mul(exponent)?)))
+            .collect::<Option<_>>()
+            .ok_or(TypeError::Overflow)?;
+
+        Ok(Unit {
+            factors,
+            factor: self.factor.powi(exponent),
+            dimension: self.dimension.pow(exponent)?,
+        })
+    }
+
+    /// The unit of a plain number.
+    pub fn one() -> Unit {
+        Unit {
+            factors: Vec::new(),
+            factor: 1.0,
+            dimension: Dimension::NONE,
+        }
+    }
+
+    fn parse_term(term: &str) -> Option<Unit> {
+        // Exponents are limited to the range of the
+        // dimension's.
+        let (name, exponent) = match term.find('^') {
+            Some(idx) => (&term[..idx], term[(idx + 1)..].parse::<i8>().ok()?),
+            None => (term, 1),
+        };
+
+        let (factor, dimension) = lookup(name)?;
+        let unit = Unit {
+            factors: vec![(name.to_string(), 1)],
+            factor,
+            dimension,
+        };
+
+        unit.pow(i32::from(exponent)).ok()
+    }
+}
+
+/// Displays the unit as it would be written in a
+/// unit expression.
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_factors(f, &self.factors)
+    }
+}
+
+/// Look up a unit name, with an optional SI prefix,
+/// returning its size in SI base units and its
+/// dimension.
+fn lookup(name: &str) -> Option<(f64, Dimension)> {
+    if let Some(def) = UNITS.get(name) {
+        return Some((def.factor, def.dimension));
+    }
+
+    PREFIXES.iter().find_map(|(prefix, scale)| {
+        if !name.starts_with(prefix) {
+            return None;
+        }
+
+        match UNITS.get(&name[prefix.len()..]) {
+            Some(def) if def.prefixable => Some((scale * def.factor, def.dimension)),
+            _ => None,
+        }
+    })
+}
+
+/// Write a list of named factors, putting those with
+/// negative exponents after a ``/``.
+fn write_factors(f: &mut fmt::Formatter<'_>, factors: &[(String, i32)]) -> fmt::Result {
+    let numerator = factors.iter().filter(|(_, exp)| *exp > 0);
+    let denominator = factors.iter().filter(|(_, exp)| *exp < 0);
+
+    let mut first = true;
+    for (name, exp) in numerator {
+        if !first {
+            write!(f, "*")?;
+        }
+        write_factor(f, name, *exp)?;
+        first = false;
+    }
+
+    for (name, exp) in denominator {
+        if first {
+            // There is nothing to divide, so use a
+            // negative exponent instead.
+            write_factor(f, name, *exp)?;
+            first = false;
+        } else {
+            write!(f, "/")?;
+            write_factor(f, name, -exp)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_factor(f: &mut fmt::Formatter<'_>, name: &str, exp: i32) -> fmt::Result {
+    if exp == 1 {
+        write!(f, "{}", name)
+    } else {
+        write!(f, "{}^{}", name, exp)
+    }
+}
+
+/// A number with a unit of measure
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quantity {
+    /// The size of the quantity, in terms of ``unit``
+    pub magnitude: f64,
+
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// Create a quantity of ``magnitude`` of ``unit``.
+    pub fn new(magnitude: f64, unit: Unit) -> Quantity {
+        Quantity { magnitude, unit }
+    }
+
+    /// Return the dimension of the quantity.
+    pub fn dimension(&self) -> Dimension {
+        self.unit.dimension
+    }
+
+    /// Express the quantity in a different unit of
+    /// the same dimension.
+    pub fn convert(&self, unit: &Unit) -> Result<Quantity, TypeError> {
+        if self.unit.dimension != unit.dimension {
+            return Err(TypeError::DimensionMismatch);
+        }
+
+        let magnitude = self.magnitude * self.unit.factor / unit.factor;
+        Ok(Quantity::new(magnitude, unit.clone()))
+    }
+
+    /// Add two quantities of the same dimension. The
+    /// result is expressed in the unit of ``self``.
+    pub fn add(&self, rhs: &Quantity) -> Result<Quantity, TypeError> {
+        let rhs = rhs.convert(&self.unit)?;
+        Ok(Quantity::new(
+            self.magnitude + rhs.magnitude,
+            self.unit.clone(),
+        ))
+    }
+
+    /// Subtract two quantities of the same dimension.
+    /// The result is expressed in the unit of ``self``.
+    pub fn sub(&self, rhs: &Quantity) -> Result<Quantity, TypeError> {
+        let rhs = rhs.convert(&self.unit)?;
+        Ok(Quantity::new(
+            self.magnitude - rhs.magnitude,
+            self.unit.clone(),
+        ))
+    }
+
+    /// Multiply two quantities, combining their units.
+    pub fn mul(&self, rhs: &Quantity) -> Result<Quantity, TypeError> {
+        let unit = self.unit.mul(&rhs.unit)?;
+        Ok(Quantity::new(self.magnitude * rhs.magnitude, unit))
+    }
+
+    /// Divide two quantities, combining their units.
+    pub fn div(&self, rhs: &Quantity) -> Result<Quantity, TypeError> {
+        let unit = self.unit.div(&rhs.unit)?;
+        Ok(Quantity::new(self.magnitude / rhs.magnitude, unit))
+    }
+
+    /// Multiply the quantity by a plain number.
+    pub fn scale(&self, by: f64) -> Quantity {
+        Quantity::new(self.magnitude * by, self.unit.clone())
+    }
+
+    /// Convert the quantity into a ``Value``.
+    ///
+    /// Quantities whose units cancel out, such as
+    /// ``m/km``, become plain ``Float64`` values.
+    pub fn into_value(self) -> Value {
+        if self.unit.dimension.is_none() {
+            Value::Float64(self.magnitude * self.unit.factor)
+        } else {
+            Value::Quantity(self)
+        }
+    }
+}
+
+/// Displays the quantity the way it would be typed,
+/// for example ``9.81 m/s^2``.
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.magnitude, self.unit)
+    }
+}
@@ -4,7 +4,16 @@
 //! is used to represent each distinct element on
 //! the calculator's stack.
 
-use std::fmt;
+use super::{
+    matrix::{Entry, Matrix, Scalar},
+    units::{Dimension, Quantity},
+    words::Quotation,
+};
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+use std::{collections::BTreeMap, fmt};
 
 /// Stores a type without a concrete value
 ///
@@ -19,11 +28,38 @@ use std::fmt;
 /// Types are unit for now, but may eventually
 /// themselves be parameterized to allow for
 /// generics.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Type {
+    /// Specifically ``Int8``
+    Int8,
+
+    /// Specifically ``Int16``
+    Int16,
+
     /// Specifically ``Int32``
     Int32,
 
+    /// Specifically ``Int64``
+    Int64,
+
+    /// Specifically ``UInt8``
+    UInt8,
+
+    /// Specifically ``UInt16``
+    UInt16,
+
+    /// Specifically ``UInt32``
+    UInt32,
+
+    /// Specifically ``UInt64``
+    UInt64,
+
+    /// Specifically ``BigInt``
+    BigInt,
+
+    /// Any integer type, of fixed size or not
+    Integer,
+
     /// Specifically ``Float32``
     Float32,
 
@@ -34,49 +70,576 @@ pub enum Type {
     ///
     /// Precision may be fixed, floating, or exact.
     Fractional,
+
+    /// Specifically ``Rational``
+    Rational,
+
+    /// Specifically ``Complex``
+    Complex,
+
+    /// Specifically ``Matrix``
+    Matrix,
+
+    /// Specifically ``Str``
+    Str,
+
+    /// Specifically ``Symbol``
+    Symbol,
+
+    /// Specifically ``Bool``
+    Bool,
+
+    /// Specifically ``List``
+    List,
+
+    /// Specifically ``Map``
+    Map,
+
+    /// Any type that can be used as the key of a ``Map``
+    Key,
+
+    /// Any ``Quantity``, regardless of its dimension
+    Quantity,
+
+    /// A ``Quantity`` of exactly the given dimension,
+    /// such as ``Dimension::LENGTH``
+    Dimension(Dimension),
+
+    /// Any plain number or ``Quantity``
+    Number,
+
+    /// Specifically ``Quotation``
+    Quotation,
+
+    /// Any type at all
+    Any,
 }
 
 /// Represents a single value on the calculator stack
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
+    /// An 8-bit signed integer
+    Int8(i8),
+
+    /// A 16-bit signed integer
+    Int16(i16),
+
     /// A 32-bit signed integer
     Int32(i32),
 
+    /// A 64-bit signed integer
+    Int64(i64),
+
+    /// An 8-bit unsigned integer
+    UInt8(u8),
+
+    /// A 16-bit unsigned integer
+    UInt16(u16),
+
+    /// A 32-bit unsigned integer
+    UInt32(u32),
+
+    /// A 64-bit unsigned integer
+    UInt64(u64),
+
+    /// A signed integer of any size
+    BigInt(BigInt),
+
     /// A 32-bit floating point number
     Float32(f32),
 
     /// A 64-bit floating point number
     Float64(f64),
+
+    /// An exact fraction of two integers of any size
+    Rational(BigRational),
+
+    /// A complex number with 64-bit floating point parts
+    Complex(Complex64),
+
+    /// A dense matrix of ``Float64``, ``Rational``, or
+    /// ``Complex`` numbers
+    Matrix(Matrix),
+
+    /// A string of text
+    Str(String),
+
+    /// A bare name, used to label things such as map
+    /// entries
+    Symbol(String),
+
+    /// Either true or false
+    Bool(bool),
+
+    /// An ordered sequence of values of any type
+    List(Vec<Value>),
+
+    /// An associative collection of values
+    Map(Map),
+
+    /// A 64-bit floating point number with a unit
+    /// of measure
+    Quantity(Quantity),
+
+    /// A block of code that can be called later
+    Quotation(Quotation),
+}
+
+impl Type {
+    /// Look up a ``Type`` by the name used to write it
+    /// in a stack effect, such as ``Float64``. The names
+    /// of dimensions, such as ``Length``, refer to
+    /// quantities of that dimension.
+    pub fn from_name(name: &str) -> Option<Type> {
+        let typ = match name {
+            "Int8" => Type::Int8,
+            "Int16" => Type::Int16,
+            "Int32" => Type::Int32,
+            "Int64" => Type::Int64,
+            "UInt8" => Type::UInt8,
+            "UInt16" => Type::UInt16,
+            "UInt32" => Type::UInt32,
+            "UInt64" => Type::UInt64,
+            "BigInt" => Type::BigInt,
+            "Integer" => Type::Integer,
+            "Float32" => Type::Float32,
+            "Float64" => Type::Float64,
+            "Fractional" => Type::Fractional,
+            "Rational" => Type::Rational,
+            "Complex" => Type::Complex,
+            "Matrix" => Type::Matrix,
+            "Str" => Type::Str,
+            "Symbol" => Type::Symbol,
+            "Bool" => Type::Bool,
+            "List" => Type::List,
+            "Map" => Type::Map,
+            "Key" => Type::Key,
+            "Quantity" => Type::Quantity,
+            "Number" => Type::Number,
+            "Quotation" => Type::Quotation,
+            "Any" => Type::Any,
+            _ => return Dimension::from_name(name).map(Type::Dimension),
+        };
+
+        Some(typ)
+    }
+
+    /// The number of bits in an integer of the type, if
+    /// it is an integer type of fixed size.
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            Type::Int8 | Type::UInt8 => Some(8),
+            Type::Int16 | Type::UInt16 => Some(16),
+            Type::Int32 | Type::UInt32 => Some(32),
+            Type::Int64 | Type::UInt64 => Some(64),
+            _ => None,
+        }
+    }
+
+    /// Returns ``true`` for the types of single integers,
+    /// such as ``Int32`` or ``BigInt``.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Type::Int8
+                | Type::Int16
+                | Type::Int32
+                | Type::Int64
+                | Type::UInt8
+                | Type::UInt16
+                | Type::UInt32
+                | Type::UInt64
+                | Type::BigInt
+        )
+    }
+}
+
+/// The suffixes that give an integer literal its type,
+/// as in ``255u8``. Integers without one are ``Int32``s.
+pub const INTEGER_SUFFIXES: [(&str, Type); 9] = [
+    ("i8", Type::Int8),
+    ("i16", Type::Int16),
+    ("i32", Type::Int32),
+    ("i64", Type::Int64),
+    ("u8", Type::UInt8),
+    ("u16", Type::UInt16),
+    ("u32", Type::UInt32),
+    ("u64", Type::UInt64),
+    ("n", Type::BigInt),
+];
+
+/// Displays the type the way it is written in a
+/// stack effect.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Dimension(dim) => match dim.name() {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "Quantity({})", dim),
+            },
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// The key of a ``Map`` entry
+///
+/// Strings and symbols with the same text are
+/// distinct keys.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Str(String),
+    Symbol(String),
 }
 
+/// The associative collection stored in ``Value::Map``
+///
+/// Entries are kept sorted by key, so maps with the
+/// same entries always display identically.
+pub type Map = BTreeMap<Key, Value>;
+
 impl Value {
     /// Returns ``true`` if the ``Value`` can be treated
     /// as the specified ``Type``.
     pub fn matches(&self, candiate: &Type) -> bool {
+        if *candiate == Type::Any {
+            return true;
+        }
+
+        if *candiate == Type::Number {
+            return self.to_f64().is_some() || self.matches(&Type::Quantity);
+        }
+
+        if *candiate == Type::Integer {
+            return self.type_of().is_integer();
+        }
+
         match self {
+            Value::Int8(_) => *candiate == Type::Int8,
+            Value::Int16(_) => *candiate == Type::Int16,
             Value::Int32(_) => *candiate == Type::Int32,
+            Value::Int64(_) => *candiate == Type::Int64,
+            Value::UInt8(_) => *candiate == Type::UInt8,
+            Value::UInt16(_) => *candiate == Type::UInt16,
+            Value::UInt32(_) => *candiate == Type::UInt32,
+            Value::UInt64(_) => *candiate == Type::UInt64,
+            Value::BigInt(_) => *candiate == Type::BigInt,
             Value::Float32(_) => *candiate == Type::Float32 || *candiate == Type::Fractional,
             Value::Float64(_) => *candiate == Type::Float64 || *candiate == Type::Fractional,
+            Value::Rational(_) => *candiate == Type::Rational,
+            Value::Complex(_) => *candiate == Type::Complex,
+            Value::Matrix(_) => *candiate == Type::Matrix,
+            Value::Str(_) => *candiate == Type::Str || *candiate == Type::Key,
+            Value::Symbol(_) => *candiate == Type::Symbol || *candiate == Type::Key,
+            Value::Bool(_) => *candiate == Type::Bool,
+            Value::List(_) => *candiate == Type::List,
+            Value::Map(_) => *candiate == Type::Map,
+            Value::Quantity(q) => {
+                *candiate == Type::Quantity || *candiate == Type::Dimension(q.dimension())
+            }
+            Value::Quotation(_) => *candiate == Type::Quotation,
         }
     }
-    
+
+    /// Returns the most specific ``Type`` that the
+    /// ``Value`` matches.
+    pub fn type_of(&self) -> Type {
+        match self {
+            Value::Int8(_) => Type::Int8,
+            Value::Int16(_) => Type::Int16,
+            Value::Int32(_) => Type::Int32,
+            Value::Int64(_) => Type::Int64,
+            Value::UInt8(_) => Type::UInt8,
+            Value::UInt16(_) => Type::UInt16,
+            Value::UInt32(_) => Type::UInt32,
+            Value::UInt64(_) => Type::UInt64,
+            Value::BigInt(_) => Type::BigInt,
+            Value::Float32(_) => Type::Float32,
+            Value::Float64(_) => Type::Float64,
+            Value::Rational(_) => Type::Rational,
+            Value::Complex(_) => Type::Complex,
+            Value::Matrix(_) => Type::Matrix,
+            Value::Str(_) => Type::Str,
+            Value::Symbol(_) => Type::Symbol,
+            Value::Bool(_) => Type::Bool,
+            Value::List(_) => Type::List,
+            Value::Map(_) => Type::Map,
+            Value::Quantity(q) => Type::Dimension(q.dimension()),
+            Value::Quotation(_) => Type::Quotation,
+        }
+    }
+
+    /// Returns the ``Value`` as a ``f64``, if it is a
+    /// plain number without a unit.
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float32(x) => Some(f64::from(*x)),
+            Value::Float64(x) => Some(*x),
+            _ => self.to_bigint().and_then(|x| x.to_f64()),
+        }
+    }
+
+    /// Returns the ``Value`` as a ``BigInt``, if it is an
+    /// integer of any type.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Value::Int8(x) => Some(BigInt::from(*x)),
+            Value::Int16(x) => Some(BigInt::from(*x)),
+            Value::Int32(x) => Some(BigInt::from(*x)),
+            Value::Int64(x) => Some(BigInt::from(*x)),
+            Value::UInt8(x) => Some(BigInt::from(*x)),
+            Value::UInt16(x) => Some(BigInt::from(*x)),
+            Value::UInt32(x) => Some(BigInt::from(*x)),
+            Value::UInt64(x) => Some(BigInt::from(*x)),
+            Value::BigInt(x) => Some(x.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the bits of an integer of fixed size, with
+    /// those above its size clear.
+    pub fn to_word(&self) -> Option<u64> {
+        match self {
+            Value::Int8(x) => Some(u64::from(*x as u8)),
+            Value::Int16(x) => Some(u64::from(*x as u16)),
+            Value::Int32(x) => Some(u64::from(*x as u32)),
+            Value::Int64(x) => Some(*x as u64),
+            Value::UInt8(x) => Some(u64::from(*x)),
+            Value::UInt16(x) => Some(u64::from(*x)),
+            Value::UInt32(x) => Some(u64::from(*x)),
+            Value::UInt64(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer of the given fixed-size type
+    /// whose bits are the lowest bits of ``word``.
+    pub fn from_word(word: u64, typ: &Type) -> Option<Value> {
+        let val = match typ {
+            Type::Int8 => Value::Int8(word as i8),
+            Type::Int16 => Value::Int16(word as i16),
+            Type::Int32 => Value::Int32(word as i32),
+            Type::Int64 => Value::Int64(word as i64),
+            Type::UInt8 => Value::UInt8(word as u8),
+            Type::UInt16 => Value::UInt16(word as u16),
+            Type::UInt32 => Value::UInt32(word as u32),
+            Type::UInt64 => Value::UInt64(word),
+            _ => return None,
+        };
+
+        Some(val)
+    }
+
+    /// Returns an integer of the given type equal to
+    /// ``x``, unless the type is too small to hold it.
+    pub fn integer(x: BigInt, typ: &Type) -> Option<Value> {
+        let val = match typ {
+            Type::Int8 => Value::Int8(x.to_i8()?),
+            Type::Int16 => Value::Int16(x.to_i16()?),
+            Type::Int32 => Value::Int32(x.to_i32()?),
+            Type::Int64 => Value::Int64(x.to_i64()?),
+            Type::UInt8 => Value::UInt8(x.to_u8()?),
+            Type::UInt16 => Value::UInt16(x.to_u16()?),
+            Type::UInt32 => Value::UInt32(x.to_u32()?),
+            Type::UInt64 => Value::UInt64(x.to_u64()?),
+            Type::BigInt => Value::BigInt(x),
+            _ => return None,
+        };
+
+        Some(val)
+    }
+
     /// Returns a string representation of the ``Value``'s
     /// type.
     pub fn type_str(&self) -> &str {
         match self {
+            Value::Int8(_) => "Int8",
+            Value::Int16(_) => "Int16",
             Value::Int32(_) => "Int32",
+            Value::Int64(_) => "Int64",
+            Value::UInt8(_) => "UInt8",
+            Value::UInt16(_) => "UInt16",
+            Value::UInt32(_) => "UInt32",
+            Value::UInt64(_) => "UInt64",
+            Value::BigInt(_) => "BigInt",
             Value::Float32(_) => "Float32",
             Value::Float64(_) => "Float64",
+            Value::Rational(_) => "Rational",
+            Value::Complex(_) => "Complex",
+            Value::Matrix(_) => "Matrix",
+            Value::Str(_) => "Str",
+            Value::Symbol(_) => "Symbol",
+            Value::Bool(_) => "Bool",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Quantity(q) => q.dimension().name().unwrap_or("Quantity"),
+            Value::Quotation(_) => "Quotation",
+        }
+    }
+}
+
+impl From<Scalar> for Value {
+    fn from(x: Scalar) -> Value {
+        match x {
+            Scalar::Float64(x) => Value::Float64(x),
+            Scalar::Rational(x) => Value::Rational(x),
+            Scalar::Complex(z) => Value::Complex(z),
         }
     }
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.in_radix(Radix::Decimal))
+    }
+}
+
+/// The base in which integers are displayed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Radix {
+    #[default]
+    Decimal,
+    Hexadecimal,
+    Binary,
+    Octal,
+}
+
+impl Radix {
+    /// Returns the ``Radix`` with the given name, which
+    /// is one of ``dec``, ``hex``, ``bin``, or ``oct``.
+    pub fn from_name(name: &str) -> Option<Radix> {
+        match name {
+            "dec" => Some(Radix::Decimal),
+            "hex" => Some(Radix::Hexadecimal),
+            "bin" => Some(Radix::Binary),
+            "oct" => Some(Radix::Octal),
+            _ => None,
+        }
+    }
+
+    /// The name of the radix, as ``from_name`` accepts it
+    pub fn name(self) -> &'static str {
         match self {
+            Radix::Decimal => "dec",
+            Radix::Hexadecimal => "hex",
+            Radix::Binary => "bin",
+            Radix::Octal => "oct",
+        }
+    }
+}
+
+impl fmt::Display for Radix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Displays a ``Value`` with its integers, including
+/// those inside lists and maps, in a given ``Radix``
+///
+/// Integers of fixed size in any radix but decimal are
+/// shown as the bits of a word the size of their type,
+/// so that ``-1`` is ``0xffffffff`` as an ``Int32`` and
+/// ``0xff`` as an ``Int8``. Big integers are shown with
+/// their sign, as in ``-0x1``.
+pub struct InRadix<'a>(&'a Value, Radix);
+
+impl Value {
+    /// Returns a wrapper that displays the value with
+    /// its integers in the given radix.
+    pub fn in_radix(&self, radix: Radix) -> InRadix<'_> {
+        InRadix(self, radix)
+    }
+}
+
+impl fmt::Display for InRadix<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let InRadix(value, radix) = *self;
+
+        if let (Some(word), false) = (value.to_word(), radix == Radix::Decimal) {
+            return write_integer(f, word, radix);
+        }
+
+        match value {
+            Value::Int8(x) => write!(f, "{}", x),
+            Value::Int16(x) => write!(f, "{}", x),
             Value::Int32(x) => write!(f, "{}", x),
+            Value::Int64(x) => write!(f, "{}", x),
+            Value::UInt8(x) => write!(f, "{}", x),
+            Value::UInt16(x) => write!(f, "{}", x),
+            Value::UInt32(x) => write!(f, "{}", x),
+            Value::UInt64(x) => write!(f, "{}", x),
+            Value::BigInt(x) => write_integer(f, x, radix),
             Value::Float32(x) => write!(f, "{}", x),
             Value::Float64(x) => write!(f, "{}", x),
+            Value::Rational(x) => x.write_literal(f),
+            Value::Complex(z) => z.write_literal(f),
+            Value::Matrix(m) => write!(f, "{}", m),
+            Value::Str(s) => write!(f, "\"{}\"", s),
+            Value::Symbol(s) => write!(f, "'{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::List(items) => {
+                write!(f, "{{")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item.in_radix(radix))?;
+                }
+                write!(f, "}}")
+            }
+            Value::Map(map) => {
+                write!(f, "#{{")?;
+                for (idx, (key, val)) in map.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", key, val.in_radix(radix))?;
+                }
+                write!(f, "}}")
+            }
+            Value::Quantity(q) => write!(f, "{}", q),
+            Value::Quotation(q) => write!(f, "{}", q),
+        }
+    }
+}
+
+// Write an integer with the prefix of its radix.
+fn write_integer<T>(f: &mut fmt::Formatter<'_>, x: T, radix: Radix) -> fmt::Result
+where
+    T: fmt::Display + fmt::LowerHex + fmt::Binary + fmt::Octal,
+{
+    match radix {
+        Radix::Decimal => write!(f, "{}", x),
+        Radix::Hexadecimal => write!(f, "{:#x}", x),
+        Radix::Binary => write!(f, "{:#b}", x),
+        Radix::Octal => write!(f, "{:#o}", x),
+    }
+}
+
+impl Key {
+    /// Returns the ``Key`` equivalent to the ``Value``,
+    /// if the ``Value`` can be used as a key.
+    pub fn from_value(val: &Value) -> Option<Key> {
+        match val {
+            Value::Str(s) => Some(Key::Str(s.clone())),
+            Value::Symbol(s) => Some(Key::Symbol(s.clone())),
+            _ => None,
         }
     }
+
+    /// Converts the ``Key`` back into the ``Value``
+    /// it was made from.
+    pub fn to_value(&self) -> Value {
+        match self {
+            Key::Str(s) => Value::Str(s.clone()),
+            Key::Symbol(s) => Value::Symbol(s.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
 }
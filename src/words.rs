@@ -0,0 +1,480 @@
+//! User-defined words and quotations
+//!
+//! This module groups parsed tokens into the nodes that
+//! the ``Interpreter`` runs, handling the nested forms:
+//! quotations, word definitions, and stack effects.
+
+use super::{
+    bytecode::Code,
+    infix,
+    interpreter::EvalError,
+    parser::{self, ParsedToken},
+    units::Unit,
+    value::{Type, Value},
+};
+use std::{fmt, iter::Peekable, rc::Rc};
+
+/// A single step of a program
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    /// A token that runs on its own, along with the
+    /// text that it was parsed from
+    Token(String, ParsedToken),
+
+    /// A reference to a local name bound by ``::``
+    Local(String),
+
+    /// A quotation, which is pushed to the stack when
+    /// it runs
+    Quotation(Rc<Vec<Node>>),
+
+    /// A word definition, which defines the word when
+    /// it runs
+    Definition(Rc<Definition>),
+
+    /// ``include "path"``, which runs a file
+    Include(String),
+
+    /// ``use name``, which makes the words of another
+    /// vocabulary visible without qualification
+    Use(String),
+
+    /// ``vocab name``, which makes later definitions go
+    /// into the named vocabulary
+    Vocab(String),
+}
+
+/// The local names visible to running code, and
+/// their values
+pub type Frame = Vec<(String, Value)>;
+
+/// A block of code on the stack, which can be run
+/// with ``call``
+///
+/// Quotations are closures: a quotation created
+/// inside a ``::`` definition captures the values of
+/// its locals, and every quotation looks up names in
+/// the vocabulary it was created in.
+#[derive(Clone, Debug)]
+pub struct Quotation {
+    pub body: Rc<Vec<Node>>,
+
+    /// The body, compiled along with the code that
+    /// made the quotation
+    pub code: Code,
+
+    pub captured: Frame,
+    pub vocabulary: Rc<str>,
+}
+
+// Quotations are compared by their source, since the
+// same source always compiles to the same code.
+impl PartialEq for Quotation {
+    fn eq(&self, other: &Quotation) -> bool {
+        self.body == other.body
+            && self.captured == other.captured
+            && self.vocabulary == other.vocabulary
+    }
+}
+
+/// A user-defined word
+#[derive(Debug, PartialEq)]
+pub struct Definition {
+    pub name: String,
+
+    /// The declared stack effect, which is checked
+    /// whenever the word runs
+    pub effect: Option<Effect>,
+
+    /// Whether the inputs are bound to local names,
+    /// as with ``::``
+    pub binds_locals: bool,
+
+    pub body: Rc<Vec<Node>>,
+}
+
+/// A declared stack effect, such as ``( a b -- c )``
+///
+/// Each input and output has a name, and may have a
+/// type, written as ``name:Type``. Entries without a
+/// type accept any value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effect {
+    pub inputs: Vec<(String, Option<Type>)>,
+    pub outputs: Vec<(String, Option<Type>)>,
+}
+
+impl Effect {
+    /// Return the types of the inputs, deepest first,
+    /// as used by ``typecheck::check_args``.
+    pub fn input_types(&self) -> Vec<Type> {
+        Effect::types(&self.inputs)
+    }
+
+    /// Return the types of the outputs, deepest first,
+    /// as used by ``typecheck::check_results``.
+    pub fn output_types(&self) -> Vec<Type> {
+        Effect::types(&self.outputs)
+    }
+
+    fn types(entries: &[(String, Option<Type>)]) -> Vec<Type> {
+        entries
+            .iter()
+            .map(|(_, typ)| typ.clone().unwrap_or(Type::Any))
+            .collect()
+    }
+}
+
+/// Group parsed tokens into a sequence of ``Node``s.
+///
+/// Definitions may only appear at the top level, and
+/// every ``[`` and ``:`` must be closed. Text between
+/// ``(`` and ``)`` is a comment, except when it is the
+/// stack effect of a definition.
+pub fn compile<'a, I>(tokens: I) -> Result<Vec<Node>, EvalError>
+where
+    I: IntoIterator<Item = (&'a str, ParsedToken)>,
+{
+    let mut compiler = Compiler { locals: Vec::new() };
+    compiler.sequence(&mut tokens.into_iter().peekable(), Context::TopLevel)
+}
+
+/// Where in the program a sequence of nodes appears
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    TopLevel,
+    Quotation,
+    Definition,
+}
+
+struct Compiler {
+    /// The locals bound by the ``::`` definition being
+    /// compiled, if any
+    locals: Vec<String>,
+}
+
+impl Compiler {
+    fn sequence<'a, I>(
+        &mut self,
+        tokens: &mut Peekable<I>,
+        context: Context,
+    ) -> Result<Vec<Node>, EvalError>
+    where
+        I: Iterator<Item = (&'a str, ParsedToken)>,
+    {
+        let mut nodes = Vec::new();
+
+        while let Some((tok, parsed_tok)) = tokens.next() {
+            // Locals shadow everything else, including
+            // builtins and units.
+            if self.locals.iter().any(|local| local == tok) {
+                nodes.push(Node::Local(tok.to_string()));
+                continue;
+            }
+
+            match parsed_tok {
+                ParsedToken::StartQuotation => {
+                    let body = self.sequence(tokens, Context::Quotation)?;
+                    nodes.push(Node::Quotation(Rc::new(body)));
+                }
+                ParsedToken::EndQuotation if context == Context::Quotation => return Ok(nodes),
+                ParsedToken::EndDefinition if context == Context::Definition => return Ok(nodes),
+                ParsedToken::StartDefinition | ParsedToken::StartLocalsDefinition => {
+                    if context != Context::TopLevel {
+                        return Err(syntax("definitions must be at the top level"));
+                    }
+
+                    let binds_locals = parsed_tok == ParsedToken::StartLocalsDefinition;
+                    let def = self.definition(tokens, binds_locals)?;
+                    nodes.push(Node::Definition(Rc::new(def)));
+                }
+                ParsedToken::Word(name) if is_directive(&name) => {
+                    if context != Context::TopLevel {
+                        return Err(syntax(&format!("{} must be at the top level", name)));
+                    }
+
+                    nodes.push(directive(&name, tokens)?);
+                }
+                ParsedToken::Infix(expr) => self.infix(&expr, &mut nodes)?,
+                // Parentheses anywhere but after the name
+                // in a definition hold a comment.
+                ParsedToken::StartEffect => skip_comment(tokens)?,
+                ParsedToken::EndQuotation | ParsedToken::EndDefinition | ParsedToken::EndEffect => {
+                    return Err(syntax(&format!("unexpected {}", tok)))
+                }
+                // The bare name would be read as the unit,
+                // so such a variable could only be recalled.
+                ParsedToken::Store(name) if Unit::parse(&name).is_some() => {
+                    return Err(syntax(&format!(
+                        "cannot store to {}, as it is a unit",
+                        name
+                    )))
+                }
+                ParsedToken::BadToken => return Err(EvalError::BadToken(tok.to_string())),
+                _ => nodes.push(Node::Token(tok.to_string(), parsed_tok)),
+            }
+        }
+
+        match context {
+            Context::TopLevel => Ok(nodes),
+            Context::Quotation => Err(syntax("missing ]")),
+            Context::Definition => Err(syntax("missing ;")),
+        }
+    }
+
+    fn definition<'a, I>(
+        &mut self,
+        tokens: &mut Peekable<I>,
+        binds_locals: bool,
+    ) -> Result<Definition, EvalError>
+    where
+        I: Iterator<Item = (&'a str, ParsedToken)>,
+    {
+        // Builtins may be shadowed by user-defined words,
+        // but units may not, as quantities would then be
+        // read differently once a word is defined.
+        let name = match tokens.next() {
+            Some((_, ParsedToken::Word(name))) => name,
+            Some((tok, ParsedToken::Intrinsic(_))) if parser::is_identifier(tok) => tok.to_string(),
+            Some((tok, ParsedToken::Unit(_))) => {
+                return Err(syntax(&format!("cannot define {}, as it is a unit", tok)))
+            }
+            Some((tok, _)) => return Err(syntax(&format!("cannot define {}", tok))),
+            None => return Err(syntax("missing word name")),
+        };
+
+        // A stack effect may follow the name, and is
+        // required when binding locals.
+        let effect = match tokens.peek() {
+            Some((_, ParsedToken::StartEffect)) => {
+                tokens.next();
+                Some(effect(tokens)?)
+            }
+            _ => None,
+        };
+
+        if binds_locals {
+            match &effect {
+                Some(effect) => {
+                    self.locals = effect.inputs.iter().map(|(name, _)| name.clone()).collect();
+                }
+                None => return Err(syntax(&format!("{} needs a stack effect", name))),
+            }
+        }
+
+        let body = self.sequence(tokens, Context::Definition);
+        self.locals.clear();
+
+        Ok(Definition {
+            name,
+            effect,
+            binds_locals,
+            body: Rc::new(body?),
+        })
+    }
+
+    // Translate an infix expression, and add the nodes
+    // of the postfix code it stands for.
+    fn infix(&self, expr: &str, nodes: &mut Vec<Node>) -> Result<(), EvalError> {
+        let postfix = infix::translate(expr)
+            .map_err(|msg| syntax(&msg))?
+            .join(" ");
+
+        for (tok, parsed_tok) in parser::parse_line(&postfix) {
+            if self.locals.iter().any(|local| local == tok) {
+                nodes.push(Node::Local(tok.to_string()));
+            } else if parsed_tok == ParsedToken::BadToken {
+                return Err(EvalError::BadToken(tok.to_string()));
+            } else {
+                nodes.push(Node::Token(tok.to_string(), parsed_tok));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_directive(name: &str) -> bool {
+    name == "include" || name == "use" || name == "vocab"
+}
+
+// Parse the argument of a directive, which is a path
+// string for ``include`` and a vocabulary name for
+// the others.
+fn directive<'a, I>(name: &str, tokens: &mut I) -> Result<Node, EvalError>
+where
+    I: Iterator<Item = (&'a str, ParsedToken)>,
+{
+    match (name, tokens.next()) {
+        ("include", Some((_, ParsedToken::Literal(Value::Str(path))))) => Ok(Node::Include(path)),
+        ("include", _) => Err(syntax("include needs a path string")),
+        ("use", Some((tok, _))) if parser::is_vocabulary_name(tok) => {
+            Ok(Node::Use(tok.to_string()))
+        }
+        ("vocab", Some((tok, _))) if parser::is_vocabulary_name(tok) => {
+            Ok(Node::Vocab(tok.to_string()))
+        }
+        _ => Err(syntax(&format!("{} needs a vocabulary name", name))),
+    }
+}
+
+// Skip the inside of a comment, after the ``(``.
+fn skip_comment<'a, I>(tokens: &mut I) -> Result<(), EvalError>
+where
+    I: Iterator<Item = (&'a str, ParsedToken)>,
+{
+    match tokens.find(|(_, parsed_tok)| *parsed_tok == ParsedToken::EndEffect) {
+        Some(_) => Ok(()),
+        None => Err(syntax("missing )")),
+    }
+}
+
+/// Returns ``true`` if the tokens leave a quotation,
+/// definition, or parenthesis open, so that more
+/// input is needed to complete them.
+///
+/// Nothing inside parentheses is counted, as
+/// comments may hold any tokens.
+pub fn is_incomplete(tokens: &[(&str, ParsedToken)]) -> bool {
+    let mut depth = 0;
+    let mut tokens = tokens.iter();
+
+    while let Some((_, parsed_tok)) = tokens.next() {
+        match parsed_tok {
+            ParsedToken::StartQuotation
+            | ParsedToken::StartDefinition
+            | ParsedToken::StartLocalsDefinition => depth += 1,
+            ParsedToken::EndQuotation | ParsedToken::EndDefinition => depth -= 1,
+            ParsedToken::StartEffect => {
+                let closed = tokens.any(|(_, parsed_tok)| *parsed_tok == ParsedToken::EndEffect);
+                if !closed {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    depth > 0
+}
+
+// Parse the inside of a stack effect, after the ``(``.
+fn effect<'a, I>(tokens: &mut I) -> Result<Effect, EvalError>
+where
+    I: Iterator<Item = (&'a str, ParsedToken)>,
+{
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut seen_separator = false;
+
+    loop {
+        match tokens.next() {
+            Some((_, ParsedToken::EndEffect)) => break,
+            Some(("--", _)) if !seen_separator => seen_separator = true,
+            Some((tok, _)) if seen_separator => outputs.push(effect_entry(tok)?),
+            Some((tok, _)) => inputs.push(effect_entry(tok)?),
+            None => return Err(syntax("missing )")),
+        }
+    }
+
+    if !seen_separator {
+        return Err(syntax("stack effect is missing --"));
+    }
+
+    Ok(Effect { inputs, outputs })
+}
+
+// Parse one ``name`` or ``name:Type`` entry of a
+// stack effect.
+fn effect_entry(tok: &str) -> Result<(String, Option<Type>), EvalError> {
+    let (name, typ) = match tok.find(':') {
+        Some(idx) => {
+            let typ = Type::from_name(&tok[(idx + 1)..])
+                .ok_or_else(|| syntax(&format!("unknown type in {}", tok)))?;
+
+            (&tok[..idx], Some(typ))
+        }
+        None => (tok, None),
+    };
+
+    if !parser::is_identifier(name) {
+        return Err(syntax(&format!("bad name in stack effect: {}", tok)));
+    }
+
+    Ok((name.to_string(), typ))
+}
+
+fn syntax(msg: &str) -> EvalError {
+    EvalError::Syntax(msg.to_string())
+}
+
+/// Displays the node as source code that would
+/// compile to it.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Token(tok, _) => write!(f, "{}", tok),
+            Node::Local(name) => write!(f, "{}", name),
+            Node::Quotation(body) => {
+                write!(f, "[ ")?;
+                write_body(f, body)?;
+                write!(f, "]")
+            }
+            Node::Definition(def) => write!(f, "{}", def),
+            Node::Include(path) => write!(f, "include \"{}\"", path),
+            Node::Use(name) => write!(f, "use {}", name),
+            Node::Vocab(name) => write!(f, "vocab {}", name),
+        }
+    }
+}
+
+impl fmt::Display for Quotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[ ")?;
+        write_body(f, &self.body)?;
+        write!(f, "]")
+    }
+}
+
+impl fmt::Display for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = if self.binds_locals { "::" } else { ":" };
+        write!(f, "{} {} ", start, self.name)?;
+
+        if let Some(effect) = &self.effect {
+            write!(f, "{} ", effect)?;
+        }
+
+        write_body(f, &self.body)?;
+        write!(f, ";")
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        write_entries(f, &self.inputs)?;
+        write!(f, " --")?;
+        write_entries(f, &self.outputs)?;
+        write!(f, " )")
+    }
+}
+
+fn write_body(f: &mut fmt::Formatter<'_>, body: &[Node]) -> fmt::Result {
+    for node in body {
+        write!(f, "{} ", node)?;
+    }
+
+    Ok(())
+}
+
+fn write_entries(f: &mut fmt::Formatter<'_>, entries: &[(String, Option<Type>)]) -> fmt::Result {
+    for (name, typ) in entries {
+        match typ {
+            Some(typ) => write!(f, " {}:{}", name, typ)?,
+            None => write!(f, " {}", name)?,
+        }
+    }
+
+    Ok(())
+}
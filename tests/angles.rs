@@ -0,0 +1,85 @@
+//! Tests for the angle mode of the trigonometric
+//! builtins
+
+mod common;
+
+use cattywampus::{functions::AngleMode, session, typecheck::TypeError, value::Value};
+use common::{close, error, eval, float, top};
+use std::f64::consts::{FRAC_PI_4, PI};
+
+fn assert_near(program: &str, expected: f64) {
+    let found = float(program);
+    assert!(
+        close(found, expected, 1e-12),
+        "{}: expected {} but got {}",
+        program,
+        expected,
+        found
+    );
+}
+
+#[test]
+fn arguments_are_read_in_the_mode() {
+    assert_near("0.5 sin", 0.479425538604203);
+    assert_near("deg 30.0 sin", 0.5);
+    assert_near("deg 45.0 tan", 1.0);
+    assert_near("deg 90.0 csc", 1.0);
+    assert_near("grad 100.0 sin", 1.0);
+}
+
+#[test]
+fn results_are_given_in_the_mode() {
+    assert_near("deg 1.0 asin", 90.0);
+    assert_near("deg -1.0 acos", 180.0);
+    assert_near("grad 0.0 acos", 100.0);
+    assert_near("deg rad 1.0 atan", FRAC_PI_4);
+
+    // Out of range arguments still have no angle.
+    assert!(float("deg 2.0 asin").is_nan());
+}
+
+#[test]
+fn whole_turns_are_exact() {
+    assert_eq!(top("deg 360.0 sin"), Value::Float64(0.0));
+    assert_eq!(top("deg 720.0 cos"), Value::Float64(1.0));
+    assert_eq!(top("grad -400.0 sin"), Value::Float64(0.0));
+}
+
+#[test]
+fn conversions_ignore_the_mode() {
+    assert_near("180.0 deg>rad", PI);
+    assert_near("deg 180.0 deg>rad", PI);
+    assert_near("3.141592653589793 rad>deg", 180.0);
+    assert_near("720.0 deg>rad", 4.0 * PI);
+}
+
+#[test]
+fn angles_keep_their_type() {
+    assert_eq!(top("deg 90.0f sin"), Value::Float32(1.0));
+    assert_eq!(top("deg 1.0f asin"), Value::Float32(90.0));
+    assert!(matches!(error("deg 30 sin"), TypeError::TypeMismatch));
+    assert!(matches!(error("deg \"x\" asin"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn the_mode_lasts_until_changed() {
+    let mut interp = eval("grad").unwrap();
+    assert_eq!(interp.angle_mode(), AngleMode::Gradians);
+
+    interp.eval_line("200.0 cos").unwrap();
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Float64(-1.0)));
+
+    // Words may change the mode when they run.
+    interp
+        .eval_line(":: sine_deg ( x -- y ) deg x sin rad ; 90.0 sine_deg")
+        .unwrap();
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Float64(1.0)));
+    assert_eq!(interp.angle_mode(), AngleMode::Radians);
+}
+
+#[test]
+fn sessions_keep_the_mode() {
+    let source = session::to_source(&eval("deg").unwrap()).unwrap();
+    let loaded = session::from_source(&source).unwrap();
+    assert_eq!(loaded.angle_mode(), AngleMode::Degrees);
+}
@@ -0,0 +1,170 @@
+//! Tests for the bitwise builtins and for showing
+//! integers in other radixes
+
+mod common;
+
+use cattywampus::{
+    typecheck::TypeError,
+    value::{Radix, Value},
+};
+use common::{error, top};
+
+fn int(program: &str) -> i32 {
+    match top(program) {
+        Value::Int32(x) => x,
+        other => panic!("{}: expected an Int32 but got {:?}", program, other),
+    }
+}
+
+#[test]
+fn logic_works_on_the_whole_word() {
+    assert_eq!(int("0b1100 0b1010 band"), 0b1000);
+    assert_eq!(int("0b1100 0b1010 bor"), 0b1110);
+    assert_eq!(int("0b1100 0b1010 bxor"), 0b0110);
+    assert_eq!(int("0 bnot"), -1);
+    assert_eq!(int("-2147483648 bnot"), i32::MAX);
+}
+
+#[test]
+fn shifts_reach_the_sign_bit_and_fill_with_zeros() {
+    assert_eq!(int("1 31 shl"), i32::MIN);
+    assert_eq!(int("3 31 shl"), i32::MIN);
+    assert_eq!(int("-1 31 shr"), 1);
+    assert_eq!(int("-16 2 shr"), 0x3fff_fffc);
+    assert_eq!(int("5 0 shr"), 5);
+}
+
+#[test]
+fn shifts_must_fit_the_word() {
+    for program in &["1 32 shl", "1 -1 shl", "1 32 shr", "1 -1 shr"] {
+        assert!(
+            matches!(error(program), TypeError::DomainError),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn rotations_wrap_any_amount() {
+    assert_eq!(int("0x7000_0001 4 rotl"), 0x17);
+    assert_eq!(int("1 1 rotr"), i32::MIN);
+    assert_eq!(int("1 -1 rotl"), i32::MIN);
+    assert_eq!(int("1 33 rotl"), 2);
+    assert_eq!(int("1 -2147483648 rotr"), 1);
+}
+
+#[test]
+fn counting_bits_at_zero_and_minus_one() {
+    assert_eq!(int("0 popcount"), 0);
+    assert_eq!(int("-1 popcount"), 32);
+    assert_eq!(int("0 clz"), 32);
+    assert_eq!(int("-1 clz"), 0);
+    assert_eq!(int("0 ctz"), 32);
+    assert_eq!(int("-2147483648 ctz"), 31);
+}
+
+#[test]
+fn bitwise_words_take_only_integers() {
+    assert!(matches!(error("1.0 2.0 band"), TypeError::TypeMismatch));
+    assert!(matches!(error("true popcount"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn integers_display_in_a_radix() {
+    let shown = |val: &Value, radix| val.in_radix(radix).to_string();
+
+    assert_eq!(shown(&Value::Int32(0), Radix::Binary), "0b0");
+    assert_eq!(shown(&Value::Int32(-1), Radix::Hexadecimal), "0xffffffff");
+    assert_eq!(
+        shown(&Value::Int32(i32::MIN), Radix::Octal),
+        "0o20000000000"
+    );
+    assert_eq!(shown(&Value::Int32(-1), Radix::Decimal), "-1");
+    assert_eq!(shown(&Value::Float64(2.5), Radix::Hexadecimal), "2.5");
+    assert_eq!(
+        shown(&top("{16,#{'a:{8}}}"), Radix::Hexadecimal),
+        "{0x10,#{'a:{0x8}}}"
+    );
+}
+
+#[test]
+fn integers_in_a_radix_read_back() {
+    let radixes = [Radix::Hexadecimal, Radix::Binary, Radix::Octal];
+
+    for x in &[0, -1, i32::MAX, i32::MIN, -0x1234_5678] {
+        for radix in &radixes {
+            let shown = Value::Int32(*x).in_radix(*radix).to_string();
+            assert_eq!(top(&shown), Value::Int32(*x), "{}", shown);
+        }
+    }
+}
+
+#[test]
+fn radixes_are_named() {
+    for name in &["dec", "hex", "bin", "oct"] {
+        assert_eq!(Radix::from_name(name).map(Radix::name), Some(*name));
+    }
+
+    assert_eq!(Radix::from_name("deg"), None);
+}
+
+#[test]
+fn words_are_as_wide_as_their_type() {
+    assert_eq!(top("0u8 bnot"), Value::UInt8(255));
+    assert_eq!(top("0i8 bnot"), Value::Int8(-1));
+    assert_eq!(top("1u8 7 shl"), Value::UInt8(128));
+    assert_eq!(top("1i8 7 shl"), Value::Int8(i8::MIN));
+    assert_eq!(top("-1i16 15 shr"), Value::Int16(1));
+    assert_eq!(top("0x81u8 1 rotl"), Value::UInt8(0x03));
+    assert_eq!(top("1u16 1 rotr"), Value::UInt16(0x8000));
+    assert_eq!(top("-1i64 popcount"), Value::Int64(64));
+    assert_eq!(top("1u8 clz"), Value::UInt8(7));
+    assert_eq!(top("0u16 ctz"), Value::UInt16(16));
+    assert_eq!(top("0xf0u8 0x3cu8 bxor"), Value::UInt8(0xcc));
+
+    assert!(matches!(error("1u8 8 shl"), TypeError::DomainError));
+    assert!(matches!(error("1u64 64u64 shr"), TypeError::DomainError));
+    assert!(matches!(error("1u8 1i8 band"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn big_integers_have_endless_sign_bits() {
+    let big = |digits: &str| Value::BigInt(digits.parse().unwrap());
+
+    assert_eq!(top("0n bnot"), big("-1"));
+    assert_eq!(top("-1n 0xffn band"), big("255"));
+    assert_eq!(top("1n 100 shl"), big("1267650600228229401496703205376"));
+    assert_eq!(top("-5n 1 shr"), big("-3"));
+    assert_eq!(top("-5n 1000 shr"), big("-1"));
+    assert_eq!(top("255n popcount"), big("8"));
+    assert_eq!(top("1024n ctz"), big("10"));
+
+    for program in &["-1n popcount", "0n ctz", "1n -1 shl"] {
+        assert!(
+            matches!(error(program), TypeError::DomainError),
+            "{}",
+            program
+        );
+    }
+
+    for program in &["1n 1 rotl", "1n 1 rotr", "1n clz"] {
+        assert!(
+            matches!(error(program), TypeError::TypeMismatch),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn every_integer_type_displays_in_a_radix() {
+    let shown = |program: &str, radix| top(program).in_radix(radix).to_string();
+
+    assert_eq!(shown("-1i8", Radix::Hexadecimal), "0xff");
+    assert_eq!(shown("5u8", Radix::Binary), "0b101");
+    assert_eq!(shown("-1i64", Radix::Octal), "0o1777777777777777777777");
+    assert_eq!(shown("-1i16", Radix::Decimal), "-1");
+    assert_eq!(shown("-255n", Radix::Hexadecimal), "-0xff");
+    assert_eq!(shown("{200u8}", Radix::Hexadecimal), "{0xc8}");
+}
@@ -0,0 +1,108 @@
+//! Tests for running programs from the command line
+//! and from standard input
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+const EXE: &str = env!("CARGO_BIN_EXE_cattywampus");
+
+fn run(args: &[&str]) -> Output {
+    Command::new(EXE).args(args).output().unwrap()
+}
+
+// Pipe a program to standard input.
+fn pipe(args: &[&str], source: &str) -> Output {
+    let mut child = Command::new(EXE)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn programs_print_the_top_of_the_stack() {
+    let output = run(&["-e", "2 3 + 4.0 sqrt"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "2\n");
+
+    let output = run(&["--all", "-e", "2 3 + 4.0 sqrt"]);
+    assert_eq!(stdout(&output), "5\n2\n");
+
+    let output = run(&["--quiet", "-e", "1"]);
+    assert_eq!(stdout(&output), "");
+
+    // An empty stack prints nothing.
+    let output = run(&["-e", ""]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn piped_programs_span_lines() {
+    let output = pipe(&[], ": twice\n  2 *\n;\n21 twice\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "42\n");
+}
+
+#[test]
+fn exit_codes_tell_errors_apart() {
+    // Parse errors
+    for program in &["1 2 $$", ": f [ ;", "[1,2;3]"] {
+        assert_eq!(run(&["-e", program]).status.code(), Some(2), "{}", program);
+    }
+    assert_eq!(pipe(&[], "1\n2 ]\n").status.code(), Some(2));
+
+    // Errors while running
+    for program in &["1 \"a\" +", "nope", "1.0 m 1.0 s +"] {
+        assert_eq!(run(&["-e", program]).status.code(), Some(1), "{}", program);
+    }
+    assert_eq!(pipe(&[], "1\n\"a\" +\n").status.code(), Some(1));
+
+    // Bad usage, and missing files
+    assert_eq!(run(&["--bogus"]).status.code(), Some(64));
+    assert_eq!(run(&["-e"]).status.code(), Some(64));
+    assert_eq!(run(&["-e", "1", "-e", "2"]).status.code(), Some(64));
+    assert_eq!(run(&["-e", "1", "script.cw"]).status.code(), Some(64));
+    assert_eq!(run(&["no/such/script.cw"]).status.code(), Some(66));
+}
+
+#[test]
+fn failures_print_nothing_to_stdout() {
+    let output = run(&["-e", "1 2 \"a\" +"]);
+    assert_eq!(stdout(&output), "");
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn errors_name_the_program_and_line() {
+    let output = pipe(&[], "1\n2\n\"a\" +\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("<stdin>:3: "), "{}", stderr);
+
+    let output = run(&["-e", "nope"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("-e:1: "), "{}", stderr);
+}
+
+#[test]
+fn the_prelude_can_be_left_out() {
+    assert_eq!(stdout(&run(&["-e", "3 sq"])), "9\n");
+    assert_eq!(run(&["--no-prelude", "-e", "3 sq"]).status.code(), Some(1));
+}
@@ -0,0 +1,57 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test file includes this module with
+//! ``mod common;``, and most use only some of it.
+
+#![allow(dead_code)]
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter},
+    typecheck::TypeError,
+    value::Value,
+};
+
+/// Run a program in a new ``Interpreter``, and return
+/// the interpreter.
+pub fn eval(program: &str) -> Result<Interpreter, EvalError> {
+    let mut interp = Interpreter::new();
+    interp.eval_line(program)?;
+    Ok(interp)
+}
+
+/// The stack that a program leaves, from the bottom up.
+pub fn stack(program: &str) -> Vec<Value> {
+    let interp = eval(program).unwrap_or_else(|err| panic!("{}: {}", program, err));
+    interp.stack().iter().cloned().collect()
+}
+
+/// The value that a program leaves on top of the stack.
+pub fn top(program: &str) -> Value {
+    let top = stack(program).pop();
+    top.unwrap_or_else(|| panic!("{}: empty stack", program))
+}
+
+/// The Float64 that a program leaves on top of the
+/// stack.
+pub fn float(program: &str) -> f64 {
+    match top(program) {
+        Value::Float64(x) => x,
+        other => panic!("{}: expected a Float64 but got {:?}", program, other),
+    }
+}
+
+/// The type error that a program fails with.
+pub fn error(program: &str) -> TypeError {
+    match eval(program) {
+        Err(EvalError::Type(err, _)) => err,
+        Err(err) => panic!("{}: expected a type error but got {}", program, err),
+        Ok(_) => panic!("{}: expected a type error", program),
+    }
+}
+
+/// Returns ``true`` if ``x`` is within ``tolerance`` of
+/// ``y``, relative to ``y`` or to 1, whichever is
+/// larger.
+pub fn close(x: f64, y: f64, tolerance: f64) -> bool {
+    x == y || (x - y).abs() <= tolerance * y.abs().max(1.0)
+}
@@ -0,0 +1,143 @@
+//! Tests for the regression and curve fitting builtins
+
+mod common;
+
+use cattywampus::{typecheck::TypeError, value::Value};
+use common::{close, error, stack};
+
+// The Float64s that a program leaves, or the items of
+// the list it leaves.
+fn floats(program: &str) -> Vec<f64> {
+    let vals = match stack(program).as_slice() {
+        [Value::List(items)] => items.clone(),
+        vals => vals.to_vec(),
+    };
+
+    vals.iter()
+        .map(|val| match val {
+            Value::Float64(x) => *x,
+            other => panic!("{}: expected a Float64 but got {:?}", program, other),
+        })
+        .collect()
+}
+
+fn assert_fit(program: &str, expected: &[f64], tolerance: f64) {
+    let found = floats(program);
+    assert!(
+        found.len() == expected.len()
+            && found
+                .iter()
+                .zip(expected)
+                .all(|(x, y)| close(*x, *y, tolerance)),
+        "{}: expected {:?} but got {:?}",
+        program,
+        expected,
+        found
+    );
+}
+
+#[test]
+fn lines_give_slope_intercept_and_r_squared() {
+    assert_fit("{1,2,3,4} {3,5,7,9} linreg", &[2.0, 1.0, 1.0], 1e-14);
+    assert_fit("{1,2,3,4,5} {2,4,5,4,5} linreg", &[0.6, 2.2, 0.6], 1e-14);
+    // A flat line explains flat data perfectly.
+    assert_fit("{1,2,3} {4,4,4} linreg", &[0.0, 4.0, 1.0], 1e-14);
+}
+
+#[test]
+fn lines_survive_large_offsets() {
+    assert_fit(
+        "{1000000001.0,1000000002.0,1000000003.0} {1.0,2.0,3.0} linreg",
+        &[1.0, -1000000000.0, 1.0],
+        1e-9,
+    );
+}
+
+#[test]
+fn lines_need_two_distinct_x() {
+    assert!(matches!(error("{1} {1} linreg"), TypeError::DomainError));
+    assert!(matches!(
+        error("{1,1} {1,2} linreg"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(error("{} {} linreg"), TypeError::DomainError));
+}
+
+#[test]
+fn points_must_pair_up() {
+    assert!(matches!(
+        error("{1,2} {1,2,3} linreg"),
+        TypeError::ShapeMismatch
+    ));
+    assert!(matches!(
+        error("{1,\"a\"} {1,2} linreg"),
+        TypeError::TypeMismatch
+    ));
+}
+
+#[test]
+fn polynomials_list_the_constant_term_first() {
+    assert_fit("{0,1,2,3} {1,6,17,34} 2 polyfit", &[1.0, 2.0, 3.0], 1e-12);
+    // Degree 0 is the mean.
+    assert_fit("{1,2,3} {1.0,5.0,6.0} 0 polyfit", &[4.0], 1e-14);
+    // The least-squares line through a parabola
+    assert_fit(
+        "{-1.0,0.0,1.0} {1.0,0.0,1.0} 1 polyfit",
+        &[2.0 / 3.0, 0.0],
+        1e-14,
+    );
+}
+
+#[test]
+fn high_degree_polynomials_stay_accurate() {
+    // y = x^5 at x = 1..=8, where the normal equations
+    // would square an already large condition number.
+    assert_fit(
+        "{1,2,3,4,5,6,7,8} {1,32,243,1024,3125,7776,16807,32768} 5 polyfit",
+        &[0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+        1e-8,
+    );
+}
+
+#[test]
+fn polynomials_need_enough_distinct_points() {
+    assert!(matches!(
+        error("{1,2} {1,2} 2 polyfit"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(
+        error("{1,1,1} {1,2,3} 1 polyfit"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(
+        error("{1,2} {1,2} -1 polyfit"),
+        TypeError::DomainError
+    ));
+}
+
+#[test]
+fn exponentials_and_powers_fit_logarithms() {
+    assert_fit(
+        "{0.0,1.0,2.0} {2.0,5.43656365691809,14.7781121978613} expfit",
+        &[2.0, 1.0],
+        1e-12,
+    );
+    assert_fit("{1.0,2.0,4.0} {3.0,12.0,48.0} powerfit", &[3.0, 2.0], 1e-14);
+    assert_fit("{1.0,2.0,4.0} {5.0,5.0,5.0} powerfit", &[5.0, 0.0], 1e-14);
+}
+
+#[test]
+fn logarithmic_fits_need_positive_data() {
+    assert!(matches!(
+        error("{1,2} {1,-2} expfit"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(
+        error("{1,2} {1,0} expfit"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(
+        error("{0,2} {1,2} powerfit"),
+        TypeError::DomainError
+    ));
+}
@@ -0,0 +1,120 @@
+//! Tests for inferring the stack effects of words
+
+mod common;
+
+use cattywampus::{
+    inference::InferenceError,
+    interpreter::{EvalError, Interpreter},
+};
+use common::eval;
+
+// The effect inferred for the word ``f``, if any.
+fn effect(program: &str) -> Option<String> {
+    let interp = eval(program).unwrap_or_else(|err| panic!("{}: {}", program, err));
+    interp.word_effect("f").map(|effect| effect.to_string())
+}
+
+fn inference_error(program: &str) -> InferenceError {
+    match eval(program) {
+        Err(EvalError::IllTyped(_, err)) => err,
+        Err(err) => panic!(
+            "{}: expected an ill-typed definition but got {}",
+            program, err
+        ),
+        Ok(_) => panic!("{}: expected an ill-typed definition", program),
+    }
+}
+
+#[test]
+fn effects_give_signature_types() {
+    assert_eq!(effect(": f 2.0 sqrt ;").unwrap(), "( -- Fractional )");
+    assert_eq!(effect(": f 1 + ;").unwrap(), "( Number -- Number )");
+    assert_eq!(
+        effect(": f sqrt sqrt ;").unwrap(),
+        "( Fractional -- Fractional )"
+    );
+    assert_eq!(effect(": f ;").unwrap(), "( -- )");
+}
+
+#[test]
+fn declared_types_refine_the_inputs() {
+    assert_eq!(
+        effect(": f ( x:Float64 -- y ) sqrt ;").unwrap(),
+        "( Float64 -- Fractional )"
+    );
+    assert_eq!(
+        effect(":: f ( a b -- c ) a b * ;").unwrap(),
+        "( Number Number -- Number )"
+    );
+}
+
+#[test]
+fn quotations_known_when_defined_are_followed() {
+    assert_eq!(
+        effect(": f [ 1 + ] call ;").unwrap(),
+        "( Number -- Number )"
+    );
+    assert_eq!(effect(": f [ ] ;").unwrap(), "( -- Quotation )");
+}
+
+#[test]
+fn run_time_values_leave_the_effect_unknown() {
+    // A quotation from the inputs, or a word that is not
+    // defined yet, can only be known when the word runs.
+    for program in &[": f call ;", ": f later ;"] {
+        assert_eq!(effect(program), None, "{}", program);
+    }
+
+    // Variables may hold any value.
+    assert_eq!(effect(": f x@ 1 + ;").unwrap(), "( -- Number )");
+
+    // Such words still run, and are checked as they do.
+    let mut interp = eval(": f later ; : later 2 ;").unwrap();
+    assert!(interp.eval_line("f").is_ok());
+}
+
+#[test]
+fn ill_typed_definitions_are_rejected() {
+    assert!(matches!(
+        inference_error(": f \"a\" 1 + ;"),
+        InferenceError::Mismatch { .. }
+    ));
+    assert!(matches!(
+        inference_error(": f ( x -- y ) 1 + + ;"),
+        InferenceError::TooManyInputs(_)
+    ));
+    assert!(matches!(
+        inference_error(": f ( -- a b ) 1 ;"),
+        InferenceError::WrongOutputs {
+            declared: 2,
+            inferred: 1
+        }
+    ));
+    assert!(matches!(
+        inference_error(": f ( -- x:Str ) 1 ;"),
+        InferenceError::Mismatch { .. }
+    ));
+}
+
+#[test]
+fn rejected_definitions_are_not_defined() {
+    let mut interp = Interpreter::new();
+    assert!(interp.eval_line(": f 1 ; : f \"a\" 1 + ;").is_err());
+
+    // The earlier definition is kept.
+    let kept = interp.word_effect("f").unwrap().to_string();
+    assert_eq!(kept, "( -- Int32 )");
+    assert!(interp.eval_line("f").is_ok());
+}
+
+#[test]
+fn words_use_the_effects_of_other_words() {
+    assert_eq!(
+        effect(": up 1.0 + ; : f up sqrt ;").unwrap(),
+        "( Number -- Fractional )"
+    );
+    assert!(matches!(
+        inference_error(": name ( -- x:Str ) \"a\" ; : f name sqrt ;"),
+        InferenceError::Mismatch { .. }
+    ));
+}
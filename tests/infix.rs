@@ -0,0 +1,70 @@
+//! Tests for infix expressions
+
+mod common;
+
+use cattywampus::{infix, interpreter::EvalError, value::Value};
+use common::{eval, stack};
+
+fn postfix(expr: &str) -> String {
+    match infix::translate(expr) {
+        Ok(tokens) => tokens.join(" "),
+        Err(msg) => panic!("{}: {}", expr, msg),
+    }
+}
+
+#[test]
+fn subtraction_is_left_associative() {
+    assert_eq!(postfix("1 - 2 - 3"), "1 2 - 3 -");
+    assert_eq!(postfix("8 / 4 / 2"), "8 4 / 2 /");
+    assert_eq!(stack("$( 10 - 4 - 3 )"), vec![Value::Int32(3)]);
+}
+
+#[test]
+fn powers_are_right_associative_and_bind_tightest() {
+    assert_eq!(postfix("2 ^ 3 ^ 2"), "2 3 2 pow pow");
+    assert_eq!(postfix("2 * (3 + 4) ^ 2"), "2 3 4 + 2 pow *");
+    assert_eq!(stack("$( 2 * (3 + 4) ^ 2 )"), vec![Value::Int32(98)]);
+}
+
+#[test]
+fn unary_minus_binds_looser_than_powers() {
+    assert_eq!(postfix("-2 ^ 2"), "2 2 pow negate");
+    assert_eq!(postfix("-x * 3"), "x negate 3 *");
+
+    // After an operator, a minus sign starts a literal.
+    assert_eq!(postfix("2 * -3"), "2 -3 *");
+    assert_eq!(postfix("2 - -3"), "2 -3 -");
+}
+
+#[test]
+fn calls_take_any_number_of_arguments() {
+    assert_eq!(postfix("f()"), "f");
+    assert_eq!(postfix("sin(x) + cos(y)"), "x sin y cos +");
+    assert_eq!(postfix("logn(x + 1, 2.0)"), "x 1 + 2.0 logn");
+    assert_eq!(postfix("stats:mean(xs)"), "xs stats:mean");
+}
+
+#[test]
+fn malformed_expressions_are_rejected() {
+    for expr in &[
+        "", "2 +", "(2 + 3", "2 + 3)", "sin(x", "2 $ 3", "f(1,)", "()", "2 3",
+    ] {
+        assert!(infix::translate(expr).is_err(), "{}", expr);
+    }
+}
+
+#[test]
+fn infix_code_runs_inside_definitions() {
+    assert_eq!(stack("1 $(2+3) 4"), stack("1 5 4"));
+    assert_eq!(
+        stack(":: f ( a b -- c ) $( hypot(a, b) * 2.0 ) ; 3.0 4.0 f"),
+        vec![Value::Float64(10.0)]
+    );
+}
+
+#[test]
+fn infix_errors_are_reported_like_postfix_errors() {
+    assert!(matches!(eval("$( 1 + \"a\" )"), Err(EvalError::Type(_, _))));
+    assert!(matches!(eval("$( 2 + )"), Err(EvalError::Syntax(_))));
+    assert!(matches!(eval("$( 2 + 3"), Err(EvalError::BadToken(_))));
+}
@@ -0,0 +1,128 @@
+//! Tests for splitting lines into tokens, and for
+//! where each token is found
+
+use cattywampus::{
+    parser::{self, ParsedToken},
+    value::Value,
+};
+
+// The text and span of each token in a line.
+fn spans(line: &str) -> Vec<(&str, (usize, usize))> {
+    parser::tokenize(line)
+        .map(|token| (token.text, (token.span.start, token.span.end)))
+        .collect()
+}
+
+fn parsed(line: &str) -> Vec<ParsedToken> {
+    parser::parse_line(line).map(|(_, parsed)| parsed).collect()
+}
+
+#[test]
+fn tokens_record_their_spans() {
+    assert_eq!(
+        spans("  0xff  0b101 0o17 -0x10"),
+        vec![
+            ("0xff", (2, 6)),
+            ("0b101", (8, 13)),
+            ("0o17", (14, 18)),
+            ("-0x10", (19, 24)),
+        ]
+    );
+
+    // Strings and compound literals may hold spaces,
+    // and a ``\`` begins a comment.
+    assert_eq!(
+        spans("1 \"a b\" {1,\"c d\"} x! \\ comment"),
+        vec![
+            ("1", (0, 1)),
+            ("\"a b\"", (2, 7)),
+            ("{1,\"c d\"}", (8, 17)),
+            ("x!", (18, 20)),
+        ]
+    );
+
+    // Spans are in bytes.
+    assert_eq!(
+        spans("\"héllo\" 1"),
+        vec![("\"héllo\"", (0, 8)), ("1", (9, 10))]
+    );
+}
+
+#[test]
+fn radixes_are_recognized() {
+    assert_eq!(
+        parsed("0xff 0b101 0o17 -0x10 0XFF"),
+        vec![
+            ParsedToken::Literal(Value::Int32(255)),
+            ParsedToken::Literal(Value::Int32(5)),
+            ParsedToken::Literal(Value::Int32(15)),
+            ParsedToken::Literal(Value::Int32(-16)),
+            ParsedToken::BadToken,
+        ]
+    );
+
+    for token in &["0b102", "0o8", "0x", "0b", "0xg"] {
+        assert_eq!(parsed(token), vec![ParsedToken::BadToken], "{}", token);
+    }
+}
+
+#[test]
+fn structural_tokens_are_recognized() {
+    assert_eq!(
+        parsed(":: f ( a -- b ) [ a ] ;"),
+        vec![
+            ParsedToken::StartLocalsDefinition,
+            ParsedToken::Word("f".to_string()),
+            ParsedToken::StartEffect,
+            ParsedToken::Word("a".to_string()),
+            ParsedToken::BadToken,
+            ParsedToken::Word("b".to_string()),
+            ParsedToken::EndEffect,
+            ParsedToken::StartQuotation,
+            ParsedToken::Word("a".to_string()),
+            ParsedToken::EndQuotation,
+            ParsedToken::EndDefinition,
+        ]
+    );
+
+    assert_eq!(
+        parsed("x! x@ stats:mean"),
+        vec![
+            ParsedToken::Store("x".to_string()),
+            ParsedToken::Recall("x".to_string()),
+            ParsedToken::Qualified("stats".to_string(), "mean".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn infix_expressions_run_to_their_closing_parenthesis() {
+    assert_eq!(
+        spans("$( 1 + (2 * 3) ) y@"),
+        vec![("$( 1 + (2 * 3) )", (0, 16)), ("y@", (17, 19))]
+    );
+    assert_eq!(
+        parsed("$( 1 + (2 * 3) )"),
+        vec![ParsedToken::Infix(" 1 + (2 * 3) ".to_string())]
+    );
+    assert_eq!(parsed("$( 1 + 2"), vec![ParsedToken::BadToken]);
+}
+
+#[test]
+fn unclosed_strings_and_bad_names_are_bad_tokens() {
+    assert_eq!(parsed("\"open"), vec![ParsedToken::BadToken]);
+    assert_eq!(parsed("héllo"), vec![ParsedToken::BadToken]);
+
+    // Strings have no escapes, so cannot hold quotes.
+    assert_eq!(parsed("\"a\"b\""), vec![ParsedToken::BadToken]);
+}
+
+#[test]
+fn comments_are_whole_tokens() {
+    assert_eq!(spans("1 \\ 2"), vec![("1", (0, 1))]);
+    assert_eq!(spans("1\\ 2"), vec![("1\\", (0, 2)), ("2", (3, 4))]);
+    assert_eq!(
+        spans("\"a \\ b\" 3"),
+        vec![("\"a \\ b\"", (0, 7)), ("3", (8, 9))]
+    );
+}
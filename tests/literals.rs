@@ -0,0 +1,161 @@
+//! Tests for the forms of number literals
+
+use cattywampus::{
+    parser::{self, ParsedToken},
+    value::Value,
+};
+use num_complex::Complex64;
+
+fn parse(token: &str) -> ParsedToken {
+    let mut tokens = parser::parse_line(token);
+    let (_, parsed) = tokens.next().expect("a token");
+    assert!(tokens.next().is_none(), "{}: more than one token", token);
+    parsed
+}
+
+fn literal(token: &str) -> Value {
+    match parse(token) {
+        ParsedToken::Literal(val) => val,
+        other => panic!("{}: expected a literal but got {:?}", token, other),
+    }
+}
+
+fn assert_bad(tokens: &[&str]) {
+    for token in tokens {
+        assert_eq!(parse(token), ParsedToken::BadToken, "{}", token);
+    }
+}
+
+#[test]
+fn separators_go_between_digits() {
+    assert_eq!(literal("1_000_000"), Value::Int32(1_000_000));
+    assert_eq!(literal("0x7fff_ffff"), Value::Int32(i32::MAX));
+    assert_eq!(literal("1_000.000_1"), Value::Float64(1000.0001));
+    assert_eq!(literal("1e1_0"), Value::Float64(1e10));
+
+    assert_bad(&["1__0", "1_", "1_.5", "1._5", "0x_1", "1e_5"]);
+}
+
+#[test]
+fn integers_must_fit_in_an_int32() {
+    assert_eq!(literal("2147483647"), Value::Int32(i32::MAX));
+    assert_eq!(literal("-2147483648"), Value::Int32(i32::MIN));
+    assert_eq!(literal("-0x8000_0000"), Value::Int32(i32::MIN));
+
+    assert_bad(&["2147483648", "-2147483649", "99999999999"]);
+}
+
+#[test]
+fn other_radixes_may_give_every_bit() {
+    assert_eq!(literal("0x8000_0000"), Value::Int32(i32::MIN));
+    assert_eq!(literal("0xffff_ffff"), Value::Int32(-1));
+    assert_eq!(literal("0o37777777777"), Value::Int32(-1));
+    assert_eq!(
+        literal(&format!("0b1{}", "0".repeat(31))),
+        Value::Int32(i32::MIN)
+    );
+
+    // A minus sign asks for the number itself, which
+    // must fit.
+    assert_bad(&["0x1_0000_0000", "-0x8000_0001", "-0xffff_ffff"]);
+}
+
+#[test]
+fn floats_must_be_finite_unless_written_as_inf() {
+    assert_eq!(literal("1.7976931348623157e308"), Value::Float64(f64::MAX));
+    assert_eq!(literal("3.4e38f"), Value::Float32(3.4e38));
+    assert_eq!(literal("-inf"), Value::Float64(f64::NEG_INFINITY));
+    assert_eq!(literal("inff"), Value::Float32(f32::INFINITY));
+
+    // Underflow to zero is allowed, as the value is
+    // still the nearest one.
+    assert_eq!(literal("1e-400"), Value::Float64(0.0));
+
+    assert_bad(&["1e999", "-1e999", "1e39f"]);
+}
+
+#[test]
+fn decimal_points_and_exponents_make_floats() {
+    assert_eq!(literal("-.5"), Value::Float64(-0.5));
+    assert_eq!(literal("1."), Value::Float64(1.0));
+    assert_eq!(literal("1E-3"), Value::Float64(1e-3));
+    assert_eq!(literal("3f"), Value::Float32(3.0));
+    assert_eq!(literal(".5e-3f"), Value::Float32(0.5e-3));
+
+    assert_bad(&["1e", "1e+", "1.5.3", ".", "-.", "1ff", "0x1.5"]);
+}
+
+#[test]
+fn nan_is_a_literal() {
+    assert!(matches!(literal("nan"), Value::Float64(x) if x.is_nan()));
+    assert!(matches!(literal("nanf"), Value::Float32(x) if x.is_nan()));
+}
+
+#[test]
+fn collections_take_any_number_literal() {
+    assert_eq!(
+        literal("{1e2,0x1_0,.5f}"),
+        Value::List(vec![
+            Value::Float64(100.0),
+            Value::Int32(16),
+            Value::Float32(0.5),
+        ])
+    );
+    assert_eq!(literal("[1e3,0x10;1_0,.5]").to_string(), "[1000,16;10,0.5]");
+    assert_bad(&["{1__0}", "[1e999]", "[1,3m]", "[1/2,90min]"]);
+}
+
+#[test]
+fn fractions_are_kept_in_lowest_terms() {
+    assert_eq!(literal("2/4").to_string(), "1/2");
+    assert_eq!(literal("-3/6").to_string(), "-1/2");
+    assert_eq!(literal("4/2").to_string(), "2/1");
+    assert_eq!(literal("0/5").to_string(), "0/1");
+    assert_eq!(
+        literal("100_000_000_000_000_000_000/3").to_string(),
+        "100000000000000000000/3"
+    );
+
+    assert_bad(&["1/0", "1/-2", "1.5/2", "1/2/3", "1/_2"]);
+}
+
+#[test]
+fn complex_numbers_take_a_decimal_imaginary_part() {
+    assert_eq!(literal("1-2i"), Value::Complex(Complex64::new(1.0, -2.0)));
+    assert_eq!(literal("2.5i"), Value::Complex(Complex64::new(0.0, 2.5)));
+    assert_eq!(
+        literal("-1e-3i"),
+        Value::Complex(Complex64::new(0.0, -1e-3))
+    );
+    assert_eq!(
+        literal("1e3+1e-3i"),
+        Value::Complex(Complex64::new(1e3, 1e-3))
+    );
+    assert_eq!(literal("-0.5+0i").to_string(), "-0.5+0i");
+
+    // Names ending in ``i`` are not numbers.
+    assert_eq!(parse("pi"), ParsedToken::Word("pi".into()));
+    assert_bad(&["1fi", "1e400i", "1+-2i", "1+2fi"]);
+}
+
+#[test]
+fn suffixes_give_integers_their_type() {
+    assert_eq!(literal("255u8"), Value::UInt8(255));
+    assert_eq!(literal("-128i8"), Value::Int8(-128));
+    assert_eq!(literal("1_000i16"), Value::Int16(1000));
+    assert_eq!(literal("7i32"), Value::Int32(7));
+    assert_eq!(literal("-1i64"), Value::Int64(-1));
+    assert_eq!(literal("65535u16"), Value::UInt16(u16::MAX));
+    assert_eq!(literal("0xffff_ffffu32"), Value::UInt32(u32::MAX));
+    assert_eq!(literal("18446744073709551615u64"), Value::UInt64(u64::MAX));
+    assert_eq!(
+        literal("-123456789012345678901234567890n"),
+        Value::BigInt("-123456789012345678901234567890".parse().unwrap())
+    );
+
+    // Other radixes give every bit of the type.
+    assert_eq!(literal("0xffi8"), Value::Int8(-1));
+    assert_eq!(literal("0x8000i16"), Value::Int16(i16::MIN));
+
+    assert_bad(&["256u8", "-1u8", "128i8", "0x100i8", "-0xffi8", "1_u8"]);
+}
@@ -0,0 +1,69 @@
+//! Tests for map literals and the map builtins
+
+mod common;
+
+use cattywampus::{
+    parser::{self, ParsedToken},
+    typecheck::TypeError,
+    value::Value,
+};
+use common::{error, top};
+
+fn shown(program: &str) -> String {
+    top(program).to_string()
+}
+
+#[test]
+fn entries_are_sorted_and_later_keys_win() {
+    assert_eq!(shown("#{'b:1,'a:2}"), "#{'a:2,'b:1}");
+    assert_eq!(shown("#{'a:1,'a:2}"), "#{'a:2}");
+    assert_eq!(top("#{'a:1,'b:2}"), top("#{'b:2,'a:1}"));
+}
+
+#[test]
+fn strings_and_symbols_are_different_keys() {
+    assert_eq!(shown("#{\"x\":1,'x:2}"), "#{\"x\":1,'x:2}");
+    assert_eq!(top("#{'a:1} \"a\" has?"), Value::Bool(false));
+    assert!(matches!(error("#{\"a\":1} 'a get"), TypeError::MissingKey));
+}
+
+#[test]
+fn empty_maps_have_no_keys() {
+    assert_eq!(shown("#{}"), "#{}");
+    assert_eq!(shown("#{} keys"), "{}");
+    assert_eq!(shown("#{} 'a remove"), "#{}");
+    assert!(matches!(error("#{} 'a get"), TypeError::MissingKey));
+}
+
+#[test]
+fn values_may_be_nested_collections() {
+    let program = "#{'a:{1,2},'b:#{'c:3}}";
+    assert_eq!(shown(program), program);
+    assert_eq!(shown(&format!("{} 'b get 'c get", program)), "3");
+    assert_eq!(shown("#{'b:#{},'a:{}} values"), "{{},#{}}");
+}
+
+#[test]
+fn put_replaces_existing_entries() {
+    assert_eq!(shown("#{'a:1} 'a 5 put"), "#{'a:5}");
+    assert_eq!(top("#{'a:1} 'a 5.0 put 'a get"), Value::Float64(5.0));
+    assert_ne!(top("#{'a:1}"), top("#{'a:1.0}"));
+}
+
+#[test]
+fn keys_must_be_strings_or_symbols() {
+    for program in &["#{'a:1} 1 get", "#{'a:1} 2.5 1 put", "#{'a:1} {'a} has?"] {
+        assert!(
+            matches!(error(program), TypeError::TypeMismatch),
+            "{}",
+            program
+        );
+    }
+
+    for tok in &["#{1:2}", "#{'a}", "#{'a:1:2}", "#{'a:1,}"] {
+        let parsed = parser::parse_line(tok)
+            .map(|(_, parsed)| parsed)
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, vec![ParsedToken::BadToken], "{}", tok);
+    }
+}
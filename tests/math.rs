@@ -0,0 +1,126 @@
+//! Tests for the elementary math builtins
+
+mod common;
+
+use cattywampus::{typecheck::TypeError, value::Value};
+use common::{close, error, float, top};
+use std::f64::consts::FRAC_PI_4;
+
+fn shown(program: &str) -> String {
+    top(program).to_string()
+}
+
+#[test]
+fn hypot_does_not_overflow() {
+    assert_eq!(float("3.0 4.0 hypot"), 5.0);
+    assert_eq!(float("1e300 1e300 hypot"), 1.4142135623730951e300);
+    assert_eq!(top("3.0f 4.0f hypot"), Value::Float32(5.0));
+    assert_eq!(shown("3.0 m 400.0 cm hypot"), "5 m");
+
+    assert!(matches!(error("3 4 hypot"), TypeError::TypeMismatch));
+    assert!(matches!(
+        error("1.0 m 2.0 hypot"),
+        TypeError::DimensionMismatch
+    ));
+}
+
+#[test]
+fn halves_round_away_from_zero() {
+    assert_eq!(float("2.5 round"), 3.0);
+    assert_eq!(float("-2.5 round"), -3.0);
+    assert_eq!(float("-2.5 floor"), -3.0);
+    assert_eq!(float("-2.5 ceil"), -2.0);
+    assert_eq!(float("-2.5 trunc"), -2.0);
+    assert_eq!(shown("2.5 km round"), "3 km");
+}
+
+#[test]
+fn integers_are_already_whole() {
+    assert_eq!(top("7 floor"), Value::Int32(7));
+    assert_eq!(top("-7 round"), Value::Int32(-7));
+    assert_eq!(top("7 fract"), Value::Int32(0));
+}
+
+#[test]
+fn fractional_parts_keep_the_sign() {
+    assert_eq!(float("-2.25 fract"), -0.25);
+    assert_eq!(float("-2.25 trunc -2.25 fract +"), -2.25);
+}
+
+#[test]
+fn signs_of_zero_and_nan() {
+    assert_eq!(top("-7 signum"), Value::Int32(-1));
+    assert_eq!(top("0 signum"), Value::Int32(0));
+    assert_eq!(float("-0.0 signum"), 0.0);
+    assert!(float("nan signum").is_nan());
+    assert_eq!(top("-0.5f signum"), Value::Float32(-1.0));
+    assert_eq!(top("-3.0 s signum"), Value::Float64(-1.0));
+}
+
+#[test]
+fn abs_reports_overflow() {
+    assert_eq!(top("-2147483647 abs"), Value::Int32(i32::MAX));
+    assert!(matches!(error("-2147483648 abs"), TypeError::Overflow));
+    assert_eq!(shown("-2.0 km abs"), "2 km");
+}
+
+#[test]
+fn comparisons_skip_nan() {
+    assert_eq!(top("3 5 min"), Value::Int32(3));
+    assert_eq!(top("3 5 max"), Value::Int32(5));
+    assert_eq!(float("1.5 nan min"), 1.5);
+    assert_eq!(float("nan 1.5 max"), 1.5);
+    assert_eq!(shown("1.0 km 900.0 m min"), "900 m");
+
+    assert!(matches!(error("1 2.0 min"), TypeError::TypeMismatch));
+    assert!(matches!(
+        error("1.0 m 2.0 s max"),
+        TypeError::DimensionMismatch
+    ));
+}
+
+#[test]
+fn clamp_needs_a_range() {
+    assert_eq!(top("12 0 10 clamp"), Value::Int32(10));
+    assert_eq!(top("5 5 5 clamp"), Value::Int32(5));
+    assert_eq!(float("-1.0 0.0 1.0 clamp"), 0.0);
+    assert_eq!(shown("5.0 m 1.0 m 200.0 cm clamp"), "2 m");
+
+    assert!(matches!(error("1 5 0 clamp"), TypeError::DomainError));
+    assert!(matches!(error("1.0 nan 0.0 clamp"), TypeError::DomainError));
+}
+
+#[test]
+fn logb_gives_the_true_exponent() {
+    assert_eq!(float("12.0 logb"), 3.0);
+    assert_eq!(float("-0.3 logb"), -2.0);
+    assert_eq!(float("5e-324 logb"), -1074.0);
+    assert_eq!(float("0.0 logb"), f64::NEG_INFINITY);
+    assert_eq!(float("1000.0 log10"), 3.0);
+    assert_eq!(float("0.125 log2"), -3.0);
+}
+
+#[test]
+fn small_arguments_keep_their_precision() {
+    assert_eq!(float("1e-20 expm1"), 1e-20);
+    assert_eq!(float("1e-20 ln1p"), 1e-20);
+    assert_eq!(float("1e-20 sinh"), 1e-20);
+    assert_eq!(float("1e-20 atanh"), 1e-20);
+}
+
+#[test]
+fn inverse_hyperbolics_have_domains() {
+    assert_eq!(float("1.0 acosh"), 0.0);
+    assert!(float("0.5 acosh").is_nan());
+    assert_eq!(float("1.0 atanh"), f64::INFINITY);
+    assert!(float("2.0 atanh").is_nan());
+}
+
+#[test]
+fn atan2_finds_the_quadrant() {
+    assert!(close(float("1.0 1.0 atan2"), FRAC_PI_4, 1e-15));
+    assert!(close(float("1.0 -1.0 atan2"), 3.0 * FRAC_PI_4, 1e-15));
+    assert!(close(float("-1.0 -1.0 atan2"), -3.0 * FRAC_PI_4, 1e-15));
+    assert_eq!(float("deg 1.0 0.0 atan2"), 90.0);
+    assert!(matches!(error("1.0 2.0f atan2"), TypeError::TypeMismatch));
+}
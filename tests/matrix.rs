@@ -0,0 +1,266 @@
+//! Tests for matrix literals and the linear algebra
+//! builtins
+
+mod common;
+
+use cattywampus::{
+    matrix::Matrix,
+    parser::{self, ParsedToken},
+    typecheck::TypeError,
+    value::Value,
+};
+use common::{close, error, float, stack, top};
+
+// The matrix that a program leaves on top of the
+// stack.
+fn matrix(program: &str) -> Matrix {
+    match top(program) {
+        Value::Matrix(m) => m,
+        other => panic!("{}: expected a Matrix but got {:?}", program, other),
+    }
+}
+
+// The entries of a ``Float64`` matrix, row by row.
+fn entries(m: &Matrix) -> Vec<f64> {
+    let m = match m {
+        Matrix::Float64(m) => m,
+        other => panic!("expected Float64 entries but got {:?}", other),
+    };
+
+    (0..m.rows())
+        .flat_map(|i| (0..m.cols()).map(move |j| (i, j)))
+        .map(|idx| m[idx])
+        .collect()
+}
+
+// Every matrix that a program leaves, from the bottom
+// of the stack up.
+fn matrices(program: &str) -> Vec<Matrix> {
+    stack(program)
+        .into_iter()
+        .map(|val| match val {
+            Value::Matrix(m) => m,
+            other => panic!("{}: expected a Matrix but got {:?}", program, other),
+        })
+        .collect()
+}
+
+fn assert_close(program: &str, found: &Matrix, expected: &[f64]) {
+    let found = entries(found);
+    let matching = found.len() == expected.len()
+        && found
+            .iter()
+            .zip(expected)
+            .all(|(x, y)| close(*x, *y, 1e-12));
+
+    assert!(
+        matching,
+        "{}: expected {:?} but got {:?}",
+        program, expected, found
+    );
+}
+
+fn assert_entries(program: &str, expected: &[f64]) {
+    assert_close(program, &matrix(program), expected);
+}
+
+#[test]
+fn literals_need_rectangular_rows() {
+    assert_eq!(matrix("[1,2,3;4,5,6]").rows(), 2);
+    assert_eq!(matrix("[1,2,3;4,5,6]").cols(), 3);
+    assert_eq!(matrix("[7]").to_string(), "[7]");
+
+    for tok in &[
+        "[1,2;3]", "[1,2;]", "[1,,2]", "[a,b]", "[;]", "[1/0]", "[1fi]",
+    ] {
+        let parsed = parser::parse_line(tok)
+            .map(|(_, parsed)| parsed)
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, vec![ParsedToken::BadToken], "{}", tok);
+    }
+}
+
+#[test]
+fn products_follow_the_inner_dimension() {
+    assert_eq!(top("[1,2,3] [4;5;6] mmul").to_string(), "[32]");
+    assert_eq!(
+        top("[4;5;6] [1,2,3] mmul").to_string(),
+        "[4,8,12;5,10,15;6,12,18]"
+    );
+    assert_eq!(top("[1,2;3,4] 2 identity mmul").to_string(), "[1,2;3,4]");
+    assert!(matches!(
+        error("[1,2;3,4] [1,2,3] mmul"),
+        TypeError::ShapeMismatch
+    ));
+}
+
+#[test]
+fn identity_orders_are_bounded_before_allocating() {
+    assert_entries("1 identity", &[1.0]);
+    assert_eq!(matrix("1024 identity").rows(), 1024);
+
+    for program in &[
+        "0 identity",
+        "-1 identity",
+        "1025 identity",
+        "2147483647 identity",
+    ] {
+        assert!(
+            matches!(error(program), TypeError::DomainError),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn square_only_words_reject_other_shapes() {
+    for program in &[
+        "[1,2,3] det",
+        "[1,2,3] inv",
+        "[1,2,3] trace",
+        "[1,2,3] lu",
+        "[1;2] eigen",
+    ] {
+        assert!(
+            matches!(error(program), TypeError::ShapeMismatch),
+            "{}",
+            program
+        );
+    }
+
+    assert!(matches!(
+        error("[1,2;3,4] [1;2;3] solve"),
+        TypeError::ShapeMismatch
+    ));
+}
+
+#[test]
+fn singular_matrices_have_no_inverse() {
+    assert_eq!(float("[1,2;2,4] det"), 0.0);
+    assert_eq!(top("[1,2;2,4] rank"), Value::Int32(1));
+    assert_eq!(top("[0,0;0,0] rank"), Value::Int32(0));
+
+    for program in &["[1,2;2,4] inv", "[0,0;0,0] inv", "[1,2;2,4] [1;2] solve"] {
+        assert!(
+            matches!(error(program), TypeError::DomainError),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn systems_are_solved() {
+    assert_entries("[1,2;3,4] inv", &[-2.0, 1.0, 1.5, -0.5]);
+    assert_entries("[1,2;3,4] [5;6] solve", &[-4.0, 4.5]);
+    assert!(close(float("[1,2;3,4] det"), -2.0, 1e-12));
+
+    // Pivoting handles a zero in the first position.
+    assert_entries("[0,1;1,0] [2;3] solve", &[3.0, 2.0]);
+}
+
+#[test]
+fn lu_factors_reproduce_the_permuted_matrix() {
+    let factors = matrices("[0,2,1;1,1,1;2,1,0] lu");
+
+    let (l, u, p) = (&factors[0], &factors[1], &factors[2]);
+    let pa = p.mul(&matrix("[0,2,1;1,1,1;2,1,0]")).unwrap();
+    let lu = l.mul(u).unwrap();
+
+    assert!(entries(&pa)
+        .iter()
+        .zip(entries(&lu))
+        .all(|(x, y)| close(*x, y, 1e-12)));
+    assert!((0..3).all(|i| entries(l)[i * 4] == 1.0));
+}
+
+#[test]
+fn eigen_needs_a_symmetric_matrix() {
+    let found = matrices("[2,1;1,2] eigen");
+    assert_close("[2,1;1,2] eigen", &found[0], &[1.0, 3.0]);
+    assert_eq!(matrices("[2,0;0,3] eigen")[1].to_string(), "[1,0;0,1]");
+    assert!(matches!(error("[1,2;3,4] eigen"), TypeError::DomainError));
+}
+
+#[test]
+fn entries_decide_the_type_of_a_literal() {
+    assert!(matches!(matrix("[1,2]"), Matrix::Float64(_)));
+    assert!(matches!(matrix("[1,1/2]"), Matrix::Rational(_)));
+    assert!(matches!(matrix("[1/2,0.5]"), Matrix::Float64(_)));
+    assert!(matches!(matrix("[1/2,0.5;1,2i]"), Matrix::Complex(_)));
+
+    assert_eq!(matrix("[1,2/4]").to_string(), "[1/1,1/2]");
+    assert_eq!(matrix("[1/2,0.5]").to_string(), "[0.5,0.5]");
+    assert_eq!(
+        matrix("[1,-2i;2.5-1e-3i,-3i]").to_string(),
+        "[1+0i,0-2i;2.5-0.001i,0-3i]"
+    );
+}
+
+#[test]
+fn matrices_read_back_as_shown() {
+    for program in &["[0.1,-2e30;3,4]", "[-1/3,7/1]", "[1+2i,-0.5-0i]", "[]"] {
+        let m = top(program);
+        assert_eq!(top(&m.to_string()), m, "{}", program);
+    }
+}
+
+#[test]
+fn rationals_stay_exact() {
+    assert_eq!(
+        top("[1/1,1/2,1/3;1/2,1/3,1/4;1/3,1/4,1/5] inv").to_string(),
+        "[9/1,-36/1,30/1;-36/1,192/1,-180/1;30/1,-180/1,180/1]"
+    );
+    assert_eq!(top("[1/2,1/3;1/4,1/5] det").to_string(), "1/60");
+    assert_eq!(top("[1/2,1/3;1/4,1/5] trace").to_string(), "7/10");
+    assert_eq!(top("[1/3,0;0,1] [1/1;1] solve").to_string(), "[3/1;1/1]");
+
+    // No tolerance is needed to find that these are
+    // singular.
+    assert_eq!(top("[1/3,1/6;2/1,1/1] rank"), Value::Int32(1));
+    assert!(matches!(
+        error("[1/3,1/6;2/1,1/1] inv"),
+        TypeError::DomainError
+    ));
+}
+
+#[test]
+fn mixed_entries_give_the_less_exact_type() {
+    assert_eq!(top("[1/2] [2/1] mmul").to_string(), "[1/1]");
+    assert_eq!(top("[1/2] [2] mmul").to_string(), "[1]");
+    assert_eq!(top("[1,2] [1i;1] mmul").to_string(), "[2+1i]");
+    assert_eq!(top("[1/2,0;0,1] [1i;1] solve").to_string(), "[0+2i;1+0i]");
+}
+
+#[test]
+fn complex_matrices_multiply_and_solve() {
+    assert_eq!(top("[0,1i;1i,0] [1;1i] mmul").to_string(), "[-1+0i;0+1i]");
+    assert_eq!(top("[1i,0;0,2] [1;4] solve").to_string(), "[0-1i;2+0i]");
+    assert_eq!(top("[1i,0;0,1i] det").to_string(), "-1+0i");
+    assert_eq!(top("[1i,0;0,1i] rank"), Value::Int32(2));
+
+    // Only real matrices have a symmetric eigenproblem.
+    assert!(matches!(
+        error("[1i,0;0,1i] eigen"),
+        TypeError::TypeMismatch
+    ));
+    let program = "[2/1,0;0,1/2] eigen";
+    assert_close(program, &matrices(program)[0], &[0.5, 2.0]);
+}
+
+#[test]
+fn the_empty_matrix_has_no_rows_or_columns() {
+    let empty = matrix("[]");
+    assert_eq!((empty.rows(), empty.cols()), (0, 0));
+    assert_eq!(empty.to_string(), "[]");
+
+    assert_eq!(float("[] det"), 1.0);
+    assert_eq!(float("[] trace"), 0.0);
+    assert_eq!(top("[] rank"), Value::Int32(0));
+    for program in &["[] inv", "[] transpose", "[] [] mmul", "[] [] solve"] {
+        assert_eq!(top(program).to_string(), "[]", "{}", program);
+    }
+
+    assert!(matches!(error("[1,2] [] mmul"), TypeError::ShapeMismatch));
+}
@@ -0,0 +1,257 @@
+//! Tests for the number theory builtins
+
+mod common;
+
+use cattywampus::{typecheck::TypeError, value::Value};
+use common::{error, stack, top};
+
+fn int(program: &str) -> i32 {
+    match top(program) {
+        Value::Int32(x) => x,
+        other => panic!("{}: expected an Int32 but got {:?}", program, other),
+    }
+}
+
+fn ints(xs: &[i32]) -> Value {
+    Value::List(xs.iter().map(|&x| Value::Int32(x)).collect())
+}
+
+fn big(digits: &str) -> Value {
+    Value::BigInt(digits.parse().unwrap())
+}
+
+#[test]
+fn gcd_and_lcm_ignore_signs() {
+    assert_eq!(int("-12 18 gcd"), 6);
+    assert_eq!(int("12 -18 gcd"), 6);
+    assert_eq!(int("0 0 gcd"), 0);
+    assert_eq!(int("0 -5 gcd"), 5);
+    assert_eq!(int("-4 6 lcm"), 12);
+    assert_eq!(int("0 6 lcm"), 0);
+}
+
+#[test]
+fn gcd_and_lcm_overflow_at_the_edges() {
+    // |-2147483648| is not an Int32.
+    assert!(matches!(error("-2147483648 0 gcd"), TypeError::Overflow));
+    assert_eq!(int("-2147483648 6 gcd"), 2);
+    assert!(matches!(error("65536 65537 lcm"), TypeError::Overflow));
+    assert_eq!(int("46340 46341 lcm"), 2147441940);
+}
+
+#[test]
+fn primes_up_to_the_largest_int32() {
+    assert_eq!(top("2147483647 prime?"), Value::Bool(true));
+    assert_eq!(top("2147483646 prime?"), Value::Bool(false));
+    // A Carmichael number, which fools the Fermat test
+    assert_eq!(top("561 prime?"), Value::Bool(false));
+
+    for n in &["-7", "0", "1"] {
+        assert_eq!(top(&format!("{} prime?", n)), Value::Bool(false), "{}", n);
+    }
+}
+
+#[test]
+fn next_prime_is_strictly_greater() {
+    assert_eq!(int("13 next-prime"), 17);
+    assert_eq!(int("-5 next-prime"), 2);
+    assert_eq!(int("2147483628 next-prime"), 2147483629);
+    assert!(matches!(
+        error("2147483647 next-prime"),
+        TypeError::Overflow
+    ));
+}
+
+#[test]
+fn factors_are_listed_with_multiplicity() {
+    assert_eq!(top("360 factor"), ints(&[2, 2, 2, 3, 3, 5]));
+    assert_eq!(
+        top("2147483646 factor"),
+        ints(&[2, 3, 3, 7, 11, 31, 151, 331])
+    );
+    assert_eq!(top("2147483647 factor"), ints(&[2147483647]));
+    assert_eq!(top("1 factor"), ints(&[]));
+    assert!(matches!(error("0 factor"), TypeError::DomainError));
+    assert!(matches!(error("-12 factor"), TypeError::DomainError));
+}
+
+#[test]
+fn totient_of_units_and_primes() {
+    assert_eq!(int("1 totient"), 1);
+    assert_eq!(int("36 totient"), 12);
+    assert_eq!(int("2147483647 totient"), 2147483646);
+    assert!(matches!(error("0 totient"), TypeError::DomainError));
+}
+
+#[test]
+fn modular_arithmetic_reduces_into_the_modulus() {
+    assert_eq!(int("4 13 497 modpow"), 445);
+    assert_eq!(int("-2 3 5 modpow"), 2);
+    assert_eq!(int("2 0 1 modpow"), 0);
+    // The intermediate products need more than 32 bits.
+    assert_eq!(int("2147483646 2147483646 2147483647 modpow"), 1);
+
+    assert_eq!(int("3 11 modinv"), 4);
+    assert_eq!(int("-3 11 modinv"), 7);
+
+    assert!(matches!(error("2 -1 5 modpow"), TypeError::DomainError));
+    assert!(matches!(error("2 3 0 modpow"), TypeError::DomainError));
+    assert!(matches!(error("4 6 modinv"), TypeError::DomainError));
+}
+
+#[test]
+fn isqrt_rounds_down_near_perfect_squares() {
+    assert_eq!(int("0 isqrt"), 0);
+    assert_eq!(int("2147395599 isqrt"), 46339);
+    assert_eq!(int("2147395600 isqrt"), 46340);
+    assert_eq!(int("2147483647 isqrt"), 46340);
+    assert!(matches!(error("-1 isqrt"), TypeError::DomainError));
+}
+
+#[test]
+fn divmod_truncates_like_division() {
+    assert_eq!(
+        stack("-7 2 divmod"),
+        vec![Value::Int32(-3), Value::Int32(-1)]
+    );
+    assert_eq!(
+        stack("7 -2 divmod"),
+        vec![Value::Int32(-3), Value::Int32(1)]
+    );
+    assert!(matches!(error("1 0 divmod"), TypeError::DomainError));
+    assert!(matches!(
+        error("-2147483648 -1 divmod"),
+        TypeError::Overflow
+    ));
+}
+
+#[test]
+fn binomials_and_factorials_up_to_the_int32_limit() {
+    assert_eq!(int("10 11 binomial"), 0);
+    assert_eq!(int("33 16 binomial"), 1166803110);
+    assert!(matches!(error("34 17 binomial"), TypeError::Overflow));
+    assert!(matches!(error("-3 2 binomial"), TypeError::DomainError));
+
+    assert_eq!(int("0 factorial"), 1);
+    assert_eq!(int("12 factorial"), 479001600);
+    assert!(matches!(error("13 factorial"), TypeError::Overflow));
+    assert!(matches!(error("-1 factorial"), TypeError::DomainError));
+    assert_eq!(top("4.0 factorial"), Value::Float64(24.0));
+}
+
+#[test]
+fn inc_and_dec_check_for_overflow() {
+    assert_eq!(int("2147483646 inc"), 2147483647);
+    assert!(matches!(error("2147483647 inc"), TypeError::Overflow));
+    assert!(matches!(error("-2147483648 dec"), TypeError::Overflow));
+}
+
+#[test]
+fn every_integer_type_keeps_its_type() {
+    assert_eq!(top("12u8 18u8 gcd"), Value::UInt8(6));
+    assert_eq!(top("-4i64 6i64 lcm"), Value::Int64(12));
+    assert_eq!(top("36i16 totient"), Value::Int16(12));
+    assert_eq!(top("4u16 13u16 497u16 modpow"), Value::UInt16(445));
+    assert_eq!(top("3i8 11i8 modinv"), Value::Int8(4));
+    assert_eq!(top("200u32 isqrt"), Value::UInt32(14));
+    assert_eq!(
+        stack("-7i8 2i8 divmod"),
+        vec![Value::Int8(-3), Value::Int8(-1)]
+    );
+    assert_eq!(
+        top("12u8 factor"),
+        Value::List(vec![Value::UInt8(2), Value::UInt8(2), Value::UInt8(3)])
+    );
+    assert_eq!(top("255u8 prime?"), Value::Bool(false));
+    assert_eq!(top("2u8 7u8 binomial"), Value::UInt8(0));
+    assert_eq!(top("254u8 inc"), Value::UInt8(255));
+
+    assert!(matches!(error("12u8 18 gcd"), TypeError::TypeMismatch));
+    assert!(matches!(error("12 18i64 lcm"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn results_must_fit_the_type() {
+    assert!(matches!(error("251u8 next-prime"), TypeError::Overflow));
+    assert_eq!(top("5u8 factorial"), Value::UInt8(120));
+    assert!(matches!(error("6u8 factorial"), TypeError::Overflow));
+    assert!(matches!(error("-128i8 0i8 gcd"), TypeError::Overflow));
+    assert!(matches!(error("-128i8 -1i8 divmod"), TypeError::Overflow));
+    assert!(matches!(error("255u8 inc"), TypeError::Overflow));
+    assert!(matches!(error("0u64 dec"), TypeError::Overflow));
+    assert!(matches!(error("21u64 factorial"), TypeError::Overflow));
+    assert_eq!(
+        top("20u64 factorial"),
+        Value::UInt64(2_432_902_008_176_640_000)
+    );
+}
+
+#[test]
+fn big_integers_never_overflow() {
+    assert_eq!(
+        top("30n factorial"),
+        big("265252859812191058636308480000000")
+    );
+    assert_eq!(
+        top("100n 50n binomial"),
+        big("100891344545564193334812497256")
+    );
+    assert_eq!(top("2n 100n 1000000007n modpow"), big("976371285"));
+    assert_eq!(top("3n 1000000007n modinv"), big("333333336"));
+    assert_eq!(
+        top("100000000000000000000000000000000000000000n isqrt"),
+        big("316227766016837933199")
+    );
+    assert_eq!(
+        top("18446744073709551615n next-prime"),
+        big("18446744073709551629")
+    );
+    // The largest Mersenne prime below 2^128
+    assert_eq!(
+        top("170141183460469231731687303715884105727n prime?"),
+        Value::Bool(true)
+    );
+    assert_eq!(
+        top("170141183460469231731687303715884105729n prime?"),
+        Value::Bool(false)
+    );
+}
+
+#[test]
+fn large_factors_are_found_without_trial_division() {
+    let factors = |xs: &[u64]| Value::List(xs.iter().map(|&x| Value::UInt64(x)).collect());
+
+    assert_eq!(
+        top("1000000016000000063u64 factor"),
+        factors(&[1_000_000_007, 1_000_000_009])
+    );
+    assert_eq!(
+        top("18446744073709551615u64 factor"),
+        factors(&[3, 5, 17, 257, 641, 65537, 6700417])
+    );
+    assert_eq!(
+        top("1000000016000000063n 1000000007n * factor"),
+        Value::List(vec![
+            big("1000000007"),
+            big("1000000007"),
+            big("1000000009")
+        ])
+    );
+}
+
+#[test]
+fn arithmetic_works_on_every_integer_type() {
+    assert_eq!(top("200u8 55u8 +"), Value::UInt8(255));
+    assert_eq!(top("-7i16 2i16 /"), Value::Int16(-3));
+    assert_eq!(top("-5i64 abs"), Value::Int64(5));
+    assert_eq!(top("3u32 4u32 max"), Value::UInt32(4));
+    assert_eq!(top("2n 100n pow"), big("1267650600228229401496703205376"));
+    assert_eq!(top("{1u8,2u8,3u8} sum"), Value::UInt8(6));
+
+    assert!(matches!(error("200u8 56u8 +"), TypeError::Overflow));
+    assert!(matches!(error("0u8 1u8 -"), TypeError::Overflow));
+    assert!(matches!(error("1u8 negate"), TypeError::Overflow));
+    assert!(matches!(error("1n 0n /"), TypeError::DomainError));
+    assert!(matches!(error("2i64 64i64 pow"), TypeError::Overflow));
+    assert!(matches!(error("1u8 1u16 +"), TypeError::TypeMismatch));
+}
@@ -0,0 +1,260 @@
+//! Tests for the numerical methods
+
+mod common;
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter},
+    numerical::Limits,
+    session,
+    typecheck::TypeError,
+    value::Value,
+};
+use common::{error, eval, float, top};
+use std::f64::consts::{FRAC_PI_2, PI, SQRT_2};
+
+fn assert_near(program: &str, expected: f64, allowed: f64) {
+    let found = float(program);
+    assert!(
+        (found - expected).abs() <= allowed,
+        "{}: expected {} but got {}",
+        program,
+        expected,
+        found
+    );
+}
+
+fn no_convergence(program: &str) -> (String, u32) {
+    match eval(program) {
+        Err(EvalError::NoConvergence(name, iterations)) => (name, iterations),
+        Err(err) => panic!("{}: expected non-convergence but got {}", program, err),
+        Ok(_) => panic!("{}: expected non-convergence", program),
+    }
+}
+
+#[test]
+fn bracketing_methods_agree() {
+    for method in &["bisect", "brent"] {
+        let root = |body: &str, a: &str, b: &str| format!("[ {} ] {} {} {}", body, a, b, method);
+
+        assert_near(&root("sq 2.0 -", "0.0", "2.0"), SQRT_2, 1e-11);
+        assert_near(&root("exp 5.0 -", "0.0", "10.0"), 5.0f64.ln(), 1e-11);
+        // The ends may come in either order.
+        assert_near(&root("cos", "3.0", "0.0"), FRAC_PI_2, 1e-11);
+    }
+}
+
+#[test]
+fn roots_at_the_ends_are_found_exactly() {
+    assert_eq!(float("[ 1.0 - ] 1.0 3.0 bisect"), 1.0);
+    assert_eq!(float("[ 1.0 - ] -2.0 1.0 brent"), 1.0);
+    assert_eq!(float("[ sq 4.0 - ] 2.0 newton"), 2.0);
+}
+
+#[test]
+fn brackets_must_change_sign() {
+    for method in &["bisect", "brent"] {
+        let program = format!("[ sq 1.0 + ] 0.0 2.0 {}", method);
+        assert!(
+            matches!(error(&program), TypeError::DomainError),
+            "{}",
+            program
+        );
+    }
+
+    assert!(matches!(
+        error("[ sqrt ] -1.0 1.0 bisect"),
+        TypeError::DomainError
+    ));
+}
+
+#[test]
+fn newton_follows_the_slope() {
+    assert_near("[ sq 2.0 - ] 1.0 newton", SQRT_2, 1e-11);
+    assert_near("[ x! x x * x * 2.0 - ] 1.0 newton", 2f64.cbrt(), 1e-11);
+
+    // Without a root, it wanders until it runs out.
+    let (name, _) = no_convergence("[ sq 1.0 + ] 1.0 newton");
+    assert_eq!(name, "newton");
+}
+
+#[test]
+fn minima_inside_and_at_the_ends() {
+    // A minimum can only be found to about the square
+    // root of the precision.
+    assert_near("[ 3.0 - sq ] 0.0 10.0 minimize", 3.0, 1e-7);
+    assert_near("[ cos ] 0.0 6.0 minimize", PI, 1e-7);
+    assert_near("[ 1.0 + ] 0.0 1.0 minimize", 0.0, 1e-7);
+}
+
+#[test]
+fn integrals_follow_the_direction_of_their_bounds() {
+    assert_near("[ sq ] 0.0 1.0 integrate", 1.0 / 3.0, 1e-12);
+    assert_near("[ sq ] 1.0 0.0 integrate", -1.0 / 3.0, 1e-12);
+    assert_eq!(float("[ sq ] 2.0 2.0 integrate"), 0.0);
+    assert_near("[ sin ] 0.0 pi integrate", 2.0, 1e-12);
+}
+
+#[test]
+fn integrals_adapt_to_hard_integrands() {
+    // A vertical tangent at one end
+    assert_near("[ sqrt ] 0.0 1.0 integrate", 2.0 / 3.0, 1e-9);
+    // A long, slowly decaying tail
+    assert_near("[ recip ] 1.0 1000.0 integrate", 1000f64.ln(), 1e-9);
+    // A narrow peak in a wide interval
+    assert_near("[ sq neg exp ] -10.0 10.0 integrate", PI.sqrt(), 1e-9);
+}
+
+#[test]
+fn integrals_need_finite_bounds_and_a_finite_answer() {
+    assert!(matches!(
+        error("[ sq ] 0.0 inf integrate"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(
+        error("[ sq ] nan 1.0 integrate"),
+        TypeError::DomainError
+    ));
+
+    let (name, _) = no_convergence("[ recip ] 0.0 1.0 integrate");
+    assert_eq!(name, "integrate");
+}
+
+#[test]
+fn derivatives_at_zero_and_far_from_it() {
+    assert_near("[ sin ] 1.0 derivative", 1f64.cos(), 1e-9);
+    assert_near("[ exp ] 0.0 derivative", 1.0, 1e-9);
+    assert_near("[ sq ] 0.0 derivative", 0.0, 1e-9);
+    assert_near("[ cube ] 2.0 derivative", 12.0, 1e-8);
+    assert_near("[ x! x sqrt x * ] 4.0 derivative", 3.0, 1e-9);
+    // The step scales with x.
+    assert_near("[ sq ] 1e8 derivative", 2e8, 1e-1);
+}
+
+#[test]
+fn float32_gives_float32() {
+    for program in &[
+        "[ sq 2.0f - ] 0.0f 2.0f bisect",
+        "[ sq 2.0f - ] 0.0f 2.0f brent",
+        "[ sq 2.0f - ] 1.0f newton",
+    ] {
+        match top(program) {
+            Value::Float32(x) => assert!((x - std::f32::consts::SQRT_2).abs() < 1e-6),
+            other => panic!("{}: expected a Float32 but got {:?}", program, other),
+        }
+    }
+
+    assert!(matches!(
+        top("[ 3.0f - sq ] 0.0f 10.0f minimize"),
+        Value::Float32(_)
+    ));
+
+    match top("[ sin ] 0.0f 3.0f integrate") {
+        Value::Float32(x) => assert!((x - (1.0 - 3.0f32.cos())).abs() < 1e-5),
+        other => panic!("expected a Float32 but got {:?}", other),
+    }
+
+    match top("[ sin ] 1.0f derivative") {
+        Value::Float32(x) => assert!((x - 1.0f32.cos()).abs() < 1e-4),
+        other => panic!("expected a Float32 but got {:?}", other),
+    }
+}
+
+#[test]
+fn ends_must_be_fractional_and_alike() {
+    assert!(matches!(
+        error("[ 1.0 - ] 0.0 2.0f bisect"),
+        TypeError::TypeMismatch
+    ));
+    assert!(matches!(
+        error("[ 1.0 - ] 0 2 brent"),
+        TypeError::TypeMismatch
+    ));
+    assert!(matches!(
+        error("1.0 0.0 2.0 brent"),
+        TypeError::TypeMismatch
+    ));
+    assert!(matches!(
+        error("[ 1.0 - ] 2.0 brent"),
+        TypeError::WrongArity
+    ));
+}
+
+#[test]
+fn a_coarse_tolerance_stops_early() {
+    let off = (float("1e-3 tolerance [ sq 2.0 - ] 0.0 2.0 bisect") - SQRT_2).abs();
+    assert!(off < 1e-3 && off > 1e-9, "{}", off);
+}
+
+#[test]
+fn running_out_of_iterations_names_the_method() {
+    let (name, iterations) = no_convergence("5 iterations [ sq 2.0 - ] 0.0 2.0 bisect");
+    assert_eq!((name.as_str(), iterations), ("bisect", 5));
+
+    // One iteration is enough when the first guess is
+    // the root.
+    assert_eq!(float("1 iterations [ sq 4.0 - ] 2.0 newton"), 2.0);
+}
+
+#[test]
+fn limits_must_be_positive_and_below_one() {
+    let interp = eval("1e-6 tolerance 20 iterations").unwrap();
+    assert_eq!(
+        interp.limits(),
+        Limits {
+            tolerance: 1e-6,
+            iterations: 20
+        }
+    );
+
+    assert!(matches!(error("0.0 tolerance"), TypeError::DomainError));
+    assert!(matches!(error("1.5 tolerance"), TypeError::DomainError));
+    assert!(matches!(error("nan tolerance"), TypeError::DomainError));
+    assert!(matches!(error("0 iterations"), TypeError::DomainError));
+}
+
+#[test]
+fn failures_leave_the_stack_alone() {
+    let mut interp = Interpreter::new();
+    assert!(interp.eval_line("[ sq 1.0 + ] 0.0 2.0 bisect").is_err());
+    assert_eq!(interp.stack().height(), 3);
+}
+
+#[test]
+fn quotations_must_be_functions_of_one_number() {
+    for program in &[
+        "[ 1.0 ] 0.0 1.0 bisect",
+        "[ + ] 0.0 1.0 brent",
+        "[ \"a\" + ] 1.0 newton",
+        "[ {1} ] 0.0 1.0 minimize",
+        "[ 1.0 + 2.0 ] 0.0 1.0 integrate",
+        "[ \"a\" ] 1.0 derivative",
+    ] {
+        assert!(
+            matches!(eval(program), Err(EvalError::IllTypedFunction(_, _))),
+            "{}",
+            program
+        );
+    }
+
+    // Definitions are checked before they run.
+    assert!(matches!(
+        eval(": bad ( -- x ) [ 1.0 ] 0.0 2.0 brent ;"),
+        Err(EvalError::IllTyped(_, _))
+    ));
+
+    // Quotations that are only known when they run are
+    // checked by what they leave.
+    assert!(matches!(
+        error("[ 1.0 ] f! [ f call ] 0.0 1.0 bisect"),
+        TypeError::BrokenCallee
+    ));
+}
+
+#[test]
+fn sessions_keep_the_limits() {
+    let interp = eval("1e-6 tolerance 20 iterations").unwrap();
+    let source = session::to_source(&interp).unwrap();
+    let loaded = session::from_source(&source).unwrap();
+
+    assert_eq!(loaded.limits(), interp.limits());
+}
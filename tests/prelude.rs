@@ -0,0 +1,95 @@
+//! Tests for the words defined in the prelude
+
+mod common;
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter, PRELUDE_VOCABULARY},
+    typecheck::TypeError,
+    value::Value,
+};
+use common::{close, error, eval, float, top};
+use std::f64::consts;
+
+// The words that the tests below cover.
+const TESTED: &[&str] = &[
+    "pi", "tau", "e", "phi", "sq", "cube", "neg", "half", "avg", "lerp", "dist", "logn",
+];
+
+#[test]
+fn every_prelude_word_is_tested() {
+    for (vocab, def) in Interpreter::new().definitions() {
+        if vocab == PRELUDE_VOCABULARY {
+            assert!(
+                TESTED.contains(&def.name.as_str()),
+                "{} is not tested",
+                def.name
+            );
+        }
+    }
+}
+
+#[test]
+fn constants_are_exact_float64s() {
+    assert_eq!(float("pi"), consts::PI);
+    assert_eq!(float("tau"), 2.0 * consts::PI);
+    assert_eq!(float("e"), consts::E);
+    assert_eq!(float("phi"), (1.0 + 5.0_f64.sqrt()) / 2.0);
+}
+
+#[test]
+fn powers_keep_their_argument_types() {
+    assert_eq!(top("3 sq"), Value::Int32(9));
+    assert_eq!(top("2.0f cube"), Value::Float32(8.0));
+    assert_eq!(top("3.0 m sq").to_string(), "9 m^2");
+    assert!(matches!(error("46341 sq"), TypeError::Overflow));
+}
+
+#[test]
+fn float_words_reject_other_types() {
+    assert_eq!(float("2.5 neg"), -2.5);
+    assert_eq!(float("7.0 half"), 3.5);
+    assert_eq!(float("3.0 8.0 avg"), 5.5);
+
+    for program in &["3 neg", "1.0f half", "\"a\" 1.0 avg"] {
+        assert!(
+            matches!(error(program), TypeError::TypeMismatch),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn lerp_reaches_both_ends() {
+    assert_eq!(float("10.0 20.0 0.0 lerp"), 10.0);
+    assert_eq!(float("10.0 20.0 1.0 lerp"), 20.0);
+    assert_eq!(float("10.0 20.0 0.25 lerp"), 12.5);
+    assert_eq!(float("10.0 20.0 2.0 lerp"), 30.0);
+}
+
+#[test]
+fn distances_and_logarithms() {
+    assert_eq!(float("1.0 1.0 4.0 5.0 dist"), 5.0);
+    assert!(close(float("8.0 2.0 logn"), 3.0, 1e-15));
+    assert!(float("8.0 1.0 logn").is_infinite());
+}
+
+#[test]
+fn prelude_words_can_be_shadowed() {
+    let mut interp = eval(":: sq ( x -- y ) x ;").unwrap();
+    assert_eq!(interp.take_warnings(), vec!["user:sq shadows prelude:sq"]);
+
+    interp.eval_line("4.0 sq 4.0 prelude:sq").unwrap();
+    let stack = interp.stack().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(stack, vec![Value::Float64(4.0), Value::Float64(16.0)]);
+}
+
+#[test]
+fn prelude_can_be_left_out() {
+    let mut interp = Interpreter::without_prelude();
+    assert!(interp.definitions().is_empty());
+    assert!(matches!(
+        interp.eval_line("2.0 sq"),
+        Err(EvalError::UnknownWord(_))
+    ));
+}
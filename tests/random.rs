@@ -0,0 +1,168 @@
+//! Tests for the random number builtins
+
+mod common;
+
+use cattywampus::{
+    interpreter::Interpreter, random::Generator, typecheck::TypeError, value::Value,
+};
+use common::{error, eval, stack};
+
+// Run a program many times in one seeded interpreter,
+// giving the value it leaves each time.
+fn draws(program: &str, count: usize) -> Vec<Value> {
+    let mut interp = eval("12345 seed").unwrap();
+
+    (0..count)
+        .map(|_| {
+            interp.eval_line(program).unwrap();
+            interp.stack_mut().pop().unwrap()
+        })
+        .collect()
+}
+
+fn float(val: &Value) -> f64 {
+    match val {
+        Value::Float64(x) => *x,
+        other => panic!("expected a Float64 but got {:?}", other),
+    }
+}
+
+#[test]
+fn seeds_give_the_same_numbers() {
+    let program = "42 seed random 1 100 randint 0.0 1.0 normal {1,2,3,4,5} shuffle";
+    assert_eq!(stack(program), stack(program));
+    assert_ne!(stack("1 seed random"), stack("2 seed random"));
+}
+
+#[test]
+fn reseeding_matches_the_seed_word() {
+    let mut interp = Interpreter::new();
+    interp.reseed(42);
+    interp.eval_line("random").unwrap();
+    assert_eq!(interp.stack().iter().last(), stack("42 seed random").last());
+
+    // Negative seeds are sign-extended, as --seed reads
+    // them.
+    let mut interp = Interpreter::new();
+    interp.reseed(-1i64 as u64);
+    interp.eval_line("random").unwrap();
+    assert_eq!(interp.stack().iter().last(), stack("-1 seed random").last());
+}
+
+#[test]
+fn seed_leaves_the_stack_alone() {
+    assert_eq!(stack("1.5 7 seed"), vec![Value::Float64(1.5)]);
+    assert!(matches!(error("1.5 seed"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn random_is_in_the_half_open_unit_interval() {
+    for x in draws("random", 1000).iter().map(float) {
+        assert!((0.0..1.0).contains(&x), "{}", x);
+    }
+}
+
+#[test]
+fn randint_includes_both_ends() {
+    let mut seen = [false; 3];
+    for x in draws("-1 1 randint", 100) {
+        match x {
+            Value::Int32(x) if (-1..=1).contains(&x) => seen[(x + 1) as usize] = true,
+            other => panic!("expected an Int32 from -1 to 1 but got {:?}", other),
+        }
+    }
+    assert_eq!(seen, [true; 3]);
+}
+
+#[test]
+fn randint_spans_from_one_value_to_every_int32() {
+    assert_eq!(stack("5 5 randint"), vec![Value::Int32(5)]);
+    assert!(matches!(
+        stack("-2147483648 2147483647 randint").as_slice(),
+        [Value::Int32(_)]
+    ));
+    assert!(matches!(error("2 1 randint"), TypeError::DomainError));
+}
+
+#[test]
+fn normal_numbers_have_the_right_moments() {
+    let xs: Vec<_> = draws("10.0 2.0 normal", 20000).iter().map(float).collect();
+    let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+    let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / xs.len() as f64;
+
+    assert!((mean - 10.0).abs() < 0.05, "mean {}", mean);
+    assert!((var - 4.0).abs() < 0.15, "variance {}", var);
+}
+
+#[test]
+fn normal_spread_must_not_be_negative() {
+    assert_eq!(stack("3.0 0.0 normal"), vec![Value::Float64(3.0)]);
+    assert!(matches!(error("0.0 -1.0 normal"), TypeError::DomainError));
+    assert!(matches!(error("0.0 nan normal"), TypeError::DomainError));
+}
+
+#[test]
+fn shuffles_keep_every_item() {
+    let shuffled = match stack("9 seed {1,2,3,4,5,6,7,8} shuffle").as_slice() {
+        [Value::List(items)] => items.clone(),
+        other => panic!("expected a list but got {:?}", other),
+    };
+
+    let mut sorted: Vec<_> = shuffled
+        .iter()
+        .map(|x| match x {
+            Value::Int32(x) => *x,
+            other => panic!("expected an Int32 but got {:?}", other),
+        })
+        .collect();
+    sorted.sort();
+
+    assert_eq!(sorted, (1..=8).collect::<Vec<_>>());
+}
+
+#[test]
+fn short_lists_shuffle_to_themselves() {
+    assert_eq!(stack("{} shuffle"), vec![Value::List(vec![])]);
+    assert_eq!(
+        stack("{'a} shuffle"),
+        vec![Value::List(vec![Value::Symbol("a".into())])]
+    );
+}
+
+#[test]
+fn choices_come_from_the_list() {
+    for x in draws("{\"a\",\"b\"} choice", 20) {
+        assert!(
+            x == Value::Str("a".into()) || x == Value::Str("b".into()),
+            "{:?}",
+            x
+        );
+    }
+
+    assert!(matches!(error("{} choice"), TypeError::DomainError));
+}
+
+#[test]
+fn zero_input_builtins_work_in_words() {
+    let mut interp = eval(": coin ( -- x:Float64 ) random ;").unwrap();
+    interp.eval_line("coin").unwrap();
+
+    assert!(matches!(
+        interp.stack().iter().last(),
+        Some(Value::Float64(_))
+    ));
+}
+
+#[test]
+fn generators_are_uniform_over_small_ranges() {
+    let mut generator = Generator::new(0);
+    let mut counts = [0i32; 6];
+
+    for _ in 0..60000 {
+        counts[generator.below(6) as usize] += 1;
+    }
+
+    for count in &counts {
+        assert!((*count - 10000).abs() < 400, "{:?}", counts);
+    }
+}
@@ -0,0 +1,118 @@
+//! Tests for running programs that span many lines
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter},
+    script::{self, PendingLines, ScriptError},
+    value::Value,
+};
+
+fn run(source: &str) -> Result<Interpreter, ScriptError> {
+    let mut interp = Interpreter::new();
+    script::eval_source(&mut interp, source)?;
+    Ok(interp)
+}
+
+fn stack(source: &str) -> Vec<Value> {
+    let interp = run(source).unwrap_or_else(|err| panic!("{:?}: {}", source, err));
+    interp.stack().iter().cloned().collect()
+}
+
+// The line that a failing program reports.
+fn failing_line(source: &str) -> usize {
+    match run(source) {
+        Err(err) => err.line,
+        Ok(_) => panic!("{:?}: expected an error", source),
+    }
+}
+
+#[test]
+fn definitions_and_quotations_span_lines() {
+    let source = ": twice\n  2 *\n;\n[ 1\n  + ] q!\n20 twice q@ call\n";
+    assert_eq!(stack(source), vec![Value::Int32(41)]);
+}
+
+#[test]
+fn backslashes_start_line_comments() {
+    assert_eq!(
+        stack("1 \\ 2 3\n4\n"),
+        vec![Value::Int32(1), Value::Int32(4)]
+    );
+    assert_eq!(stack("\\ : open [\n5\n"), vec![Value::Int32(5)]);
+    assert_eq!(stack("\"a\\b\""), vec![Value::Str("a\\b".to_string())]);
+}
+
+#[test]
+fn parentheses_hold_comments_outside_effects() {
+    assert_eq!(stack("1 ( 2 3 ) 4"), vec![Value::Int32(1), Value::Int32(4)]);
+    assert_eq!(
+        stack(": f ( x -- y ) ( add one ) 1 + ;\n2 f"),
+        vec![Value::Int32(3)]
+    );
+}
+
+#[test]
+fn comments_do_not_open_anything() {
+    // A ``:`` or ``[`` in a comment would otherwise hold
+    // back the lines that follow until the file ends.
+    assert_eq!(failing_line("( a : b [ c )\n1\nnope\n2\n"), 3);
+    assert_eq!(failing_line("1 ( see :: and [ ) 2\n3\nnope\n"), 3);
+
+    // A comment that is left open continues onto the next
+    // lines.
+    assert_eq!(stack("( a\n  b ) 5\n"), vec![Value::Int32(5)]);
+}
+
+#[test]
+fn empty_sources_do_nothing() {
+    assert!(stack("").is_empty());
+    assert!(stack("\n\n  \n\\ only a comment\n").is_empty());
+}
+
+#[test]
+fn errors_report_their_line() {
+    assert_eq!(failing_line("1 2 +\n\"a\" 1 +\n"), 2);
+    assert_eq!(failing_line("1\n\n\nnope\n"), 4);
+
+    // Errors in code that spans lines report the line it
+    // begins on.
+    assert_eq!(failing_line("1\n: bad\n  \"a\" 1 +\n;\n"), 2);
+}
+
+#[test]
+fn unclosed_code_is_an_error() {
+    match run("1\n: open\n  2\n") {
+        Err(ScriptError {
+            line: 2,
+            error: EvalError::Syntax(_),
+        }) => {}
+        other => panic!(
+            "expected a syntax error on line 2 but got {:?}",
+            other.err()
+        ),
+    }
+}
+
+#[test]
+fn pending_lines_wait_for_what_they_open() {
+    let mut pending = PendingLines::default();
+
+    assert_eq!(pending.push(": twice"), None);
+    assert_eq!(pending.push("  ( x -- y )"), None);
+    assert!(!pending.is_empty());
+    assert_eq!(
+        pending.push("  2 * ;"),
+        Some(vec![
+            ": twice".to_string(),
+            "  ( x -- y )".to_string(),
+            "  2 * ;".to_string()
+        ])
+    );
+    assert!(pending.is_empty());
+
+    // Lines that close nothing are returned at once.
+    assert_eq!(pending.push("1 2 +"), Some(vec!["1 2 +".to_string()]));
+
+    assert_eq!(pending.push("[ 1 ( open"), None);
+    assert_eq!(pending.take(), vec!["[ 1 ( open".to_string()]);
+    assert!(pending.is_empty());
+}
@@ -0,0 +1,138 @@
+//! Tests for saving and loading sessions
+
+mod common;
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter},
+    session::{self, SessionError},
+    value::Value,
+};
+use common::eval;
+
+// Save an interpreter as source and load it again.
+fn reload(interp: &Interpreter) -> Interpreter {
+    let source = session::to_source(interp).unwrap();
+    session::from_source(&source).unwrap_or_else(|err| panic!("{}\n{}", err, source))
+}
+
+// The stack, with floats as their bits so that NaN and
+// the sign of zero are compared exactly.
+fn exact_stack(interp: &Interpreter) -> Vec<String> {
+    interp
+        .stack()
+        .iter()
+        .map(|val| match val {
+            Value::Float32(x) => format!("Float32({:#x})", x.to_bits()),
+            Value::Float64(x) => format!("Float64({:#x})", x.to_bits()),
+            other => format!("{:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn special_floats_round_trip_exactly() {
+    let program =
+        "nan inf -inf -0.0 0.0 nanf inff -inff -0.0f 0.1 0.1f 0.30000000000000004 16777217.0f";
+    let interp = eval(program).unwrap();
+    assert_eq!(exact_stack(&reload(&interp)), exact_stack(&interp));
+}
+
+#[test]
+fn values_round_trip() {
+    let program = "1 -2147483648 true \"a\\nb\" 'sym {1,{2.5f,\"c\"}} #{'a:1,\"b\":{2}} \
+                   [1,2;3,4] 2.5 km/h 120.0 s [ 1 + ] 255u8 {-1i64,12345678901234567890n}";
+    let interp = eval(program).unwrap();
+    let loaded = reload(&interp);
+
+    assert_eq!(exact_stack(&loaded), exact_stack(&interp));
+    assert_eq!(
+        session::to_source(&loaded).unwrap(),
+        session::to_source(&interp).unwrap()
+    );
+}
+
+#[test]
+fn quantities_keep_units_that_are_builtins() {
+    let program = "120.0min 2.5 km/min inf 1min * nan m";
+    let interp = eval(program).unwrap();
+    let source = session::to_source(&interp).unwrap();
+
+    assert!(source.contains("\n120.0min\n"), "{}", source);
+    assert!(source.contains("\ninf 1min *\n"), "{}", source);
+    assert_eq!(exact_stack(&reload(&interp)), exact_stack(&interp));
+}
+
+#[test]
+fn variables_and_words_round_trip() {
+    let interp =
+        eval("3.0f x! nan y! : twice ( x -- y ) 2 * ; :: hyp ( a b -- c ) a a * b b * + sqrt ;")
+            .unwrap();
+    let mut loaded = reload(&interp);
+
+    let vars = loaded
+        .variables()
+        .map(|(name, val)| format!("{} {:?}", name, val))
+        .collect::<Vec<_>>();
+    assert_eq!(vars, vec!["x Float32(3.0)", "y Float64(NaN)"]);
+
+    loaded.eval_line("4 twice 3.0 4.0 hyp").unwrap();
+    let stack = loaded.stack().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(stack, vec![Value::Int32(8), Value::Float64(5.0)]);
+}
+
+#[test]
+fn words_keep_their_latest_definition() {
+    let interp = eval(": a 1 ; : b a 10 + ; : a 2 ;").unwrap();
+    let mut loaded = reload(&interp);
+
+    loaded.eval_line("b").unwrap();
+    assert_eq!(loaded.stack().iter().last(), Some(&Value::Int32(12)));
+}
+
+#[test]
+fn vocabularies_round_trip() {
+    let interp = eval("vocab geo : half_turn 180.0 ; vocab user use geo").unwrap();
+    let mut loaded = reload(&interp);
+
+    assert_eq!(loaded.current_vocabulary(), "user");
+    loaded.eval_line("half_turn geo:half_turn").unwrap();
+    let stack = loaded.stack().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(stack, vec![Value::Float64(180.0), Value::Float64(180.0)]);
+}
+
+#[test]
+fn empty_sessions_round_trip() {
+    let loaded = reload(&Interpreter::new());
+    assert_eq!(loaded.stack().height(), 0);
+    assert_eq!(loaded.variables().count(), 0);
+}
+
+#[test]
+fn sessions_without_the_prelude_stay_without_it() {
+    let mut interp = Interpreter::without_prelude();
+    interp.eval_line(": twice 2 * ; 2.0 x!").unwrap();
+
+    let mut loaded = reload(&interp);
+    assert!(!loaded.has_prelude());
+    assert!(matches!(
+        loaded.eval_line("3.0 sq"),
+        Err(EvalError::UnknownWord(_))
+    ));
+    loaded.eval_line("3 twice").unwrap();
+    assert_eq!(loaded.stack().iter().last(), Some(&Value::Int32(6)));
+
+    // Only the header is read for the marker, not a
+    // comment further down.
+    let source = "\\ cattywampus session\n1\n\\ without prelude\n";
+    assert!(session::from_source(source).unwrap().has_prelude());
+    assert!(reload(&Interpreter::new()).has_prelude());
+}
+
+#[test]
+fn unsaveable_values_are_errors() {
+    let interp = eval(":: adder ( n -- q ) [ n + ] ; 1 adder").unwrap();
+    assert!(matches!(
+        session::to_source(&interp),
+        Err(SessionError::Unsaveable(_))
+    ));
+}
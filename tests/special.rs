@@ -0,0 +1,150 @@
+//! Tests for the special function builtins
+//!
+//! The expected values were computed to 40 digits, and
+//! rounded to the nearest Float64.
+
+mod common;
+
+use cattywampus::{typecheck::TypeError, value::Value};
+use common::{close, error, float, top};
+use std::f64::consts::PI;
+
+fn is_nan(program: &str) -> bool {
+    float(program).is_nan()
+}
+
+#[test]
+fn gamma_at_half_integers() {
+    assert!(close(float("0.5 gamma"), PI.sqrt(), 1e-14));
+    assert!(close(float("4.5 gamma"), 11.63172839656745, 1e-14));
+    assert!(close(float("-1.5 gamma"), 2.363271801207355, 1e-14));
+    assert!(close(float("0.001 gamma"), 999.4237724845955, 1e-14));
+}
+
+#[test]
+fn integer_factorials_are_exact() {
+    let mut product = 1.0;
+
+    for n in 1..=22 {
+        product *= f64::from(n);
+        assert_eq!(float(&format!("{}.0 factorial", n)), product, "{}!", n);
+    }
+
+    assert!(close(float("0.5 factorial"), PI.sqrt() / 2.0, 1e-14));
+}
+
+#[test]
+fn gamma_overflows_only_past_its_limit() {
+    assert!(close(float("171.0 gamma"), 7.257415615307998e306, 2e-13));
+    assert_eq!(float("171.7 gamma"), f64::INFINITY);
+    assert!(close(float("100.5 gamma"), 9.320963104082716e156, 1e-13));
+}
+
+#[test]
+fn gamma_poles_follow_c() {
+    assert_eq!(float("0.0 gamma"), f64::INFINITY);
+    assert_eq!(float("-0.0 gamma"), f64::NEG_INFINITY);
+    assert!(is_nan("-3.0 gamma"));
+    assert_eq!(float("-2.0 lgamma"), f64::INFINITY);
+    assert!(is_nan("0.0 digamma"));
+    assert!(is_nan("-4.0 digamma"));
+}
+
+#[test]
+fn lgamma_stays_finite_past_gamma() {
+    assert!(close(float("1000.0 lgamma"), 5905.220423209181, 1e-14));
+    assert!(close(float("-2.5 lgamma"), -0.056243716497674054, 1e-14));
+    assert!(close(float("1e-10 lgamma"), 23.025850929882736, 1e-14));
+
+    // ln Γ is 0 at 1 and 2, where only the absolute
+    // error is small.
+    assert!(float("1.0 lgamma").abs() < 1e-14);
+    assert!(float("2.0 lgamma").abs() < 1e-14);
+}
+
+#[test]
+fn digamma_steps_by_reciprocals() {
+    assert!(close(float("1.0 digamma"), -0.5772156649015329, 1e-14));
+    assert!(close(float("-0.5 digamma"), 0.03648997397857652, 1e-14));
+
+    // ψ(x + 1) = ψ(x) + 1/x
+    for x in &[0.25, 3.5, 40.0] {
+        let step = float(&format!("{:?} 1.0 + digamma", x)) - float(&format!("{:?} digamma", x));
+        assert!(close(step, 1.0 / x, 1e-13), "{}", x);
+    }
+}
+
+#[test]
+fn beta_is_symmetric_and_survives_large_arguments() {
+    assert!(close(float("2.0 3.0 beta"), 1.0 / 12.0, 1e-14));
+    assert_eq!(float("2.0 3.0 beta"), float("3.0 2.0 beta"));
+    assert!(close(float("0.5 0.5 beta"), PI, 1e-14));
+
+    // Γ(250) overflows, but the quotient does not.
+    assert!(close(
+        float("100.0 150.0 beta"),
+        2.7489030287791437e-74,
+        1e-12
+    ));
+}
+
+#[test]
+fn erf_is_odd_and_erfc_keeps_its_tail() {
+    assert!(close(float("1.0 erf"), 0.8427007929497149, 2e-15));
+    assert_eq!(float("-2.5 erf"), -float("2.5 erf"));
+    assert!(close(float("4.0 erf"), 0.9999999845827421, 2e-15));
+
+    // Where erf rounds to 1, erfc still has digits.
+    assert_eq!(float("10.0 erf"), 1.0);
+    assert!(close(float("10.0 erfc"), 2.088487583762545e-45, 1e-14));
+    assert!(close(float("-1.0 erfc"), 1.842700792949715, 1e-14));
+    assert_eq!(float("30.0 erfc"), 0.0);
+
+    for x in &["0.3", "1.7", "-0.9"] {
+        let sum = float(&format!("{} erf", x)) + float(&format!("{} erfc", x));
+        assert!(close(sum, 1.0, 1e-15), "{}", x);
+    }
+}
+
+#[test]
+fn bessel_functions_near_zeros_and_origin() {
+    assert_eq!(float("0.0 besselj0"), 1.0);
+    assert_eq!(float("0.0 besselj1"), 0.0);
+    assert!(float("2.404825557695773 besselj0").abs() < 1e-15);
+    assert!(close(float("-3.0 besselj1"), -0.3390589585259365, 1e-14));
+
+    assert_eq!(float("0.0 bessely0"), f64::NEG_INFINITY);
+    assert!(close(float("0.1 bessely1"), -6.458951094702027, 1e-14));
+    assert!(is_nan("-1.0 bessely0"));
+    assert!(is_nan("-1.0 bessely1"));
+}
+
+#[test]
+fn bessel_functions_agree_across_the_asymptotic_switch() {
+    // The Wronskian, J₁(x) Y₀(x) - J₀(x) Y₁(x) = 2 / πx,
+    // either side of where the asymptotic expansions
+    // take over.
+    for x in &[1.0, 24.9, 25.0, 25.1, 100.0] {
+        let j0 = float(&format!("{:?} besselj0", x));
+        let j1 = float(&format!("{:?} besselj1", x));
+        let y0 = float(&format!("{:?} bessely0", x));
+        let y1 = float(&format!("{:?} bessely1", x));
+        assert!(close(j1 * y0 - j0 * y1, 2.0 / (PI * x), 1e-12), "{}", x);
+    }
+
+    assert!(close(float("50.0 besselj0"), 0.055812327669251816, 1e-12));
+    assert!(close(float("100.0 bessely1"), -0.020372312002759792, 1e-12));
+}
+
+#[test]
+fn float32_results_are_rounded() {
+    assert_eq!(top("0.5f erf"), Value::Float32(0.5204999));
+    assert_eq!(top("5.0f factorial"), Value::Float32(120.0));
+    assert_eq!(top("1.0f 1.0f beta"), Value::Float32(1.0));
+}
+
+#[test]
+fn integers_are_not_promoted() {
+    assert!(matches!(error("5 gamma"), TypeError::TypeMismatch));
+    assert!(matches!(error("2.0 3.0f beta"), TypeError::TypeMismatch));
+}
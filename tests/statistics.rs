@@ -0,0 +1,185 @@
+//! Tests for the descriptive statistics builtins
+
+mod common;
+
+use cattywampus::{interpreter::Interpreter, typecheck::TypeError, value::Value};
+use common::{close, error, eval, float, stack, top};
+
+#[test]
+fn sample_and_population_spreads() {
+    let data = "{2.0,4.0,4.0,4.0,5.0,5.0,7.0,9.0}";
+    let stat = |word: &str| float(&format!("{} {}", data, word));
+
+    assert_eq!(stat("mean"), 5.0);
+    assert!(close(stat("pvariance"), 4.0, 1e-15));
+    assert!(close(stat("pstddev"), 2.0, 1e-15));
+    assert!(close(stat("variance"), 32.0 / 7.0, 1e-15));
+    assert!(close(stat("stddev"), (32.0f64 / 7.0).sqrt(), 1e-15));
+}
+
+#[test]
+fn one_sample_has_no_sample_variance() {
+    assert!(matches!(error("{3.0} variance"), TypeError::DomainError));
+    assert!(matches!(error("{3.0} stddev"), TypeError::DomainError));
+    assert_eq!(float("{3.0} pvariance"), 0.0);
+    assert_eq!(float("{3.0} median"), 3.0);
+}
+
+#[test]
+fn empty_lists_have_no_statistics() {
+    for word in &["mean", "median", "mode", "pvariance", "min", "range"] {
+        assert!(
+            matches!(error(&format!("{{}} {}", word)), TypeError::DomainError),
+            "{}",
+            word
+        );
+    }
+}
+
+#[test]
+fn empty_sums_and_products_are_zero_and_one() {
+    assert_eq!(top("{} sum"), Value::Int32(0));
+    assert_eq!(top("{} product"), Value::Int32(1));
+}
+
+#[test]
+fn quantiles_interpolate_between_samples() {
+    assert_eq!(float("{4.0,1.0,3.0,2.0} median"), 2.5);
+    assert_eq!(float("{4.0,1.0,3.0,2.0} 0.0 quantile"), 1.0);
+    assert_eq!(float("{4.0,1.0,3.0,2.0} 1.0 quantile"), 4.0);
+    assert_eq!(float("{10.0,20.0} 0.25 quantile"), 12.5);
+
+    assert!(matches!(
+        error("{1.0} 1.5 quantile"),
+        TypeError::DomainError
+    ));
+    assert!(matches!(
+        error("{1.0} -0.1 quantile"),
+        TypeError::DomainError
+    ));
+}
+
+#[test]
+fn mode_prefers_the_smallest_of_a_tie() {
+    assert_eq!(top("{3,1,3,1,2} mode"), Value::Int32(1));
+    assert_eq!(top("{5,4} mode"), Value::Int32(4));
+}
+
+#[test]
+fn extremes_ignore_nan() {
+    assert_eq!(float("{nan,2.0,1.0} min"), 1.0);
+    assert_eq!(float("{2.0,nan} max"), 2.0);
+    assert_eq!(float("{nan,2.0,1.0} range"), 1.0);
+}
+
+#[test]
+fn higher_moments_of_a_skewed_sample() {
+    assert!(close(
+        float("{1.0,2.0,3.0,10.0} skewness"),
+        1.0182337649086284,
+        1e-14
+    ));
+    assert!(close(float("{1.0,2.0,3.0,10.0} kurtosis"), -0.7696, 1e-14));
+    assert_eq!(float("{-1.0,0.0,1.0} skewness"), 0.0);
+}
+
+#[test]
+fn higher_moments_need_a_spread() {
+    for program in &["{2.0,2.0,2.0} skewness", "{5.0} kurtosis", "{3,3} kurtosis"] {
+        assert!(
+            matches!(error(program), TypeError::DomainError),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn min_and_max_take_two_numbers_or_a_list() {
+    assert_eq!(top("3 1 min"), Value::Int32(1));
+    assert_eq!(top("{3,1,2} min"), Value::Int32(1));
+    assert_eq!(top("{3,1,2} max"), Value::Int32(3));
+    assert!(matches!(error("{} max"), TypeError::DomainError));
+    assert!(matches!(error("\"a\" \"b\" max"), TypeError::TypeMismatch));
+    assert!(matches!(error("{1.0} 3.0 max"), TypeError::TypeMismatch));
+
+    let mut interp = eval(": lowest ( l:List -- x ) min ;").unwrap();
+    interp.eval_line("{4,2,8} lowest").unwrap();
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Int32(2)));
+}
+
+#[test]
+fn statistics_keep_their_types() {
+    assert_eq!(top("{3,1,2} sum"), Value::Int32(6));
+    assert_eq!(top("{3,1,2} max"), Value::Int32(3));
+    assert_eq!(top("{3,1,2,4} median"), Value::Float64(2.5));
+    assert_eq!(top("{1.5f,2.5f} mean"), Value::Float32(2.0));
+    assert_eq!(top("{1.5f,2.5f} 0.5 quantile"), Value::Float32(2.0));
+
+    assert!(matches!(error("{1,2.0} sum"), TypeError::TypeMismatch));
+    assert!(matches!(error("{1.0,\"a\"} mean"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn integer_results_must_fit() {
+    assert_eq!(top("{2147483647,1,-1} sum"), Value::Int32(2147483647));
+    assert!(matches!(error("{2147483647,1} sum"), TypeError::Overflow));
+    assert!(matches!(
+        error("{65536,65536} product"),
+        TypeError::Overflow
+    ));
+    assert!(matches!(
+        error("{-2147483648,1} range"),
+        TypeError::Overflow
+    ));
+}
+
+#[test]
+fn sums_and_spreads_are_numerically_stable() {
+    assert_eq!(float("{1e100,1.0,-1e100} sum"), 1.0);
+    assert_eq!(float("{1.0,1e16,1.0,-1e16} sum"), 2.0);
+
+    // Offsetting the data leaves the variance alone,
+    // which a sum of squares would lose.
+    assert!(close(
+        float("{1000000004.0,1000000007.0,1000000013.0,1000000016.0} variance"),
+        30.0,
+        1e-12
+    ));
+}
+
+#[test]
+fn stack_variants_take_everything_beneath_their_arguments() {
+    assert_eq!(stack("1.0 2.0 6.0 stack-mean"), vec![Value::Float64(3.0)]);
+    assert_eq!(stack("3 9 4 stack-max"), vec![Value::Int32(9)]);
+    assert_eq!(stack("3 9 4 stack-min"), vec![Value::Int32(3)]);
+    assert_eq!(
+        stack("10.0 20.0 0.25 stack-quantile"),
+        vec![Value::Float64(12.5)]
+    );
+    assert_eq!(stack("stack-sum"), vec![Value::Int32(0)]);
+}
+
+#[test]
+fn failed_stack_variants_leave_the_stack() {
+    let mut interp = Interpreter::new();
+    assert!(interp.eval_line("1.0 \"a\" 2.0 stack-mean").is_err());
+
+    let stack: Vec<_> = interp.stack().iter().cloned().collect();
+    assert_eq!(
+        stack,
+        vec![
+            Value::Float64(1.0),
+            Value::Str("a".into()),
+            Value::Float64(2.0)
+        ]
+    );
+}
+
+#[test]
+fn stack_variants_can_be_used_in_words() {
+    let mut interp = eval(": average stack-mean ;").unwrap();
+    interp.eval_line("1.0 2.0 6.0 average").unwrap();
+
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Float64(3.0)));
+}
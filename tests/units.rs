@@ -0,0 +1,103 @@
+//! Tests for units of measure and quantities
+
+mod common;
+
+use cattywampus::{typecheck::TypeError, units::Unit, value::Value};
+use common::{error, top};
+
+fn shown(program: &str) -> String {
+    top(program).to_string()
+}
+
+#[test]
+fn prefixes_scale_units() {
+    assert_eq!(shown("1.0 km >m"), "1000 m");
+    assert_eq!(shown("1.0 Ohm >kOhm"), "0.001 kOhm");
+    assert_eq!(shown("2.5f km/h"), "2.5 km/h");
+
+    // Minutes take no prefix, so that ``kmin`` is not
+    // read as a thousand minutes.
+    assert!(Unit::parse("kmin").is_none());
+    assert_eq!(shown("90.0 s >min"), "1.5 min");
+}
+
+#[test]
+fn derived_units_share_dimensions() {
+    let newton = Unit::parse("kg*m/s^2").unwrap();
+    assert_eq!(newton.dimension(), Unit::parse("N").unwrap().dimension());
+    assert_eq!(newton.factor(), 1.0);
+    assert_eq!(shown("1.0 kg*m/s^2 >N"), "1 N");
+    assert_eq!(shown("1.0 km/h >m/s"), "0.2777777777777778 m/s");
+}
+
+#[test]
+fn units_that_cancel_leave_a_number() {
+    assert_eq!(top("1.0 m 2.0 m /"), Value::Float64(0.5));
+    assert_eq!(top("3.0 km 500.0 m /"), Value::Float64(6.0));
+    assert_eq!(shown("2.0 m 3.0 m *"), "6 m^2");
+}
+
+#[test]
+fn sums_convert_to_the_left_unit() {
+    assert_eq!(shown("1.0 m 1.0 cm +"), "1.01 m");
+    assert_eq!(shown("1.0 km 1.0 m -"), "0.999 km");
+}
+
+#[test]
+fn dimensions_must_match() {
+    for program in &["1.0 m 1.0 s +", "1.0 m >s", "1.0 N >J", "1.0 m 1.0 kg -"] {
+        assert!(
+            matches!(error(program), TypeError::DimensionMismatch),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn exponents_must_fit_a_dimension() {
+    assert_eq!(shown("1.0 m^127"), "1 m^127");
+    assert!(matches!(error("1.0 m^127 1.0 m *"), TypeError::Overflow));
+    assert!(matches!(
+        error("1.0 m^-100 1.0 m^100 /"),
+        TypeError::Overflow
+    ));
+    assert!(matches!(error("1.0 m 1000 pow"), TypeError::Overflow));
+
+    // Exponents too large to read are not units at all,
+    // rather than wrapping to some other dimension.
+    assert!(Unit::parse("m^128").is_none());
+    assert!(Unit::parse("m^127*m").is_none());
+}
+
+#[test]
+fn plain_numbers_must_have_one_type() {
+    assert_eq!(top("2147483646 1 +"), Value::Int32(i32::MAX));
+    assert!(matches!(error("2147483647 1 +"), TypeError::Overflow));
+    assert!(matches!(error("-2147483648 1 -"), TypeError::Overflow));
+    assert!(matches!(error("1 2.0 +"), TypeError::TypeMismatch));
+    assert!(matches!(error("1.0 2.0f *"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn unknown_units_are_not_parsed() {
+    for expr in &["furlong", "m^", "m^x", "m//s", "/s"] {
+        assert!(Unit::parse(expr).is_none(), "{}", expr);
+    }
+}
+
+#[test]
+fn units_may_be_attached_to_their_number() {
+    assert_eq!(shown("9.81m/s^2"), "9.81 m/s^2");
+    assert_eq!(shown("-2.5e3m"), "-2500 m");
+    assert_eq!(top("2m/km"), Value::Float64(0.002));
+
+    // An ``e`` without digits begins the unit.
+    assert_eq!(shown("3eV"), "3 eV");
+
+    // This is how minutes are written, since ``min`` on
+    // its own is the builtin.
+    assert_eq!(shown("90min >h"), "1.5 h");
+    assert_eq!(shown("1min 30.0 s min"), "30 s");
+    assert_eq!(top("3 5 min"), Value::Int32(3));
+}
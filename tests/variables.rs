@@ -0,0 +1,87 @@
+//! Tests for named variables
+
+mod common;
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter},
+    typecheck::TypeError,
+    value::Value,
+};
+use common::{error, eval, stack, top};
+
+#[test]
+fn recalling_copies_the_value() {
+    assert_eq!(stack("3 x! x@ x"), vec![Value::Int32(3), Value::Int32(3)]);
+    assert_eq!(top("1 x! 2 x! x"), Value::Int32(2));
+    assert_eq!(stack("#{'a:1} q! q@ 'a get q@"), stack("1 #{'a:1}"));
+}
+
+#[test]
+fn storing_consumes_the_value() {
+    assert!(stack("1.5 x!").is_empty());
+    assert!(matches!(error("x!"), TypeError::WrongArity));
+}
+
+#[test]
+fn unknown_variables_are_errors() {
+    for program in &["x@", "y", "1 x! X"] {
+        assert!(
+            matches!(eval(program), Err(EvalError::UnknownWord(_))),
+            "{}",
+            program
+        );
+    }
+}
+
+#[test]
+fn builtin_names_need_the_explicit_form() {
+    // ``sin`` alone is still the builtin.
+    assert_eq!(top("3 sin! sin@"), Value::Int32(3));
+    assert!(matches!(error("3 sin! sin"), TypeError::WrongArity));
+}
+
+#[test]
+fn units_cannot_be_stored_to() {
+    for program in &["3 m!", "3 kg!", "[ 3 s! ]"] {
+        match eval(program) {
+            Err(EvalError::Syntax(msg)) => assert!(msg.ends_with("as it is a unit"), "{}", msg),
+            other => panic!(
+                "{}: expected a syntax error but got {:?}",
+                program,
+                other.err()
+            ),
+        }
+    }
+
+    // Nothing before the store runs either.
+    let mut interp = Interpreter::new();
+    assert!(interp.eval_line("1 x! 2 m!").is_err());
+    assert!(interp.variables().next().is_none());
+}
+
+#[test]
+fn variables_are_listed_by_name() {
+    let interp = eval("1 b! 2.5 a! 3 b!").unwrap();
+    let vars = interp
+        .variables()
+        .map(|(name, val)| (name.to_string(), val.clone()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vars,
+        vec![
+            ("a".to_string(), Value::Float64(2.5)),
+            ("b".to_string(), Value::Int32(3)),
+        ]
+    );
+}
+
+#[test]
+fn bad_tokens_stop_the_whole_line() {
+    let mut interp = eval("1 x!").unwrap();
+    assert!(matches!(
+        interp.eval_line("2 x! [1,2;3]"),
+        Err(EvalError::BadToken(_))
+    ));
+    assert_eq!(interp.variables().next().unwrap().1, &Value::Int32(1));
+}
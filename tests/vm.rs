@@ -0,0 +1,143 @@
+//! Tests that compiled words, which may skip the
+//! checks on builtins that inference proves safe, give
+//! the same results as checking every builtin
+
+mod common;
+
+use cattywampus::{
+    inference::{self, WordEffect},
+    interpreter::EvalError,
+    parser::{self, ParsedToken},
+    random::Generator,
+    stack::Stack,
+    typecheck::{self, TypeError},
+    value::{Type, Value},
+    words,
+};
+use common::eval;
+
+// Run the inputs and then the body one token at a time,
+// checking every builtin.
+fn checked(inputs: &str, body: &str) -> (Vec<Value>, Option<TypeError>) {
+    let mut stack = Stack::new();
+    let mut generator = Generator::new(0);
+    let source = format!("{} {}", inputs, body);
+
+    for (tok, parsed_tok) in parser::parse_line(&source) {
+        let result = match parsed_tok {
+            ParsedToken::Literal(val) => {
+                stack.push(val);
+                Ok(())
+            }
+            ParsedToken::Intrinsic(fun) => {
+                typecheck::checked_apply(fun, &mut stack, &mut generator)
+            }
+            other => panic!("{}: cannot run {:?}", tok, other),
+        };
+
+        if let Err(err) = result {
+            return (stack.iter().cloned().collect(), Some(err));
+        }
+    }
+
+    (stack.iter().cloned().collect(), None)
+}
+
+// Define the body as a word, and run it on the inputs.
+fn compiled(effect: &str, inputs: &str, body: &str) -> (Vec<Value>, Option<TypeError>) {
+    let mut interp = eval(&format!(": f {} {} ;", effect, body)).unwrap();
+    let result = interp.eval_line(&format!("{} f", inputs));
+    let stack = interp.stack().iter().cloned().collect();
+
+    match result {
+        Ok(()) => (stack, None),
+        Err(EvalError::Type(err, _)) => (stack, Some(err)),
+        Err(err) => panic!("{}: {}", body, err),
+    }
+}
+
+// Compare values by their bits, so that NaN matches
+// itself.
+fn same(x: &[Value], y: &[Value]) -> bool {
+    x.len() == y.len()
+        && x.iter().zip(y).all(|pair| match pair {
+            (Value::Float64(a), Value::Float64(b)) => a.to_bits() == b.to_bits(),
+            (Value::Float32(a), Value::Float32(b)) => a.to_bits() == b.to_bits(),
+            (a, b) => a == b,
+        })
+}
+
+fn assert_same(effect: &str, body: &str, inputs: &str) {
+    let (expected, expected_err) = checked(inputs, body);
+    let (found, found_err) = compiled(effect, inputs, body);
+
+    assert!(
+        same(&found, &expected),
+        "{}: expected {:?} but got {:?}",
+        body,
+        expected,
+        found
+    );
+    assert_eq!(
+        format!("{:?}", found_err),
+        format!("{:?}", expected_err),
+        "{}",
+        body
+    );
+}
+
+#[test]
+fn proven_arithmetic_matches() {
+    assert_same("( x:Float64 -- y )", "2.0 * 1.0 +", "1.5");
+    assert_same("( x:Float32 -- y )", "2.5f * sqrt", "3.0f");
+    assert_same("( x:Int32 -- y )", "3 * 7 -", "12");
+}
+
+#[test]
+fn proven_builtins_still_report_overflow() {
+    assert_same("( x:Int32 -- y )", "1 +", "2147483647");
+    assert_same("( x:Int32 -- y )", "-1 *", "-2147483648");
+    assert_same("( x:Int32 -- y )", "0 /", "5");
+}
+
+#[test]
+fn proven_builtins_give_the_same_special_floats() {
+    assert_same("( x:Float64 -- y )", "sqrt", "-1.0");
+    assert_same("( x:Float64 -- y )", "0.0 /", "1.0");
+    assert_same("( x:Float64 -- y )", "ln", "0.0");
+}
+
+#[test]
+fn typed_inputs_prove_builtins() {
+    let body = words::compile(parser::parse_line("2.0 *")).unwrap();
+    let proven =
+        inference::proven_builtins(&body, &[Type::Float64], &[], |_| WordEffect::Undefined);
+    assert_eq!(proven, vec![false, true]);
+
+    // Nothing is known about values from beneath the
+    // inputs, from variables, or from other words.
+    for source in &["*", "x@ *", "other *"] {
+        let body = words::compile(parser::parse_line(source)).unwrap();
+        let proven =
+            inference::proven_builtins(&body, &[Type::Float64], &[], |_| WordEffect::Unknown);
+        assert_eq!(proven.last(), Some(&false), "{}", source);
+    }
+}
+
+#[test]
+fn untyped_words_are_still_checked() {
+    let mut interp = eval(": f 2.0 * ;").unwrap();
+    assert!(interp.eval_line("\"a\" f").is_err());
+    assert!(interp.eval_line("3.0 f").is_ok());
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Float64(6.0)));
+}
+
+#[test]
+fn words_see_redefinitions_made_after_them() {
+    let mut interp = eval(": other 1.0 ; : f ( x:Float64 -- y ) other + ;").unwrap();
+    interp.eval_line(": other \"a\" ;").unwrap();
+    assert!(matches!(
+        interp.eval_line("2.0 f"),
+        Err(EvalError::Type(TypeError::TypeMismatch, _))
+    ));
+}
@@ -0,0 +1,157 @@
+//! Tests for vocabularies, qualified names, and
+//! including files
+
+mod common;
+
+use cattywampus::{
+    interpreter::{EvalError, Interpreter},
+    value::Value,
+};
+use common::{eval, stack};
+use std::{env, fs, path::PathBuf, process};
+
+// Run a program, giving the warnings it caused.
+fn warnings(program: &str) -> Vec<String> {
+    let mut interp = eval(program).unwrap_or_else(|err| panic!("{}: {}", program, err));
+    interp.take_warnings()
+}
+
+// A directory of its own for each test that writes
+// files.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("cattywampus-{}-{}", name, process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn words_are_found_in_used_vocabularies() {
+    assert_eq!(
+        stack("vocab a : f 1 ; vocab user a:f"),
+        vec![Value::Int32(1)]
+    );
+    assert_eq!(
+        stack("vocab a : f 1 ; vocab user use a f"),
+        vec![Value::Int32(1)]
+    );
+    assert_eq!(
+        stack("vocab math.stats : one 1 ; vocab user math.stats:one"),
+        vec![Value::Int32(1)]
+    );
+
+    assert!(matches!(
+        eval("vocab a : f 1 ; vocab user f"),
+        Err(EvalError::UnknownWord(_))
+    ));
+    assert!(matches!(eval("zz:f"), Err(EvalError::UnknownWord(_))));
+    assert!(matches!(
+        eval("use nosuch"),
+        Err(EvalError::UnknownVocabulary(_))
+    ));
+    assert!(matches!(eval("vocab 1a"), Err(EvalError::Syntax(_))));
+}
+
+#[test]
+fn collisions_warn_about_which_word_wins() {
+    let program = "vocab a : f 1 ; vocab b : f 2 ; vocab user use a use b f";
+    assert_eq!(stack(program).last(), Some(&Value::Int32(2)));
+    assert_eq!(warnings(program), vec!["b:f shadows a:f"]);
+
+    let program = "vocab a : f 1 ; vocab user : f 3 ; use a f";
+    assert_eq!(stack(program).last(), Some(&Value::Int32(3)));
+    assert_eq!(warnings(program), vec!["user:f shadows a:f"]);
+
+    let program = "vocab a : sq 5 ; vocab user use a 2 sq";
+    assert_eq!(stack(program).last(), Some(&Value::Int32(5)));
+    assert_eq!(warnings(program), vec!["a:sq shadows prelude:sq"]);
+}
+
+#[test]
+fn builtins_can_be_shadowed_and_still_named() {
+    let program = ": sin 1 ; 0.0 builtins:sin sin";
+    assert_eq!(stack(program), vec![Value::Float64(0.0), Value::Int32(1)]);
+    assert_eq!(warnings(program), vec!["user:sin shadows builtins:sin"]);
+}
+
+#[test]
+fn variables_do_not_shadow_builtins_in_words() {
+    // At run time, ``sin`` in the word is the builtin,
+    // so the word must be inferred with it too.
+    let program = "3 sin! : f ( x:Float64 -- y:Float64 ) sin ; 0.0 f";
+    assert_eq!(stack(program).last(), Some(&Value::Float64(0.0)));
+
+    assert_eq!(
+        stack("2 x! : recall_x ( -- y ) x ; recall_x").last(),
+        Some(&Value::Int32(2))
+    );
+}
+
+#[test]
+fn files_are_included() {
+    let dir = scratch_dir("include");
+    fs::write(dir.join("lib.cw"), "vocab lib\n: triple 3 * ;\n").unwrap();
+    fs::write(dir.join("main.cw"), "include \"lib.cw\"\nuse lib\n").unwrap();
+
+    let mut interp = Interpreter::new();
+    interp.search_path_mut().push(dir.clone());
+    interp.eval_line("include \"main.cw\"").unwrap();
+
+    // The included file's ``vocab`` and ``use`` last
+    // only until its end.
+    assert_eq!(interp.current_vocabulary(), "user");
+    interp.eval_line("2 lib:triple").unwrap();
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Int32(6)));
+    assert!(matches!(
+        interp.eval_line("2 triple"),
+        Err(EvalError::UnknownWord(_))
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn relative_includes_start_next_to_the_including_file() {
+    let dir = scratch_dir("relative");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub").join("inner.cw"), ": inner 7 ;\n").unwrap();
+    fs::write(dir.join("sub").join("outer.cw"), "include \"inner.cw\"\n").unwrap();
+
+    let mut interp = Interpreter::new();
+    let outer = dir.join("sub").join("outer.cw");
+    interp
+        .eval_line(&format!("include \"{}\" inner", outer.display()))
+        .unwrap();
+    assert_eq!(interp.stack().iter().last(), Some(&Value::Int32(7)));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn bad_includes_are_errors() {
+    let dir = scratch_dir("bad-include");
+    fs::write(dir.join("loop.cw"), "include \"loop.cw\"\n").unwrap();
+    fs::write(dir.join("broken.cw"), "1\n\"a\" +\n").unwrap();
+
+    let mut interp = Interpreter::new();
+    interp.search_path_mut().push(dir.clone());
+
+    assert!(matches!(
+        interp.eval_line("include \"missing.cw\""),
+        Err(EvalError::Include(_, _))
+    ));
+    match interp.eval_line("include \"broken.cw\"") {
+        Err(EvalError::InFile(path, err)) => {
+            assert_eq!(path, "broken.cw");
+            assert_eq!(err.line, 2);
+        }
+        other => panic!("expected an error in the file but got {:?}", other),
+    }
+    match interp.eval_line("include \"loop.cw\"") {
+        Err(EvalError::InFile(_, err)) => {
+            assert!(matches!(err.error, EvalError::Include(_, _)))
+        }
+        other => panic!("expected an error in the file but got {:?}", other),
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
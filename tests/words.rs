@@ -0,0 +1,127 @@
+//! Tests for word definitions, locals, and quotations
+
+mod common;
+
+use cattywampus::{interpreter::EvalError, typecheck::TypeError, value::Value};
+use common::{error, eval, stack, top};
+
+fn syntax_error(program: &str) -> String {
+    match eval(program) {
+        Err(EvalError::Syntax(msg)) => msg,
+        Err(err) => panic!("{}: expected a syntax error but got {}", program, err),
+        Ok(_) => panic!("{}: expected a syntax error", program),
+    }
+}
+
+#[test]
+fn words_run_their_body() {
+    assert_eq!(top(": sq ( x -- y ) x! x@ x@ * ; 7 sq"), Value::Int32(49));
+    assert_eq!(top(": five 5 ; : ten five five + ; ten"), Value::Int32(10));
+
+    // Redefining a word replaces it for later calls.
+    assert_eq!(top(": f 1 ; : f 2 ; f"), Value::Int32(2));
+}
+
+#[test]
+fn locals_bind_the_inputs() {
+    assert_eq!(
+        top(":: hyp ( a b -- c ) a a * b b * + sqrt ; 3.0 4.0 hyp"),
+        Value::Float64(5.0)
+    );
+    assert_eq!(
+        stack(":: swap2 ( a b -- b a ) b a ; 1 2 swap2"),
+        vec![Value::Int32(2), Value::Int32(1)]
+    );
+
+    // Locals are only seen inside their definition.
+    assert!(matches!(
+        eval(":: f ( a -- b ) a ; 1 f a"),
+        Err(EvalError::UnknownWord(_))
+    ));
+}
+
+#[test]
+fn locals_shadow_builtins_and_units() {
+    assert_eq!(top(":: f ( sin -- y ) sin 1 + ; 2 f"), Value::Int32(3));
+    assert_eq!(top(":: f ( m -- y ) m m * ; 3 f"), Value::Int32(9));
+    assert_eq!(top(":: f ( m -- y ) m ; 3 f 2.0 m").to_string(), "2 m");
+}
+
+#[test]
+fn units_cannot_be_defined() {
+    assert_eq!(syntax_error(": g 1 ;"), "cannot define g, as it is a unit");
+    assert_eq!(
+        syntax_error(":: kg ( x -- y ) x ;"),
+        "cannot define kg, as it is a unit"
+    );
+    assert_eq!(syntax_error(": 1 2 ;"), "cannot define 1");
+
+    // The unit is left as it was.
+    assert_eq!(top("2.0 g").to_string(), "2 g");
+}
+
+#[test]
+fn quotations_capture_locals() {
+    assert_eq!(top("[ 1 2 + ] call"), Value::Int32(3));
+    assert_eq!(
+        top(":: adder ( n -- q ) [ n + ] ; 2 5 adder call"),
+        Value::Int32(7)
+    );
+    assert!(matches!(top("[ ]"), Value::Quotation(_)));
+    assert!(matches!(error("1 call"), TypeError::TypeMismatch));
+}
+
+#[test]
+fn effects_are_checked_when_words_run() {
+    assert!(matches!(
+        error(":: f ( a:Float64 -- b ) a ; 1 f"),
+        TypeError::TypeMismatch
+    ));
+    assert!(matches!(
+        error(":: f ( a b -- c ) a ; 1 f"),
+        TypeError::WrongArity
+    ));
+
+    // Results are checked when the body's effect is only
+    // known as it runs.
+    assert!(matches!(
+        error(": f ( -- a b ) later ; : later 1 ; f"),
+        TypeError::BrokenCallee
+    ));
+    assert!(matches!(
+        error(": f ( -- a:Int32 ) later ; : later 1.0 ; f"),
+        TypeError::BrokenCallee
+    ));
+}
+
+#[test]
+fn malformed_definitions_are_syntax_errors() {
+    assert_eq!(syntax_error(": f 1"), "missing ;");
+    assert_eq!(syntax_error("[ 1"), "missing ]");
+    assert_eq!(syntax_error("1 ]"), "unexpected ]");
+    assert_eq!(
+        syntax_error("[ : f 1 ; ]"),
+        "definitions must be at the top level"
+    );
+    assert_eq!(syntax_error(":: f 1 ;"), "f needs a stack effect");
+    assert!(syntax_error(": f ( a:Real -- ) ;").starts_with("unknown type"));
+    assert!(syntax_error(": f ( a b ) ;").contains("--"));
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+    // Test threads have a smaller stack than the main
+    // thread, which the limit on calls is meant for.
+    let nested = std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(|| {
+            assert!(matches!(eval(": f f ; f"), Err(EvalError::TooDeep(_))));
+            assert!(matches!(
+                eval(": f [ f ] call ; f"),
+                Err(EvalError::TooDeep(_))
+            ));
+        })
+        .unwrap();
+
+    nested.join().unwrap();
+}